@@ -0,0 +1,1301 @@
+//! Loop point detection: finding where a track can seamlessly repeat, and
+//! where a fade-out (if any) starts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{AudioData, LoopPoints};
+use crate::error::{AbloopError, Result};
+
+/// How [`detect_loop`] should search for a loop point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum DetectionMode {
+    /// Slide a window across the track and cross-correlate it against the
+    /// track's tail, picking the best-matching start.
+    #[default]
+    CrossCorrelation,
+}
+
+/// How [`detect_loop`] should scale the mono buffer before searching it,
+/// so `correlation_threshold` and `fade_out_threshold_db` behave the same
+/// on a whisper-quiet master as a hot one instead of operating closer to
+/// `f32` precision limits at one end of the range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum NormalizationMode {
+    /// Use the decoded samples as-is.
+    #[default]
+    None,
+    /// Scale so the loudest sample reaches full scale (`1.0`).
+    Peak,
+    /// Scale so the buffer's overall RMS level reaches `1.0`.
+    Rms,
+}
+
+/// Which candidate [`search`] should pick when more than one clears
+/// `correlation_threshold` within [`LOOP_SELECTION_TIE_EPSILON`] of the
+/// best confidence found - otherwise the highest-confidence candidate
+/// always wins regardless of this setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum LoopSelectionPolicy {
+    /// Break ties (and everything else) by raw confidence alone.
+    #[default]
+    HighestConfidence,
+    /// Among near-tied candidates, prefer the longest loop - the one
+    /// starting earliest, since every candidate in a search shares the same
+    /// `end_frame`.
+    Longest,
+    /// Among near-tied candidates, prefer the one starting latest. The
+    /// request this implements asked for "the one ending latest in the
+    /// track", but every candidate a single search produces already shares
+    /// the same `end_frame` (the fade-trimmed track tail) - only
+    /// `start_frame` varies - so the closest real distinction is the
+    /// shortest near-tied loop, the one whose start sits nearest that fixed
+    /// end.
+    LatestStart,
+}
+
+/// Tunable parameters for [`detect_loop`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisSettings {
+    pub mode: DetectionMode,
+    /// Gain-normalize the mono buffer before detection runs.
+    pub normalize: NormalizationMode,
+    /// Candidate loop starts closer to the end of the track than this many
+    /// frames are not considered, so the detected loop is always long
+    /// enough to be useful.
+    pub min_loop_duration_frames: u64,
+    /// Length, in frames, of the window compared between candidate loop
+    /// starts and the track's tail.
+    pub correlation_window_frames: usize,
+    /// Minimum normalized cross-correlation (0.0-1.0) required before a
+    /// candidate is accepted as a real loop point.
+    pub correlation_threshold: f32,
+    /// A drop of this many dB from the track's overall RMS level, sustained
+    /// to the end of the track, is treated as a fade-out.
+    pub fade_out_threshold_db: f32,
+    /// How to break near-ties among candidates that all clear
+    /// `correlation_threshold`.
+    pub loop_selection_policy: LoopSelectionPolicy,
+}
+
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        Self {
+            mode: DetectionMode::default(),
+            normalize: NormalizationMode::default(),
+            min_loop_duration_frames: 44_100 * 2,
+            correlation_window_frames: 4096,
+            correlation_threshold: 0.9,
+            fade_out_threshold_db: -18.0,
+            loop_selection_policy: LoopSelectionPolicy::default(),
+        }
+    }
+}
+
+impl AnalysisSettings {
+    /// Start building an [`AnalysisSettings`], validated on
+    /// [`AnalysisSettingsBuilder::build`] instead of left to fail
+    /// confusingly (or silently) deep inside [`detect_loop`].
+    pub fn builder() -> AnalysisSettingsBuilder {
+        AnalysisSettingsBuilder::default()
+    }
+}
+
+/// Builds a validated [`AnalysisSettings`]. Fields default to
+/// [`AnalysisSettings::default`]'s values; call [`Self::build`] once every
+/// setter you need has been applied.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalysisSettingsBuilder {
+    settings: AnalysisSettings,
+}
+
+impl AnalysisSettingsBuilder {
+    pub fn mode(mut self, mode: DetectionMode) -> Self {
+        self.settings.mode = mode;
+        self
+    }
+
+    pub fn normalize(mut self, normalize: NormalizationMode) -> Self {
+        self.settings.normalize = normalize;
+        self
+    }
+
+    pub fn min_loop_duration_frames(mut self, frames: u64) -> Self {
+        self.settings.min_loop_duration_frames = frames;
+        self
+    }
+
+    pub fn correlation_window_frames(mut self, frames: usize) -> Self {
+        self.settings.correlation_window_frames = frames;
+        self
+    }
+
+    pub fn correlation_threshold(mut self, threshold: f32) -> Self {
+        self.settings.correlation_threshold = threshold;
+        self
+    }
+
+    pub fn fade_out_threshold_db(mut self, db: f32) -> Self {
+        self.settings.fade_out_threshold_db = db;
+        self
+    }
+
+    pub fn loop_selection_policy(mut self, policy: LoopSelectionPolicy) -> Self {
+        self.settings.loop_selection_policy = policy;
+        self
+    }
+
+    /// Validate and produce the [`AnalysisSettings`], or an
+    /// [`AbloopError::InvalidSettings`] describing the first thing wrong.
+    pub fn build(self) -> Result<AnalysisSettings> {
+        let settings = self.settings;
+        if settings.correlation_window_frames == 0 {
+            return Err(AbloopError::InvalidSettings(
+                "correlation_window_frames must be greater than 0".to_string(),
+            ));
+        }
+        if settings.correlation_window_frames as u64 >= settings.min_loop_duration_frames {
+            return Err(AbloopError::InvalidSettings(format!(
+                "correlation_window_frames ({}) must be smaller than min_loop_duration_frames ({})",
+                settings.correlation_window_frames, settings.min_loop_duration_frames
+            )));
+        }
+        if !(0.0..=1.0).contains(&settings.correlation_threshold) {
+            return Err(AbloopError::InvalidSettings(format!(
+                "correlation_threshold ({}) must be between 0.0 and 1.0",
+                settings.correlation_threshold
+            )));
+        }
+        Ok(settings)
+    }
+}
+
+/// A named combination of analysis settings tuned for a particular kind of
+/// source material, so it can be picked all at once instead of setting
+/// `--mode`/`--normalize`/etc. by hand. A user can define their own under
+/// `[presets.<name>]` in the config file alongside these built-ins - see
+/// [`crate::config::Config::resolve_preset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum AnalysisPreset {
+    /// Tight, high-confidence loops typical of game soundtracks: a short
+    /// correlation window and a strict threshold.
+    GameMusic,
+    /// Orchestral recordings with a long decaying tail: a wider window and
+    /// a looser threshold so the reverb tail doesn't mask the match.
+    ClassicalLongTail,
+    /// Dense, loud masters: peak-normalize so the thresholds below aren't
+    /// thrown off by limiting or compression.
+    Electronic,
+    /// Quiet, slowly evolving pads: RMS-normalize and a relaxed fade-out
+    /// threshold so a gentle swell isn't mistaken for a fade.
+    Ambient,
+    /// Short stingers and menu themes (a few seconds, sometimes under 10):
+    /// the other presets' 1-8 second minimum durations and multi-thousand-
+    /// frame correlation window both assume more track than a jingle has to
+    /// search over, so this scales both way down and relaxes the threshold
+    /// slightly to match how little tail there is to correlate against.
+    Jingle,
+}
+
+impl AnalysisPreset {
+    /// A human-readable label, for menus and `--list-presets`-style output.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GameMusic => "Game music",
+            Self::ClassicalLongTail => "Classical with long tail",
+            Self::Electronic => "Electronic",
+            Self::Ambient => "Ambient",
+            Self::Jingle => "Jingle / short stinger",
+        }
+    }
+
+    pub fn values(self) -> AnalysisPresetValues {
+        match self {
+            Self::GameMusic => AnalysisPresetValues {
+                mode: DetectionMode::CrossCorrelation,
+                normalize: NormalizationMode::None,
+                min_loop_duration: 1.0,
+                correlation_window_frames: 2048,
+                correlation_threshold: 0.95,
+                fade_out_threshold_db: -18.0,
+            },
+            Self::ClassicalLongTail => AnalysisPresetValues {
+                mode: DetectionMode::CrossCorrelation,
+                normalize: NormalizationMode::Rms,
+                min_loop_duration: 5.0,
+                correlation_window_frames: 8192,
+                correlation_threshold: 0.8,
+                fade_out_threshold_db: -24.0,
+            },
+            Self::Electronic => AnalysisPresetValues {
+                mode: DetectionMode::CrossCorrelation,
+                normalize: NormalizationMode::Peak,
+                min_loop_duration: 2.0,
+                correlation_window_frames: 4096,
+                correlation_threshold: 0.92,
+                fade_out_threshold_db: -18.0,
+            },
+            Self::Ambient => AnalysisPresetValues {
+                mode: DetectionMode::CrossCorrelation,
+                normalize: NormalizationMode::Rms,
+                min_loop_duration: 8.0,
+                correlation_window_frames: 8192,
+                correlation_threshold: 0.75,
+                fade_out_threshold_db: -30.0,
+            },
+            Self::Jingle => AnalysisPresetValues {
+                mode: DetectionMode::CrossCorrelation,
+                normalize: NormalizationMode::None,
+                min_loop_duration: 0.3,
+                correlation_window_frames: 512,
+                correlation_threshold: 0.85,
+                fade_out_threshold_db: -18.0,
+            },
+        }
+    }
+}
+
+/// The values bundled by an [`AnalysisPreset`] or a user-defined named
+/// preset in the config file - the same fields as the CLI's analysis
+/// flags, so either can build an [`AnalysisSettings`] once a sample rate
+/// is known.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisPresetValues {
+    pub mode: DetectionMode,
+    pub normalize: NormalizationMode,
+    pub min_loop_duration: f64,
+    pub correlation_window_frames: usize,
+    pub correlation_threshold: f32,
+    pub fade_out_threshold_db: f32,
+}
+
+impl Default for AnalysisPresetValues {
+    /// The same values [`AnalysisSettings::default`] builds from, just in
+    /// the unitless/per-second form this type stores them in - the
+    /// `min_loop_duration` config.toml default of `2.0` seconds, since
+    /// there's no sample rate available here to convert a frame count.
+    fn default() -> Self {
+        let settings = AnalysisSettings::default();
+        Self {
+            mode: settings.mode,
+            normalize: settings.normalize,
+            min_loop_duration: 2.0,
+            correlation_window_frames: settings.correlation_window_frames,
+            correlation_threshold: settings.correlation_threshold,
+            fade_out_threshold_db: settings.fade_out_threshold_db,
+        }
+    }
+}
+
+impl AnalysisPresetValues {
+    pub fn into_settings(self, sample_rate: u32) -> Result<AnalysisSettings> {
+        AnalysisSettings::builder()
+            .mode(self.mode)
+            .normalize(self.normalize)
+            .min_loop_duration_frames((self.min_loop_duration * sample_rate as f64) as u64)
+            .correlation_window_frames(self.correlation_window_frames)
+            .correlation_threshold(self.correlation_threshold)
+            .fade_out_threshold_db(self.fade_out_threshold_db)
+            .build()
+    }
+}
+
+/// A detected loop point, with a confidence score in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoopCandidate {
+    pub start_frame: u64,
+    pub end_frame: u64,
+    pub confidence: f32,
+}
+
+/// Where a fade-out begins, if the track's tail trails off instead of
+/// ending (or looping) cleanly.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FadeOut {
+    pub start_frame: u64,
+    pub confidence: f32,
+}
+
+/// Why [`detect_loop`] didn't return a loop point, when it didn't -
+/// surfaced in the CLI's human/JSON output and the GUI's status bar
+/// instead of a bare "no loop found", so a user can tell a legitimately
+/// loop-free jingle from a track that just needs a different threshold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LoopDetectionOutcome {
+    /// A candidate was found and accepted; see [`AnalysisResult::loop_points`].
+    Found,
+    /// The track, after trimming any detected fade-out, is too short for
+    /// `min_loop_duration_frames` plus a correlation window to fit at all.
+    #[default]
+    TooShort,
+    /// The track's tail is at or near digital silence, so cross-correlation
+    /// has nothing distinctive to match candidates against.
+    Silent,
+    /// At least one candidate was scored, but none reached
+    /// `correlation_threshold`.
+    BelowThreshold,
+    /// The search was cancelled (via [`CancellationToken`]) before it
+    /// finished scoring candidates.
+    Cancelled,
+}
+
+/// The outcome of running [`detect_loop`] on a track.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnalysisResult {
+    pub loop_points: Option<LoopCandidate>,
+    pub fade_out: Option<FadeOut>,
+    /// Why `loop_points` is `None`, or [`LoopDetectionOutcome::Found`] when
+    /// it isn't.
+    pub outcome: LoopDetectionOutcome,
+}
+
+/// A single evaluated loop-start candidate, kept around for
+/// [`detect_loop_debug`] diagnostics. Normal detection only needs the best
+/// one, which is why [`AnalysisResult`] doesn't carry this.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DebugCandidate {
+    pub start_frame: u64,
+    pub confidence: f32,
+    /// RMS of the candidate window divided by RMS of the track's tail;
+    /// candidates that correlate well but sit far from 1.0 here match the
+    /// tail's shape at the wrong level, which is worth a second look.
+    pub rms_ratio: f32,
+}
+
+/// The result of [`detect_loop_with_progress`], plus the top candidates
+/// considered along the way (best confidence first). Produced by
+/// [`detect_loop_debug`] for `--debug-analysis` diagnostics.
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugAnalysis {
+    pub result: AnalysisResult,
+    pub top_candidates: Vec<DebugCandidate>,
+    /// The curves behind `result` and `top_candidates`, for the GUI's
+    /// waveform overlay rather than the CLI's ranked candidate list.
+    pub signals: DebugSignals,
+}
+
+/// Analysis-internal curves kept alongside [`DebugAnalysis`] so the GUI's
+/// waveform window can plot why detection landed where it did (or found
+/// nothing at all): the full correlation search in frame order, and the
+/// RMS history [`detect_fade_out`] walked back over.
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugSignals {
+    /// Every candidate the search considered, in frame order and
+    /// untruncated - unlike [`DebugAnalysis::top_candidates`], which is
+    /// sorted by confidence and cut to the top N for the CLI's ranked
+    /// list, this is meant to be plotted as a curve over the waveform.
+    pub correlation_curve: Vec<DebugCandidate>,
+    /// RMS of each `fade_rms_chunk_frames`-sized chunk, oldest first, as
+    /// examined by [`detect_fade_out`]'s walk back from the end of the
+    /// track. Empty if the track was too short for fade-out detection to
+    /// run at all.
+    pub fade_rms_history: Vec<f32>,
+    /// Frame length of each `fade_rms_history` chunk.
+    pub fade_rms_chunk_frames: u64,
+}
+
+/// Detect a loop point (and, incidentally, a fade-out) in `audio`.
+///
+/// Returns an [`AnalysisResult`] with `loop_points: None` when no candidate
+/// clears `settings.correlation_threshold`, rather than guessing.
+pub fn detect_loop(audio: &AudioData, settings: &AnalysisSettings) -> AnalysisResult {
+    detect_loop_with_progress(audio, settings, |_| {})
+}
+
+/// Like [`detect_loop`], but calls `on_progress` with the fraction (`0.0`
+/// to `1.0`) of the search completed so far, for long tracks where the
+/// correlation search itself takes noticeable time.
+pub fn detect_loop_with_progress(
+    audio: &AudioData,
+    settings: &AnalysisSettings,
+    on_progress: impl FnMut(f32),
+) -> AnalysisResult {
+    search(audio, settings, None, on_progress).result
+}
+
+/// Like [`detect_loop`], but also returns the `top_n` best candidates
+/// considered during the search, with their raw confidence and RMS ratio.
+/// Intended for diagnostics (`--debug-analysis`), not normal detection.
+pub fn detect_loop_debug(
+    audio: &AudioData,
+    settings: &AnalysisSettings,
+    top_n: usize,
+) -> DebugAnalysis {
+    let mut search = search(audio, settings, None, |_| {});
+    let correlation_curve = search.candidates.clone();
+    search
+        .candidates
+        .sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+    search.candidates.truncate(top_n);
+    DebugAnalysis {
+        result: search.result,
+        top_candidates: search.candidates,
+        signals: DebugSignals {
+            correlation_curve,
+            fade_rms_history: search.fade_rms_history,
+            fade_rms_chunk_frames: search.fade_rms_chunk_frames,
+        },
+    }
+}
+
+/// Candidates found in both runs within [`MATCH_WINDOW_FRAMES`] of each
+/// other, considered "the same" loop start under different settings.
+const MATCH_WINDOW_FRAMES: u64 = 1024;
+
+/// One candidate matched between two [`DebugAnalysis`] runs on the same
+/// track, for comparing how its confidence moved.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CandidateDelta {
+    pub start_frame_a: u64,
+    pub start_frame_b: u64,
+    pub confidence_a: f32,
+    pub confidence_b: f32,
+}
+
+impl CandidateDelta {
+    /// `confidence_b - confidence_a`; positive means the second run scored
+    /// this candidate higher.
+    pub fn confidence_delta(&self) -> f32 {
+        self.confidence_b - self.confidence_a
+    }
+}
+
+/// The result of comparing two [`DebugAnalysis`] runs on the same track -
+/// e.g. the same file analyzed with two different [`AnalysisSettings`], or
+/// the same settings across two crate versions - produced by
+/// [`diff_analysis`] for tuning diagnostics.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisDiff {
+    pub loop_points_a: Option<LoopCandidate>,
+    pub loop_points_b: Option<LoopCandidate>,
+    /// Candidates found by both runs, matched by start frame.
+    pub matched: Vec<CandidateDelta>,
+    /// Candidates only run `a` found, with no match within `b` inside
+    /// [`MATCH_WINDOW_FRAMES`].
+    pub only_in_a: Vec<DebugCandidate>,
+    /// Candidates only run `b` found.
+    pub only_in_b: Vec<DebugCandidate>,
+}
+
+/// Compare two [`DebugAnalysis`] runs on the same track (e.g. before/after
+/// a settings tweak), pairing up candidates whose start frames are close
+/// enough to be the same loop start so their confidence can be compared
+/// directly. Invaluable when tuning [`AnalysisSettings`].
+pub fn diff_analysis(a: &DebugAnalysis, b: &DebugAnalysis) -> AnalysisDiff {
+    let mut matched = Vec::new();
+    let mut only_in_a = Vec::new();
+    let mut remaining_b = b.top_candidates.clone();
+
+    for candidate_a in &a.top_candidates {
+        let closest = remaining_b
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, candidate_b)| candidate_a.start_frame.abs_diff(candidate_b.start_frame));
+        match closest {
+            Some((index, candidate_b))
+                if candidate_a.start_frame.abs_diff(candidate_b.start_frame) <= MATCH_WINDOW_FRAMES =>
+            {
+                matched.push(CandidateDelta {
+                    start_frame_a: candidate_a.start_frame,
+                    start_frame_b: candidate_b.start_frame,
+                    confidence_a: candidate_a.confidence,
+                    confidence_b: candidate_b.confidence,
+                });
+                remaining_b.remove(index);
+            }
+            _ => only_in_a.push(*candidate_a),
+        }
+    }
+
+    AnalysisDiff {
+        loop_points_a: a.result.loop_points,
+        loop_points_b: b.result.loop_points,
+        matched,
+        only_in_a,
+        only_in_b: remaining_b,
+    }
+}
+
+/// RMS below this (after whatever normalization `settings.normalize`
+/// applied, not necessarily full-scale) is treated as near-silent for
+/// [`LoopDetectionOutcome::Silent`] rather than
+/// [`LoopDetectionOutcome::BelowThreshold`] - there's nothing in a
+/// near-silent tail for cross-correlation to distinguish a real match from
+/// noise, so "below threshold" would be misleading.
+const SILENCE_RMS_THRESHOLD: f32 = 1e-4;
+
+/// Classify why a search that reached the correlation stage still has no
+/// accepted loop point (or why it does), from the same values `search` and
+/// [`ChunkedLoopSearch::finish`] already have on hand once they get that far.
+fn loop_detection_outcome(found: bool, cancelled: bool, tail_rms: f32) -> LoopDetectionOutcome {
+    if found {
+        LoopDetectionOutcome::Found
+    } else if cancelled {
+        LoopDetectionOutcome::Cancelled
+    } else if tail_rms < SILENCE_RMS_THRESHOLD {
+        LoopDetectionOutcome::Silent
+    } else {
+        LoopDetectionOutcome::BelowThreshold
+    }
+}
+
+/// Candidates within this much confidence of the best one found are
+/// considered tied for [`LoopSelectionPolicy`] purposes, rather than only
+/// ever breaking an exact tie.
+const LOOP_SELECTION_TIE_EPSILON: f32 = 0.02;
+
+/// Pick the candidate `search` and [`ChunkedLoopSearch::finish`] should
+/// report, per `policy`. `policy` only matters when more than one
+/// candidate sits within [`LOOP_SELECTION_TIE_EPSILON`] of the best
+/// confidence in `candidates` - otherwise the highest-confidence candidate
+/// wins regardless.
+fn select_best_candidate(candidates: &[DebugCandidate], policy: LoopSelectionPolicy) -> Option<&DebugCandidate> {
+    let best_confidence = candidates.iter().map(|c| c.confidence).fold(f32::MIN, f32::max);
+    let tied = || {
+        candidates
+            .iter()
+            .filter(move |c| best_confidence - c.confidence <= LOOP_SELECTION_TIE_EPSILON)
+    };
+    match policy {
+        LoopSelectionPolicy::HighestConfidence => {
+            candidates.iter().max_by(|a, b| a.confidence.partial_cmp(&b.confidence).unwrap())
+        }
+        LoopSelectionPolicy::Longest => tied().min_by_key(|c| c.start_frame),
+        LoopSelectionPolicy::LatestStart => tied().max_by_key(|c| c.start_frame),
+    }
+}
+
+struct Search {
+    result: AnalysisResult,
+    candidates: Vec<DebugCandidate>,
+    fade_rms_history: Vec<f32>,
+    fade_rms_chunk_frames: u64,
+}
+
+/// The signal work [`search`] and [`ChunkedLoopSearch::start`] both need
+/// before they can do anything loop- or fade-specific: the mono mix,
+/// normalized the same way regardless of which one runs, and the fade-out
+/// RMS walk-back over it. Computed once by [`AudioFeatures::extract`]
+/// instead of each of the two call sites repeating the same
+/// `to_mono`/`normalize_mono`/`detect_fade_out` sequence.
+struct AudioFeatures {
+    mono: Vec<f32>,
+    total_frames: u64,
+    fade_out: Option<FadeOut>,
+    fade_rms_history: Vec<f32>,
+    fade_rms_chunk_frames: u64,
+}
+
+impl AudioFeatures {
+    /// Downmixing and normalizing the whole track to the mono buffer every
+    /// later phase works from, plus the fade-out walk-back over it - the
+    /// "preprocessing" phase timed under the `tracing` feature (see
+    /// [`crate::diagnostics`]); fade-out detection is also timed as its own
+    /// nested span (see [`detect_fade_out`]), so this span's own duration
+    /// includes it rather than excluding it.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "analysis::preprocess", skip_all))]
+    fn extract(audio: &AudioData, settings: &AnalysisSettings) -> Self {
+        let mut mono = to_mono(audio);
+        normalize_mono(&mut mono, settings.normalize);
+        let total_frames = mono.len() as u64;
+        let (fade_out, fade_rms_history, fade_rms_chunk_frames) = detect_fade_out(&mono, settings);
+        AudioFeatures {
+            mono,
+            total_frames,
+            fade_out,
+            fade_rms_history,
+            fade_rms_chunk_frames,
+        }
+    }
+}
+
+/// The strided normalized-cross-correlation sweep over candidate loop
+/// starts, timed as a single `tracing` span under the `tracing` feature
+/// (see [`crate::diagnostics`]) rather than as separate coarse/fine
+/// sub-spans: this search has one pass at a stride bounded by
+/// [`search`]'s comment above it, not an FFT-based coarse pass refined by a
+/// second fine NCC pass, so there's no real coarse/fine boundary in this
+/// implementation to time separately.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "analysis::correlation_search", skip_all))]
+fn search(
+    audio: &AudioData,
+    settings: &AnalysisSettings,
+    cancel: Option<&CancellationToken>,
+    mut on_progress: impl FnMut(f32),
+) -> Search {
+    let AudioFeatures {
+        mono,
+        total_frames,
+        fade_out,
+        fade_rms_history,
+        fade_rms_chunk_frames,
+    } = AudioFeatures::extract(audio, settings);
+    let effective_end = fade_out.map_or(total_frames, |f| f.start_frame);
+
+    let window_len = settings
+        .correlation_window_frames
+        .min(effective_end as usize / 4)
+        .max(1);
+
+    if effective_end < settings.min_loop_duration_frames + window_len as u64 {
+        on_progress(1.0);
+        return Search {
+            result: AnalysisResult {
+                loop_points: None,
+                fade_out,
+                outcome: LoopDetectionOutcome::TooShort,
+            },
+            candidates: Vec::new(),
+            fade_rms_history,
+            fade_rms_chunk_frames,
+        };
+    }
+
+    let tail = &mono[effective_end as usize - window_len..effective_end as usize];
+    let tail_rms = rms(tail);
+    let search_start = settings.min_loop_duration_frames as usize;
+    let search_end = effective_end as usize - window_len;
+
+    // Bound the number of comparisons for very long tracks; a coarser
+    // stride still finds a loop point close enough to be useful.
+    let stride = ((search_end.saturating_sub(search_start)) / 2000).max(1);
+    let search_len = search_end.saturating_sub(search_start).max(1);
+
+    let mut candidates = Vec::new();
+    let mut cancelled = false;
+    for start in (search_start..=search_end).step_by(stride) {
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            cancelled = true;
+            break;
+        }
+        let window = &mono[start..start + window_len];
+        let score = normalized_cross_correlation(window, tail);
+        let rms_ratio = if tail_rms == 0.0 {
+            0.0
+        } else {
+            rms(window) / tail_rms
+        };
+        candidates.push(DebugCandidate {
+            start_frame: start as u64,
+            confidence: score.clamp(0.0, 1.0),
+            rms_ratio,
+        });
+        on_progress((start - search_start) as f32 / search_len as f32);
+    }
+    on_progress(1.0);
+
+    let best = select_best_candidate(&candidates, settings.loop_selection_policy);
+    let loop_points = best.and_then(|candidate| {
+        (candidate.confidence >= settings.correlation_threshold).then_some(LoopCandidate {
+            start_frame: candidate.start_frame,
+            end_frame: effective_end,
+            confidence: candidate.confidence,
+        })
+    });
+    let outcome = loop_detection_outcome(loop_points.is_some(), cancelled, tail_rms);
+
+    Search {
+        result: AnalysisResult {
+            loop_points,
+            fade_out,
+            outcome,
+        },
+        candidates,
+        fade_rms_history,
+        fade_rms_chunk_frames,
+    }
+}
+
+/// One loop search broken into resumable steps, for hosts with no
+/// background-thread option to fall back on - notably `wasm32-unknown-unknown`,
+/// which has neither `std::thread::spawn` (unlike [`run_analysis_async`]'s
+/// native backend) nor, in this crate's dependency set, shared-memory wasm
+/// threads - but that still want to avoid blocking their event loop (the
+/// browser's UI thread) for a long track's whole search. Call [`Self::step`]
+/// repeatedly - once per animation frame, once per Web Worker message,
+/// however the host yields control back to its event loop - until
+/// [`Self::is_finished`].
+pub struct ChunkedLoopSearch {
+    mono: Vec<f32>,
+    fade_out: Option<FadeOut>,
+    window_len: usize,
+    tail: Vec<f32>,
+    tail_rms: f32,
+    correlation_threshold: f32,
+    selection_policy: LoopSelectionPolicy,
+    search_end: usize,
+    stride: usize,
+    next_start: usize,
+    search_len: usize,
+    candidates: Vec<DebugCandidate>,
+    finished: bool,
+    /// Set by [`Self::start`] when the track was too short to search at
+    /// all, so [`Self::finish`] can report
+    /// [`LoopDetectionOutcome::TooShort`] instead of
+    /// [`LoopDetectionOutcome::BelowThreshold`] for a search that never ran.
+    too_short: bool,
+}
+
+impl ChunkedLoopSearch {
+    /// Set up a search over `audio`; nothing expensive runs until
+    /// [`Self::step`] is called. Immediately [`Self::is_finished`], with no
+    /// loop found, if the track is too short to search at all.
+    pub fn start(audio: &AudioData, settings: &AnalysisSettings) -> Self {
+        let AudioFeatures { mono, total_frames, fade_out, .. } = AudioFeatures::extract(audio, settings);
+        let effective_end = fade_out.map_or(total_frames, |f| f.start_frame);
+
+        let window_len = settings
+            .correlation_window_frames
+            .min(effective_end as usize / 4)
+            .max(1);
+
+        if effective_end < settings.min_loop_duration_frames + window_len as u64 {
+            return ChunkedLoopSearch {
+                mono,
+                fade_out,
+                window_len,
+                tail: Vec::new(),
+                tail_rms: 0.0,
+                correlation_threshold: settings.correlation_threshold,
+                selection_policy: settings.loop_selection_policy,
+                search_end: 0,
+                stride: 1,
+                next_start: 0,
+                search_len: 0,
+                candidates: Vec::new(),
+                finished: true,
+                too_short: true,
+            };
+        }
+
+        let tail = mono[effective_end as usize - window_len..effective_end as usize].to_vec();
+        let tail_rms = rms(&tail);
+        let search_start = settings.min_loop_duration_frames as usize;
+        let search_end = effective_end as usize - window_len;
+        let stride = ((search_end.saturating_sub(search_start)) / 2000).max(1);
+        let search_len = search_end.saturating_sub(search_start).max(1);
+
+        ChunkedLoopSearch {
+            mono,
+            fade_out,
+            window_len,
+            tail,
+            tail_rms,
+            correlation_threshold: settings.correlation_threshold,
+            selection_policy: settings.loop_selection_policy,
+            search_end,
+            stride,
+            next_start: search_start,
+            search_len,
+            candidates: Vec::new(),
+            finished: false,
+            too_short: false,
+        }
+    }
+
+    /// Score up to `max_candidates` more stride positions. Returns the
+    /// fraction of the search completed so far (`1.0` once
+    /// [`Self::is_finished`]).
+    pub fn step(&mut self, max_candidates: usize) -> f32 {
+        if self.finished {
+            return 1.0;
+        }
+        for _ in 0..max_candidates.max(1) {
+            if self.next_start > self.search_end {
+                self.finished = true;
+                break;
+            }
+            let window = &self.mono[self.next_start..self.next_start + self.window_len];
+            let score = normalized_cross_correlation(window, &self.tail);
+            let rms_ratio = if self.tail_rms == 0.0 { 0.0 } else { rms(window) / self.tail_rms };
+            self.candidates.push(DebugCandidate {
+                start_frame: self.next_start as u64,
+                confidence: score.clamp(0.0, 1.0),
+                rms_ratio,
+            });
+            self.next_start += self.stride;
+        }
+        if self.finished {
+            1.0
+        } else {
+            let search_start = self.search_end - self.search_len.min(self.search_end);
+            ((self.next_start.saturating_sub(search_start)) as f32 / self.search_len as f32).min(1.0)
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Consume the search, returning the same [`AnalysisResult`] a
+    /// synchronous [`detect_loop`] call would have produced. Only
+    /// meaningful once [`Self::is_finished`]; called early, it just reports
+    /// whatever's been scored so far.
+    pub fn finish(self) -> AnalysisResult {
+        let best = select_best_candidate(&self.candidates, self.selection_policy);
+        let loop_points = best.and_then(|candidate| {
+            let effective_end = self.fade_out.map_or(self.mono.len() as u64, |f| f.start_frame);
+            (candidate.confidence >= self.correlation_threshold).then_some(LoopCandidate {
+                start_frame: candidate.start_frame,
+                end_frame: effective_end,
+                confidence: candidate.confidence,
+            })
+        });
+        let outcome = if self.too_short {
+            LoopDetectionOutcome::TooShort
+        } else {
+            loop_detection_outcome(loop_points.is_some(), false, self.tail_rms)
+        };
+        AnalysisResult { loop_points, fade_out: self.fade_out, outcome }
+    }
+}
+
+/// Average all channels down to a single mono buffer for analysis; the
+/// exported loop points are frame indices, so they apply to any channel
+/// count regardless of this downmix.
+fn to_mono(audio: &AudioData) -> Vec<f32> {
+    audio
+        .frames()
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Scale `mono` in place per `mode`. A no-op on silence, since there's no
+/// sensible gain to apply.
+fn normalize_mono(mono: &mut [f32], mode: NormalizationMode) {
+    let level = match mode {
+        NormalizationMode::None => return,
+        NormalizationMode::Peak => mono.iter().fold(0.0f32, |max, &sample| max.max(sample.abs())),
+        NormalizationMode::Rms => rms(mono),
+    };
+    if level == 0.0 {
+        return;
+    }
+    let gain = 1.0 / level;
+    for sample in mono.iter_mut() {
+        *sample *= gain;
+    }
+}
+
+/// Normalized cross-correlation (Pearson correlation coefficient) between
+/// two equal-length windows, in `-1.0..=1.0`.
+fn normalized_cross_correlation(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(a.len(), b.len());
+
+    let mean_a = a.iter().sum::<f32>() / a.len() as f32;
+    let mean_b = b.iter().sum::<f32>() / b.len() as f32;
+
+    let mut numerator = 0.0f32;
+    let mut denom_a = 0.0f32;
+    let mut denom_b = 0.0f32;
+    for (&x, &y) in a.iter().zip(b) {
+        let da = x - mean_a;
+        let db = y - mean_b;
+        numerator += da * db;
+        denom_a += da * da;
+        denom_b += db * db;
+    }
+
+    let denom = (denom_a * denom_b).sqrt();
+    if denom == 0.0 {
+        0.0
+    } else {
+        numerator / denom
+    }
+}
+
+/// Frame length of each chunk [`detect_fade_out`] measures RMS over.
+const FADE_CHUNK_FRAMES: usize = 2048;
+
+/// Walk back from the end of the track looking for a sustained drop below
+/// `fade_out_threshold_db` relative to the track's overall RMS level.
+/// Alongside the [`FadeOut`] (if any), returns the RMS of every
+/// [`FADE_CHUNK_FRAMES`]-sized chunk in playback order, for
+/// [`DebugSignals::fade_rms_history`] - the walk-back above only needs the
+/// chunks it actually visits, but the diagnostics overlay wants the whole
+/// curve.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "analysis::fade_out", skip_all))]
+fn detect_fade_out(mono: &[f32], settings: &AnalysisSettings) -> (Option<FadeOut>, Vec<f32>, u64) {
+    if mono.len() < FADE_CHUNK_FRAMES * 2 {
+        return (None, Vec::new(), FADE_CHUNK_FRAMES as u64);
+    }
+
+    let chunk_count = mono.len() / FADE_CHUNK_FRAMES;
+    let chunk_rms: Vec<f32> = (0..chunk_count)
+        .map(|chunk_index| rms(&mono[chunk_index * FADE_CHUNK_FRAMES..(chunk_index + 1) * FADE_CHUNK_FRAMES]))
+        .collect();
+
+    let overall_rms = rms(mono);
+    if overall_rms == 0.0 {
+        return (None, chunk_rms, FADE_CHUNK_FRAMES as u64);
+    }
+    let threshold = overall_rms * 10f32.powf(settings.fade_out_threshold_db / 20.0);
+
+    let mut fade_start_chunk = None;
+    for (chunk_index, &chunk_rms) in chunk_rms.iter().enumerate().rev() {
+        if chunk_rms > threshold {
+            break;
+        }
+        fade_start_chunk = Some(chunk_index);
+    }
+
+    // Require at least a few chunks of sustained quiet to call it a
+    // fade-out rather than a brief silence in otherwise loud material.
+    let Some(fade_start_chunk) = fade_start_chunk else {
+        return (None, chunk_rms, FADE_CHUNK_FRAMES as u64);
+    };
+    if chunk_count - fade_start_chunk < 3 {
+        return (None, chunk_rms, FADE_CHUNK_FRAMES as u64);
+    }
+
+    let quiet_fraction = (chunk_count - fade_start_chunk) as f32 / chunk_count as f32;
+    let fade_out = Some(FadeOut {
+        start_frame: (fade_start_chunk * FADE_CHUNK_FRAMES) as u64,
+        confidence: quiet_fraction.clamp(0.0, 1.0),
+    });
+    (fade_out, chunk_rms, FADE_CHUNK_FRAMES as u64)
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Chunk length [`estimate_bpm`]'s novelty curve is computed over - fine
+/// enough to resolve beat periods across the 60-200 BPM range it searches.
+const BPM_CHUNK_FRAMES: usize = 1024;
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+
+/// Frame length of one beat at `bpm`, for aligning a click track
+/// ([`crate::player::mix_metronome`]) or a bars:beats ruler (the GUI's
+/// waveform window) to the same frame-0-starting grid `estimate_bpm`
+/// assumes. `0` for a non-positive `bpm`.
+pub fn beat_period_frames(bpm: f32, sample_rate: u32) -> u64 {
+    if bpm <= 0.0 {
+        return 0;
+    }
+    (60.0 / bpm as f64 * sample_rate as f64) as u64
+}
+
+/// A coarse, simplified BPM estimate for [`crate::player`]'s metronome
+/// overlay: autocorrelate the track's energy novelty curve (how much each
+/// [`BPM_CHUNK_FRAMES`]-sized chunk's RMS rises over the last one) across
+/// the lag range covering 60-200 BPM, and report the tempo whose beat
+/// period best explains the periodicity. This isn't onset detection or
+/// beat tracking - no note segmentation, no harmonic product spectrum
+/// search - just energy-flux autocorrelation, close enough to align a
+/// click track to the track's dominant pulse. `None` for a track too short
+/// to estimate from, or with no measurable periodicity in range at all.
+pub fn estimate_bpm(audio: &AudioData) -> Option<f32> {
+    let mono = to_mono(audio);
+    let chunk_count = mono.len() / BPM_CHUNK_FRAMES;
+    if chunk_count < 8 {
+        return None;
+    }
+    let chunk_rms: Vec<f32> = (0..chunk_count)
+        .map(|i| rms(&mono[i * BPM_CHUNK_FRAMES..(i + 1) * BPM_CHUNK_FRAMES]))
+        .collect();
+    // Positive-only frame-to-frame rise in energy, an onset proxy - beats
+    // show up as energy rising, not falling.
+    let novelty: Vec<f32> = chunk_rms.windows(2).map(|pair| (pair[1] - pair[0]).max(0.0)).collect();
+
+    let chunk_seconds = BPM_CHUNK_FRAMES as f64 / audio.sample_rate as f64;
+    let min_lag = ((60.0 / MAX_BPM) / chunk_seconds).round() as usize;
+    let max_lag = (((60.0 / MIN_BPM) / chunk_seconds).round() as usize).min(novelty.len().saturating_sub(1));
+    if min_lag == 0 || min_lag >= max_lag {
+        return None;
+    }
+
+    let best_lag = (min_lag..=max_lag)
+        .max_by(|&a, &b| autocorrelation(&novelty, a).total_cmp(&autocorrelation(&novelty, b)))?;
+    let beat_period_seconds = best_lag as f64 * chunk_seconds;
+    if beat_period_seconds <= 0.0 {
+        return None;
+    }
+    Some((60.0 / beat_period_seconds) as f32)
+}
+
+/// Unnormalized autocorrelation of `signal` with itself, offset by `lag`
+/// chunks.
+fn autocorrelation(signal: &[f32], lag: usize) -> f32 {
+    signal.iter().zip(signal.iter().skip(lag)).map(|(&a, &b)| a * b).sum()
+}
+
+/// How far [`optimize_loop_points`] is willing to nudge a loop point's
+/// start or end frame while searching for a smoother seam.
+pub const OPTIMIZE_SEARCH_WINDOW_FRAMES: u64 = 256;
+
+/// A loop point nudged by [`optimize_loop_points`], with the seam cost it
+/// settled on - lower means a less audible click where playback wraps from
+/// `end_frame` back to `start_frame`. Not comparable across different
+/// tracks or normalization settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct OptimizedLoopPoints {
+    pub start_frame: u64,
+    pub end_frame: u64,
+    pub seam_cost: f32,
+}
+
+/// Nudge `loop_points`' start and end, independently, within
+/// [`OPTIMIZE_SEARCH_WINDOW_FRAMES`] frames of their current position,
+/// keeping whichever nearby pair minimizes [`seam_cost`].
+///
+/// [`detect_loop`] already picks the start that correlates best against
+/// the tail, but correlation over a whole window doesn't directly minimize
+/// the click at the seam itself; a loop point placed by hand has had no
+/// such check at all. This looks only at the handful of samples either
+/// side of the seam, so it's cheap enough to run after every detection or
+/// on demand against a manually placed point.
+pub fn optimize_loop_points(audio: &AudioData, loop_points: LoopPoints) -> OptimizedLoopPoints {
+    let mono = to_mono(audio);
+    let len = mono.len() as i64;
+    let window = OPTIMIZE_SEARCH_WINDOW_FRAMES as i64;
+
+    let mut best = OptimizedLoopPoints {
+        start_frame: loop_points.start_frame,
+        end_frame: loop_points.end_frame,
+        seam_cost: seam_cost(&mono, loop_points.start_frame, loop_points.end_frame),
+    };
+    for start_delta in -window..=window {
+        let start = loop_points.start_frame as i64 + start_delta;
+        if start < 1 || start >= len {
+            continue;
+        }
+        for end_delta in -window..=window {
+            let end = loop_points.end_frame as i64 + end_delta;
+            if end < start + 2 || end >= len {
+                continue;
+            }
+            let cost = seam_cost(&mono, start as u64, end as u64);
+            if cost < best.seam_cost {
+                best = OptimizedLoopPoints {
+                    start_frame: start as u64,
+                    end_frame: end as u64,
+                    seam_cost: cost,
+                };
+            }
+        }
+    }
+    best
+}
+
+/// The [`seam_cost`] of `loop_points` as given, without searching nearby
+/// for a lower-cost alternative the way [`optimize_loop_points`] does -
+/// what it starts its search from, useful on its own as a quick "how clean
+/// is this seam" score (e.g. for a batch report).
+pub fn seam_cost_at(audio: &AudioData, loop_points: LoopPoints) -> f32 {
+    let mono = to_mono(audio);
+    seam_cost(&mono, loop_points.start_frame, loop_points.end_frame)
+}
+
+/// Estimated audible discontinuity where playback wraps from `end_frame`
+/// back to `start_frame`: the raw amplitude jump across the seam, plus how
+/// differently the waveform is sloping on each side, a cheap proxy for the
+/// high-frequency content a click adds. A real spectral comparison would
+/// reach for an FFT - see [`crate::spectrum`] - but that's only built for
+/// the `playback`-gated visualizer, so this sticks to a dependency-free
+/// approximation usable from core analysis.
+///
+/// `start_frame`/`end_frame` can come straight from GUI widgets that allow
+/// `start_frame` up to `frame_count` and `end_frame` down to `0` (dragging a
+/// marker to the very edge of the track), so this returns `f32::INFINITY`
+/// for a pair that doesn't leave enough room to index the seam instead of
+/// panicking - the worst possible cost, which [`optimize_loop_points`]'s
+/// search naturally avoids.
+fn seam_cost(mono: &[f32], start_frame: u64, end_frame: u64) -> f32 {
+    let start = start_frame as usize;
+    let end = end_frame as usize;
+    if end < 2 || end > mono.len() || start + 1 >= mono.len() {
+        return f32::INFINITY;
+    }
+
+    let amplitude_jump = (mono[end - 1] - mono[start]).abs();
+
+    let slope_into_seam = mono[end - 1] - mono[end - 2];
+    let slope_out_of_seam = mono[start + 1] - mono[start];
+    let slope_jump = (slope_into_seam - slope_out_of_seam).abs();
+
+    amplitude_jump + slope_jump
+}
+
+/// Cooperative cancellation flag for [`run_analysis_async`]. Cloning shares
+/// the same underlying flag, so a handle kept by the caller and the copy
+/// moved into the background thread both see [`cancel`](Self::cancel).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl CancellationToken {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+struct AsyncState {
+    result: std::sync::Mutex<Option<Option<AnalysisResult>>>,
+    waker: std::sync::Mutex<Option<std::task::Waker>>,
+}
+
+/// A loop search running on a background thread. Implements [`Future`], so
+/// an async consumer (a server handler, a WASM event loop) can `.await` it
+/// directly instead of blocking on [`detect_loop`]; [`Self::cancel`] and
+/// [`Self::poll_progress`] work the same whether or not it's being polled
+/// as a future.
+pub struct AnalysisHandle {
+    state: std::sync::Arc<AsyncState>,
+    cancel: CancellationToken,
+    progress: std::sync::mpsc::Receiver<f32>,
+}
+
+impl AnalysisHandle {
+    /// Ask the background search to stop at its next checkpoint. The
+    /// future (or [`run_analysis_blocking`]) still resolves - to `None` -
+    /// once the thread notices and exits, rather than being torn down
+    /// immediately.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Drain the fraction-complete (`0.0..=1.0`) updates sent since the
+    /// last call, without blocking.
+    pub fn poll_progress(&self) -> Vec<f32> {
+        self.progress.try_iter().collect()
+    }
+}
+
+impl std::future::Future for AnalysisHandle {
+    type Output = Option<AnalysisResult>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let mut result = self.state.result.lock().unwrap();
+        if let Some(outcome) = result.take() {
+            std::task::Poll::Ready(outcome)
+        } else {
+            *self.state.waker.lock().unwrap() = Some(cx.waker().clone());
+            std::task::Poll::Pending
+        }
+    }
+}
+
+/// Run [`detect_loop`] on a background thread, returning a handle that can
+/// be polled as a future, cancelled, and queried for progress - so
+/// server-side and WASM callers don't have to manage their own thread
+/// around what would otherwise be a blocking call.
+pub fn run_analysis_async(audio: AudioData, settings: AnalysisSettings) -> AnalysisHandle {
+    let cancel = CancellationToken::new();
+    let state = std::sync::Arc::new(AsyncState {
+        result: std::sync::Mutex::new(None),
+        waker: std::sync::Mutex::new(None),
+    });
+    let (progress_tx, progress_rx) = std::sync::mpsc::channel();
+
+    let worker_cancel = cancel.clone();
+    let worker_state = state.clone();
+    std::thread::spawn(move || {
+        let outcome = search(&audio, &settings, Some(&worker_cancel), |progress| {
+            let _ = progress_tx.send(progress);
+        });
+        let outcome = if worker_cancel.is_cancelled() {
+            None
+        } else {
+            Some(outcome.result)
+        };
+        *worker_state.result.lock().unwrap() = Some(outcome);
+        if let Some(waker) = worker_state.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    });
+
+    AnalysisHandle { state, cancel, progress: progress_rx }
+}
+
+/// Run [`run_analysis_async`] to completion on the current thread, for
+/// callers (the CLI) that want the cancellation/progress API without
+/// pulling in an async executor. Returns `None` if cancelled.
+pub fn run_analysis_blocking(audio: AudioData, settings: AnalysisSettings) -> Option<AnalysisResult> {
+    block_on(run_analysis_async(audio, settings))
+}
+
+/// Minimal single-future executor: park the current thread between
+/// wakeups instead of busy-polling.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    struct ThreadWaker(std::thread::Thread);
+
+    impl std::task::Wake for ThreadWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = std::task::Waker::from(std::sync::Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = std::task::Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            std::task::Poll::Ready(output) => return output,
+            std::task::Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seam_cost_is_finite_for_an_in_range_pair() {
+        let mono = vec![0.0, 0.2, 0.4, 0.6, 0.8, 1.0, 0.8, 0.6];
+        assert!(seam_cost(&mono, 1, 6).is_finite());
+    }
+
+    #[test]
+    fn seam_cost_is_infinite_when_start_is_the_last_frame() {
+        // A GUI loop-editor marker dragged to the end of the track, as
+        // permitted by its own clamp range - must not index out of bounds.
+        let mono = vec![0.0, 0.2, 0.4, 0.6];
+        let last = mono.len() as u64 - 1;
+        assert_eq!(seam_cost(&mono, last, last + 1), f32::INFINITY);
+    }
+
+    #[test]
+    fn seam_cost_is_infinite_when_end_is_zero() {
+        let mono = vec![0.0, 0.2, 0.4, 0.6];
+        assert_eq!(seam_cost(&mono, 0, 0), f32::INFINITY);
+    }
+
+    #[test]
+    fn optimize_loop_points_does_not_panic_on_an_edge_of_track_pair() {
+        let audio = AudioData {
+            samples: vec![0.0f32; 16].into(),
+            sample_rate: 44_100,
+            channels: 1,
+            loop_points: None,
+            replay_gain: None,
+            lyrics: None,
+            visuals: Vec::new(),
+            original_samples: None,
+            original_channels: None,
+            decode_warnings: Default::default(),
+        };
+        let loop_points = LoopPoints { start_frame: 15, end_frame: 16 };
+        optimize_loop_points(&audio, loop_points);
+    }
+}
@@ -0,0 +1,1058 @@
+//! Audio loading: decoding input files into in-memory PCM plus whatever
+//! loop/tag metadata the container already carries.
+
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+#[cfg(not(target_family = "wasm"))]
+use memmap2::Mmap;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use rayon::prelude::*;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{CodecParameters, DecoderOptions};
+use symphonia::core::formats::{FormatOptions, FormatReader, Packet};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, StandardVisualKey, Tag, Visual};
+use symphonia::core::probe::Hint;
+
+use crate::error::{AbloopError, Context, Result};
+use crate::sample_cache::SampleStorage;
+
+/// How many demuxed packets [`PacketSource`] lets the demux thread get ahead
+/// of the decode loop by, on native builds - enough to keep the decoder fed
+/// across a slow read without buffering an unbounded amount of compressed
+/// audio in memory.
+#[cfg(not(target_family = "wasm"))]
+const PACKET_QUEUE_DEPTH: usize = 4;
+
+/// Frames per chunk when downmixing multichannel audio across a worker pool
+/// in [`downmix_to_stereo_parallel`] - large enough that each chunk's work
+/// comfortably outweighs the overhead of handing it to a thread.
+const DOWNMIX_CHUNK_FRAMES: usize = 1 << 16;
+
+/// Feeds packets to the decode loop in `decode`, demuxing on a background
+/// thread (native builds) so the next packet's I/O, a disk or network read,
+/// overlaps with this thread decoding and converting the previous one
+/// instead of the two waiting on each other in lockstep. `wasm32-unknown-unknown`
+/// has no threads to spawn, so the wasm half of this type just demuxes
+/// inline, same as before.
+#[cfg(not(target_family = "wasm"))]
+struct PacketSource {
+    rx: std::sync::mpsc::Receiver<std::result::Result<Packet, String>>,
+    demux_thread: std::thread::JoinHandle<Box<dyn FormatReader>>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl PacketSource {
+    fn spawn(mut format: Box<dyn FormatReader>) -> Self {
+        let (tx, rx) = std::sync::mpsc::sync_channel(PACKET_QUEUE_DEPTH);
+        let demux_thread = std::thread::spawn(move || {
+            loop {
+                match format.next_packet() {
+                    Ok(packet) => {
+                        if tx.send(Ok(packet)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(symphonia::core::errors::Error::IoError(_)) => break,
+                    Err(err) => {
+                        let _ = tx.send(Err(format!("demuxing: {err}")));
+                        break;
+                    }
+                }
+            }
+            format
+        });
+        Self { rx, demux_thread }
+    }
+
+    fn next(&mut self) -> Option<std::result::Result<Packet, String>> {
+        self.rx.recv().ok()
+    }
+
+    /// Reclaim the `FormatReader` (for its metadata) once the decode loop is
+    /// done consuming packets. Surfaces a demux-thread panic as a decode
+    /// error instead of propagating the panic itself.
+    fn into_format(self) -> std::result::Result<Box<dyn FormatReader>, String> {
+        self.demux_thread.join().map_err(|_| "demuxer thread panicked".to_string())
+    }
+}
+
+#[cfg(target_family = "wasm")]
+struct PacketSource {
+    format: Box<dyn FormatReader>,
+}
+
+#[cfg(target_family = "wasm")]
+impl PacketSource {
+    fn spawn(format: Box<dyn FormatReader>) -> Self {
+        Self { format }
+    }
+
+    fn next(&mut self) -> Option<std::result::Result<Packet, String>> {
+        match self.format.next_packet() {
+            Ok(packet) => Some(Ok(packet)),
+            Err(symphonia::core::errors::Error::IoError(_)) => None,
+            Err(err) => Some(Err(format!("demuxing: {err}"))),
+        }
+    }
+
+    fn into_format(self) -> std::result::Result<Box<dyn FormatReader>, String> {
+        Ok(self.format)
+    }
+}
+
+/// A loop region, expressed as frame indices (one frame = one sample per
+/// channel), as found in the source file or set by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopPoints {
+    pub start_frame: u64,
+    pub end_frame: u64,
+}
+
+/// ReplayGain/R128 loudness tags, as found on Vorbis comments, so playback
+/// and export normalization can use them without re-measuring loudness.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain_db: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain_db: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+/// Problems tolerated while decoding, if any packets had to be skipped
+/// rather than aborting the whole load.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeWarnings {
+    pub dropped_packets: u32,
+    pub total_packets: u32,
+}
+
+impl DecodeWarnings {
+    /// Fraction of packets dropped, in `0.0..=1.0`.
+    pub fn dropped_fraction(&self) -> f32 {
+        if self.total_packets == 0 {
+            0.0
+        } else {
+            self.dropped_packets as f32 / self.total_packets as f32
+        }
+    }
+}
+
+/// An embedded image (cover art, liner notes scan, ...), as found in the
+/// source file's metadata.
+#[derive(Debug, Clone)]
+pub struct CoverArt {
+    pub media_type: String,
+    pub data: Vec<u8>,
+    pub is_front_cover: bool,
+}
+
+impl ReplayGain {
+    fn is_empty(&self) -> bool {
+        *self == ReplayGain::default()
+    }
+}
+
+/// Decoded audio plus whatever metadata we were able to recover.
+#[derive(Debug, Clone)]
+pub struct AudioData {
+    /// Interleaved PCM samples, `channels` per frame. Cheaply `Clone`d so
+    /// starting playback or rendering an export never has to copy the
+    /// whole decoded buffer; see [`SampleStorage`] for when this is backed
+    /// by a disk-mapped spill file instead of plain heap memory.
+    pub samples: SampleStorage,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Loop points already present in the file (e.g. a WAV `smpl` chunk),
+    /// if any. `None` means detection still needs to run.
+    pub loop_points: Option<LoopPoints>,
+    /// ReplayGain/R128 tags, if the file carried any.
+    pub replay_gain: Option<ReplayGain>,
+    /// Embedded lyrics (`USLT`/`LYRICS`), if present.
+    pub lyrics: Option<String>,
+    /// All embedded images, in the order the container listed them.
+    pub visuals: Vec<CoverArt>,
+    /// The pre-downmix samples and channel count, kept only when
+    /// [`LoadOptions::keep_original_for_export`] was set and a downmix was
+    /// actually applied.
+    pub original_samples: Option<Vec<f32>>,
+    pub original_channels: Option<u16>,
+    /// Packets skipped rather than aborting the load, if any; see
+    /// [`DecodeWarnings`].
+    pub decode_warnings: DecodeWarnings,
+}
+
+impl AudioData {
+    pub fn frame_count(&self) -> u64 {
+        self.samples.len() as u64 / self.channels as u64
+    }
+
+    /// Iterate over the interleaved samples one frame (one sample per
+    /// channel) at a time, instead of manually striding by `channels`.
+    pub fn frames(&self) -> impl Iterator<Item = &[f32]> {
+        self.samples.chunks_exact(self.channels as usize)
+    }
+
+    /// Iterate over a single channel's samples across the whole clip.
+    ///
+    /// Panics if `channel` is out of range for [`AudioData::channels`].
+    pub fn channel(&self, channel: u16) -> impl Iterator<Item = f32> + '_ {
+        assert!(channel < self.channels, "channel index out of range");
+        self.samples[channel as usize..]
+            .iter()
+            .step_by(self.channels as usize)
+            .copied()
+    }
+
+    /// The image to show as the cover, preferring the one explicitly tagged
+    /// as the front cover over other embedded visuals (back covers, CD
+    /// scans, ...).
+    pub fn front_cover(&self) -> Option<&CoverArt> {
+        self.visuals
+            .iter()
+            .find(|visual| visual.is_front_cover)
+            .or_else(|| self.visuals.first())
+    }
+}
+
+/// How to handle inputs with more than two channels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DownmixMode {
+    /// Keep all channels interleaved as decoded.
+    #[default]
+    Off,
+    /// Downmix to stereo at load time.
+    Stereo,
+}
+
+/// Options controlling how a file is loaded.
+#[derive(Debug, Clone, Copy)]
+pub struct LoadOptions {
+    pub downmix: DownmixMode,
+    /// When downmixing, also keep the original multichannel samples around
+    /// (in [`AudioData::original_samples`]) so export can still use them.
+    pub keep_original_for_export: bool,
+    /// Feed `symphonia` from a memory-mapped view of the file instead of
+    /// reading it through normal file I/O; see [`MappedFile`]. Off by
+    /// default, since it assumes the file isn't modified elsewhere while
+    /// mapped - opt in for batch runs over large files, where it lowers
+    /// peak memory and lets the OS page cache carry repeated loads.
+    #[cfg(not(target_family = "wasm"))]
+    pub mmap: bool,
+    /// RAM budget, in bytes, for the decoded sample buffer before `decode`
+    /// spills it to a memory-mapped temp file instead; see
+    /// [`SampleStorage::new`]. Defaults to
+    /// [`crate::config::Config::sample_cache_budget_mb`], converted to
+    /// bytes, so this stays in effect across every loader without each
+    /// caller having to know the setting exists.
+    pub sample_cache_budget_bytes: u64,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            downmix: DownmixMode::default(),
+            keep_original_for_export: false,
+            #[cfg(not(target_family = "wasm"))]
+            mmap: false,
+            sample_cache_budget_bytes: crate::config::defaults().sample_cache_budget_mb * 1024 * 1024,
+        }
+    }
+}
+
+/// Decode progress, reported periodically while a file is being read.
+/// `total_frames` is `None` when the container doesn't declare its length
+/// up front (e.g. a chained/streamed OGG). `samples_so_far` is the
+/// interleaved PCM decoded up to this point, borrowed only for the
+/// duration of the callback - e.g. for a progressive waveform painted from
+/// partial chunks instead of waiting for the whole file to decode, via
+/// [`crate::waveform::peaks_from_samples`].
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeProgress<'a> {
+    pub frames_decoded: u64,
+    pub total_frames: Option<u64>,
+    pub samples_so_far: &'a [f32],
+    pub channels: u16,
+}
+
+/// Load an audio file from disk, decoding it to interleaved `f32` PCM.
+pub fn load_audio_from_path(path: impl AsRef<Path>) -> Result<AudioData> {
+    load_audio_from_path_with_progress(path, LoadOptions::default(), |_| {})
+}
+
+/// Like [`load_audio_from_path`], but calls `on_progress` after each decoded
+/// packet so callers (e.g. the GUI) can drive a progress bar for large
+/// files.
+pub fn load_audio_from_path_with_progress(
+    path: impl AsRef<Path>,
+    options: LoadOptions,
+    on_progress: impl FnMut(DecodeProgress<'_>),
+) -> Result<AudioData> {
+    let path = path.as_ref();
+    let ext = path.extension().and_then(|e| e.to_str()).map(String::from);
+    let is_wav = ext
+        .as_deref()
+        .map(|e| e.eq_ignore_ascii_case("wav"))
+        .unwrap_or(false);
+
+    #[cfg(not(target_family = "wasm"))]
+    let source: Box<dyn MediaSource> = if options.mmap {
+        Box::new(MappedFile::open(path).context(|| format!("memory-mapping {}", path.display()))?)
+    } else {
+        let file = File::open(path).context(|| format!("opening {}", path.display()))?;
+        Box::new(file)
+    };
+    #[cfg(target_family = "wasm")]
+    let source: Box<dyn MediaSource> = {
+        let file = File::open(path).context(|| format!("opening {}", path.display()))?;
+        Box::new(file)
+    };
+
+    let mut data = decode(source, ext.as_deref(), options, on_progress)?;
+    if is_wav {
+        let file = File::open(path).context(|| format!("opening {}", path.display()))?;
+        data.loop_points = read_wav_smpl_loop(file).unwrap_or(None);
+    }
+    Ok(data)
+}
+
+/// A `symphonia` [`MediaSource`] backed by a memory-mapped file instead of
+/// read syscalls, so the OS page cache - not this process - carries the
+/// cost of serving the bytes symphonia asks for. See [`LoadOptions::mmap`].
+#[cfg(not(target_family = "wasm"))]
+struct MappedFile {
+    cursor: Cursor<Mmap>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl MappedFile {
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: relies on the file not being truncated or rewritten
+        // elsewhere for as long as this map is alive, the same caveat
+        // every `mmap`-backed reader carries.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self { cursor: Cursor::new(mmap) })
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Read for MappedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.cursor.read(buf)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Seek for MappedFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl MediaSource for MappedFile {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.cursor.get_ref().len() as u64)
+    }
+}
+
+/// Load audio from an in-memory byte buffer (e.g. fetched from a URL),
+/// decoding it to interleaved `f32` PCM.
+///
+/// `ext_hint` is an optional filename extension (without the dot) used to
+/// help the format probe, since bytes fetched from a URL rarely carry one.
+pub fn load_audio_from_bytes(bytes: Vec<u8>, ext_hint: Option<&str>) -> Result<AudioData> {
+    let is_wav = ext_hint
+        .map(|e| e.eq_ignore_ascii_case("wav"))
+        .unwrap_or(false);
+
+    let mut data = decode(
+        Box::new(Cursor::new(bytes.clone())),
+        ext_hint,
+        LoadOptions::default(),
+        |_| {},
+    )?;
+    if is_wav {
+        data.loop_points = read_wav_smpl_loop(Cursor::new(bytes)).unwrap_or(None);
+    }
+    Ok(data)
+}
+
+/// Sample encoding of a headerless PCM stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmFormat {
+    U8,
+    S16Le,
+    S24Le,
+    S32Le,
+    F32Le,
+}
+
+impl PcmFormat {
+    fn bytes_per_sample(self) -> usize {
+        match self {
+            PcmFormat::U8 => 1,
+            PcmFormat::S16Le => 2,
+            PcmFormat::S24Le => 3,
+            PcmFormat::S32Le | PcmFormat::F32Le => 4,
+        }
+    }
+}
+
+/// Decode a headerless raw PCM buffer (as extracted from game assets that
+/// ship audio without a container) into [`AudioData`].
+pub fn load_raw_pcm(
+    bytes: &[u8],
+    sample_rate: u32,
+    channels: u16,
+    format: PcmFormat,
+) -> Result<AudioData> {
+    if channels == 0 {
+        return Err(AbloopError::DecodeFailed("raw PCM channel count must be non-zero".to_string()));
+    }
+    if sample_rate == 0 {
+        return Err(AbloopError::DecodeFailed("raw PCM sample rate must be non-zero".to_string()));
+    }
+
+    let bytes_per_sample = format.bytes_per_sample();
+    if !bytes.len().is_multiple_of(bytes_per_sample) {
+        return Err(AbloopError::DecodeFailed(
+            "raw PCM buffer length is not a multiple of the sample size".to_string(),
+        ));
+    }
+    let sample_count = bytes.len() / bytes_per_sample;
+    if !sample_count.is_multiple_of(channels as usize) {
+        return Err(AbloopError::DecodeFailed(
+            "raw PCM sample count is not a whole number of frames for the given channel count".to_string(),
+        ));
+    }
+
+    let samples = bytes
+        .chunks_exact(bytes_per_sample)
+        .map(|chunk| match format {
+            PcmFormat::U8 => (chunk[0] as f32 - 128.0) / 128.0,
+            PcmFormat::S16Le => {
+                i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / i16::MAX as f32
+            }
+            PcmFormat::S24Le => {
+                let raw = i32::from_le_bytes([chunk[0], chunk[1], chunk[2], 0]) << 8 >> 8;
+                raw as f32 / 8_388_608.0
+            }
+            PcmFormat::S32Le => {
+                i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f32
+                    / i32::MAX as f32
+            }
+            PcmFormat::F32Le => f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+        })
+        .collect::<Vec<f32>>();
+
+    Ok(AudioData {
+        samples: samples.into(),
+        sample_rate,
+        channels,
+        loop_points: None,
+        replay_gain: None,
+        lyrics: None,
+        visuals: Vec::new(),
+        original_samples: None,
+        original_channels: None,
+        decode_warnings: DecodeWarnings::default(),
+    })
+}
+
+/// Render a tracker module (`.mod`/`.xm`/`.it`/`.s3m`, among others) to PCM
+/// via `libopenmpt`, so it can be analyzed and looped like any other input.
+///
+/// Requires the `tracker` feature.
+#[cfg(feature = "tracker")]
+pub fn load_tracker_module(bytes: &[u8]) -> Result<AudioData> {
+    use openmpt::module::{Logger, Module};
+
+    const SAMPLE_RATE: u32 = 48_000;
+    const CHUNK_FRAMES: usize = 4096;
+
+    let mut buffer = bytes.to_vec();
+    let mut module = Module::create_from_memory(&mut buffer, Logger::None, &[])
+        .map_err(|_| AbloopError::DecodeFailed("libopenmpt failed to load the tracker module".to_string()))?;
+
+    let mut samples = Vec::new();
+    loop {
+        let mut chunk = vec![0.0f32; CHUNK_FRAMES * 2];
+        let frames_rendered = module.read_interleaved_float_stereo(SAMPLE_RATE as i32, &mut chunk);
+        if frames_rendered == 0 {
+            break;
+        }
+        chunk.truncate(frames_rendered * 2);
+        samples.extend_from_slice(&chunk);
+    }
+
+    Ok(AudioData {
+        samples: samples.into(),
+        sample_rate: SAMPLE_RATE,
+        channels: 2,
+        loop_points: None,
+        replay_gain: None,
+        lyrics: None,
+        visuals: Vec::new(),
+        original_samples: None,
+        original_channels: None,
+        decode_warnings: DecodeWarnings::default(),
+    })
+}
+
+/// Fetch a URL and decode it as audio (native builds only; see
+/// [`crate::wasm_api::analyze_url`] for the browser `fetch` equivalent).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_audio_from_url(url: &str) -> Result<AudioData> {
+    let response = ureq::get(url).call().context(|| format!("fetching {url}"))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .context(|| "reading response body".to_string())?;
+
+    let ext_hint = url.rsplit('.').next().filter(|s| !s.contains('/'));
+    load_audio_from_bytes(bytes, ext_hint)
+}
+
+/// Container/codec, duration, and tag summary for a file, gathered without
+/// decoding any samples - for listing many files quickly (a batch export's
+/// queue, `analyze --probe-only`) where fully decoding every one up front
+/// would be too slow.
+#[derive(Debug, Clone)]
+pub struct FormatInfo {
+    /// Short codec name as symphonia's registry knows it (e.g. `"pcm_s16le"`,
+    /// `"vorbis"`).
+    pub codec: &'static str,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// `None` when the container doesn't declare its length up front (e.g. a
+    /// chained/streamed OGG).
+    pub duration_secs: Option<f64>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+/// Probe `path` for [`FormatInfo`] without decoding any samples.
+pub fn probe(path: impl AsRef<Path>) -> Result<FormatInfo> {
+    let path = path.as_ref();
+    let file = File::open(path).context(|| format!("opening {}", path.display()))?;
+    let ext = path.extension().and_then(|e| e.to_str());
+
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = ext {
+        hint.with_extension(ext);
+    }
+
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &gapless_format_options(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|err| AbloopError::UnsupportedFormat(format!("unrecognized audio format: {err}")))?;
+
+    let track = probed
+        .format
+        .default_track()
+        .ok_or_else(|| AbloopError::DecodeFailed("no default audio track".to_string()))?;
+    let codec = symphonia::default::get_codecs()
+        .get_codec(track.codec_params.codec)
+        .map(|descriptor| descriptor.short_name)
+        .unwrap_or("unknown");
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| AbloopError::DecodeFailed("missing sample rate".to_string()))?;
+    let channels = track
+        .codec_params
+        .channels
+        .ok_or_else(|| AbloopError::DecodeFailed("missing channel layout".to_string()))?
+        .count() as u16;
+    let duration_secs = track
+        .codec_params
+        .n_frames
+        .map(|frames| frames as f64 / sample_rate as f64);
+
+    let tags = probed
+        .format
+        .metadata()
+        .current()
+        .map(|rev| rev.tags().to_vec())
+        .unwrap_or_default();
+    let title = tag_value(&tags, StandardTagKey::TrackTitle);
+    let artist = tag_value(&tags, StandardTagKey::Artist);
+
+    Ok(FormatInfo {
+        codec,
+        sample_rate,
+        channels,
+        duration_secs,
+        title,
+        artist,
+    })
+}
+
+/// First tag with the given [`StandardTagKey`], if any.
+fn tag_value(tags: &[Tag], key: StandardTagKey) -> Option<String> {
+    tags.iter()
+        .find(|tag| tag.std_key == Some(key))
+        .map(|tag| tag.value.to_string())
+}
+
+/// Above this fraction of dropped packets, a file is treated as too
+/// damaged to analyze reliably instead of returning a gappy decode.
+const MAX_DROPPED_PACKET_FRACTION: f32 = 0.05;
+
+/// `FormatOptions` asking symphonia to report durations net of encoder
+/// delay/padding where it knows about them (currently just MP3's
+/// Xing/LAME header); see [`gapless_trim_frames`] for the part symphonia
+/// doesn't do for us - actually trimming the decoded samples.
+fn gapless_format_options() -> FormatOptions {
+    FormatOptions {
+        enable_gapless: true,
+        ..Default::default()
+    }
+}
+
+fn decode(
+    source: Box<dyn MediaSource>,
+    ext_hint: Option<&str>,
+    options: LoadOptions,
+    mut on_progress: impl FnMut(DecodeProgress<'_>),
+) -> Result<AudioData> {
+    let mss = MediaSourceStream::new(source, Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = ext_hint {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &gapless_format_options(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|err| AbloopError::UnsupportedFormat(format!("unrecognized audio format: {err}")))?;
+
+    let track = probed
+        .format
+        .default_track()
+        .ok_or_else(|| AbloopError::DecodeFailed("no default audio track".to_string()))?
+        .clone();
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|err| AbloopError::UnsupportedFormat(format!("unsupported codec: {err}")))?;
+
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| AbloopError::DecodeFailed("missing sample rate".to_string()))?;
+    let channels = track
+        .codec_params
+        .channels
+        .ok_or_else(|| AbloopError::DecodeFailed("missing channel layout".to_string()))?
+        .count() as u16;
+    let total_frames = track.codec_params.n_frames;
+
+    // Decoding itself has to stay sequential - most codecs (e.g. MP3's bit
+    // reservoir) carry state across packets that only a strictly in-order
+    // decode preserves correctly - but demuxing the next packet doesn't
+    // depend on decoding the current one, so PacketSource runs it ahead on
+    // its own thread and the two overlap instead of serializing.
+    let mut packets = PacketSource::spawn(probed.format);
+
+    let mut samples = Vec::new();
+    let mut total_packets = 0u32;
+    let mut dropped_packets = 0u32;
+    while let Some(message) = packets.next() {
+        let packet = message.map_err(AbloopError::DecodeFailed)?;
+        total_packets += 1;
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A single malformed packet isn't necessarily fatal; skip it and
+            // keep going, but tally it so a badly damaged file is still
+            // rejected below instead of silently returning a gappy decode.
+            Err(symphonia::core::errors::Error::DecodeError(err)) => {
+                log::warn!("dropped a packet while decoding: {err}");
+                dropped_packets += 1;
+                continue;
+            }
+            Err(err) => return Err(AbloopError::DecodeFailed(format!("decoding packet: {err}"))),
+        };
+        let spec = *decoded.spec();
+        if spec.rate != sample_rate || spec.channels.count() as u16 != channels {
+            return Err(AbloopError::DecodeFailed(format!(
+                "stream changes sample rate or channel layout mid-file ({} Hz/{}ch -> {} Hz/{}ch); \
+                 chained or parameter-changing streams are not supported, re-encode as a single \
+                 constant-parameter stream",
+                sample_rate,
+                channels,
+                spec.rate,
+                spec.channels.count()
+            )));
+        }
+        let mut buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buf.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buf.samples());
+
+        on_progress(DecodeProgress {
+            frames_decoded: samples.len() as u64 / channels as u64,
+            total_frames,
+            samples_so_far: &samples,
+            channels,
+        });
+    }
+
+    let decode_warnings = DecodeWarnings {
+        dropped_packets,
+        total_packets,
+    };
+    if decode_warnings.dropped_fraction() > MAX_DROPPED_PACKET_FRACTION {
+        return Err(AbloopError::DecodeFailed(format!(
+            "dropped {} of {} packets ({:.1}%) while decoding; the file looks too damaged to \
+             analyze reliably",
+            dropped_packets,
+            total_packets,
+            decode_warnings.dropped_fraction() * 100.0
+        )));
+    }
+
+    let mut format = packets.into_format().map_err(AbloopError::DecodeFailed)?;
+    let (tags, visuals) = format
+        .metadata()
+        .current()
+        .map(|rev| (rev.tags().to_vec(), visuals_from_metadata(rev.visuals())))
+        .unwrap_or_default();
+    let loop_points = loop_points_from_tags(&tags);
+    let replay_gain = replay_gain_from_tags(&tags).filter(|rg| !rg.is_empty());
+    let lyrics = lyrics_from_tags(&tags);
+
+    let (delay_frames, padding_frames) = gapless_trim_frames(&track.codec_params, &tags);
+    let samples = trim_gapless(samples, channels, delay_frames, padding_frames);
+
+    let (samples, channels, original_samples, original_channels) =
+        if options.downmix == DownmixMode::Stereo && channels > 2 {
+            let stereo = downmix_to_stereo_parallel(&samples, channels);
+            if options.keep_original_for_export {
+                (stereo, 2, Some(samples), Some(channels))
+            } else {
+                (stereo, 2, None, None)
+            }
+        } else {
+            (samples, channels, None, None)
+        };
+
+    Ok(AudioData {
+        samples: SampleStorage::new(samples, options.sample_cache_budget_bytes)?,
+        sample_rate,
+        channels,
+        loop_points,
+        replay_gain,
+        lyrics,
+        visuals,
+        original_samples,
+        original_channels,
+        decode_warnings,
+    })
+}
+
+/// Downmix a long interleaved buffer to stereo across a small worker pool
+/// instead of one thread: each frame converts independently of its
+/// neighbors, so [`DOWNMIX_CHUNK_FRAMES`]-sized chunks can run in parallel
+/// and `par_chunks`/`flat_map_iter` reassemble the results back in order.
+fn downmix_to_stereo_parallel(samples: &[f32], channels: u16) -> Vec<f32> {
+    let chunk_samples = DOWNMIX_CHUNK_FRAMES * channels as usize;
+    samples.par_chunks(chunk_samples.max(channels as usize)).flat_map_iter(|chunk| downmix_to_stereo(chunk, channels)).collect()
+}
+
+/// Downmix interleaved multichannel PCM to stereo.
+///
+/// 5.1 (the common case for game/film audio) uses the ITU-R BS.775 style
+/// coefficients for center and surrounds; any other channel count falls
+/// back to folding the extra channels evenly into left/right.
+fn downmix_to_stereo(samples: &[f32], channels: u16) -> Vec<f32> {
+    const CENTER_MIX: f32 = std::f32::consts::FRAC_1_SQRT_2;
+    const SURROUND_MIX: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+    let channels = channels as usize;
+    let frame_count = samples.len() / channels;
+    let mut out = Vec::with_capacity(frame_count * 2);
+
+    for frame in samples.chunks_exact(channels) {
+        let (l, r) = if channels == 6 {
+            // WAV/SMPTE order: FL, FR, FC, LFE, BL, BR.
+            let (fl, fr, fc, _lfe, bl, br) = (frame[0], frame[1], frame[2], frame[3], frame[4], frame[5]);
+            (
+                fl + CENTER_MIX * fc + SURROUND_MIX * bl,
+                fr + CENTER_MIX * fc + SURROUND_MIX * br,
+            )
+        } else {
+            // Generic fallback: fold even channels into L, odd into R.
+            let mut l = 0.0;
+            let mut r = 0.0;
+            for (i, &sample) in frame.iter().enumerate() {
+                if i % 2 == 0 {
+                    l += sample;
+                } else {
+                    r += sample;
+                }
+            }
+            (l, r)
+        };
+        out.push(l);
+        out.push(r);
+    }
+
+    out
+}
+
+/// Scan a WAV file's RIFF chunks for a `smpl` chunk and return its first
+/// loop, if present. Returns `Ok(None)` for files with no `smpl` chunk.
+fn read_wav_smpl_loop<R: Read + Seek>(mut reader: R) -> Result<Option<LoopPoints>> {
+    let mut riff_header = [0u8; 12];
+    reader.read_exact(&mut riff_header)?;
+    if &riff_header[0..4] != b"RIFF" || &riff_header[8..12] != b"WAVE" {
+        return Err(AbloopError::UnsupportedFormat("not a RIFF/WAVE file".to_string()));
+    }
+
+    loop {
+        let mut chunk_id = [0u8; 4];
+        if reader.read_exact(&mut chunk_id).is_err() {
+            return Ok(None);
+        }
+        let chunk_size = reader.read_u32::<LittleEndian>()?;
+
+        if &chunk_id == b"smpl" {
+            // struct smpl_chunk { u32 manufacturer, product, sample_period,
+            // midi_unity_note, midi_pitch_fraction, smpte_format,
+            // smpte_offset, num_sample_loops, sampler_data; loop[...] }
+            reader.seek(SeekFrom::Current(28))?;
+            let num_loops = reader.read_u32::<LittleEndian>()?;
+            reader.seek(SeekFrom::Current(4))?; // sampler_data size
+
+            if num_loops == 0 {
+                return Ok(None);
+            }
+
+            // First loop: cue_point_id, type, start, end, fraction, play_count.
+            reader.seek(SeekFrom::Current(8))?;
+            let start = reader.read_u32::<LittleEndian>()? as u64;
+            let end = reader.read_u32::<LittleEndian>()? as u64;
+            return Ok(Some(LoopPoints {
+                start_frame: start,
+                end_frame: end,
+            }));
+        }
+
+        // `chunk_size` comes straight off disk; a hostile or corrupt file can
+        // set it to `u32::MAX`, which would overflow the padding add below.
+        let Some(padded_size) = chunk_size.checked_add(chunk_size & 1) else {
+            return Ok(None);
+        };
+        reader.seek(SeekFrom::Current(padded_size as i64))?;
+    }
+}
+
+/// The number of frames an encoder inserted at the start (`delay`, aka
+/// "priming samples") and end (`padding`) of a lossy stream to make it
+/// divide evenly into whole encoder frames - silence that isn't part of
+/// the original audio and would otherwise throw off every detected
+/// sample position. `codec_params` already has this for MP3 (symphonia
+/// parses the Xing/LAME header itself); for AAC-in-MP4, it's only
+/// available as the iTunes `iTunSMPB` tag, which we have to parse
+/// ourselves.
+fn gapless_trim_frames(codec_params: &CodecParameters, tags: &[Tag]) -> (u64, u64) {
+    if codec_params.delay.is_some() || codec_params.padding.is_some() {
+        return (
+            codec_params.delay.unwrap_or(0) as u64,
+            codec_params.padding.unwrap_or(0) as u64,
+        );
+    }
+    itunsmpb_trim(tags).unwrap_or((0, 0))
+}
+
+/// Parse an iTunes `iTunSMPB` freeform tag: a run of space-separated hex
+/// fields, `reserved delay padding original_sample_count ...`. Only the
+/// delay and padding fields are used here.
+fn itunsmpb_trim(tags: &[Tag]) -> Option<(u64, u64)> {
+    let tag = tags.iter().find(|tag| tag.key.to_ascii_lowercase().ends_with("itunsmpb"))?;
+    let value = tag.value.to_string();
+    let mut fields = value.split_whitespace();
+    fields.next()?; // reserved
+    let delay = u64::from_str_radix(fields.next()?, 16).ok()?;
+    let padding = u64::from_str_radix(fields.next()?, 16).ok()?;
+    Some((delay, padding))
+}
+
+/// Drop `delay_frames` from the start and `padding_frames` from the end of
+/// `samples`, clamped so a malformed or wildly wrong tag can't underflow
+/// past an empty buffer. See [`gapless_trim_frames`].
+fn trim_gapless(samples: Vec<f32>, channels: u16, delay_frames: u64, padding_frames: u64) -> Vec<f32> {
+    if delay_frames == 0 && padding_frames == 0 {
+        return samples;
+    }
+    let channels = (channels as usize).max(1);
+    let frame_count = samples.len() / channels;
+    let delay_frames = (delay_frames as usize).min(frame_count);
+    let padding_frames = (padding_frames as usize).min(frame_count - delay_frames);
+    let start = delay_frames * channels;
+    let end = samples.len() - padding_frames * channels;
+    samples[start..end].to_vec()
+}
+
+/// Look for RPG Maker/Godot style `LOOPSTART`/`LOOPLENGTH` Vorbis comments
+/// (as found in OGG inputs) and turn them into a [`LoopPoints`].
+fn loop_points_from_tags(tags: &[Tag]) -> Option<LoopPoints> {
+    let mut loop_start = None;
+    let mut loop_length = None;
+    for tag in tags {
+        let value = tag.value.to_string();
+        if tag.key.eq_ignore_ascii_case("LOOPSTART") {
+            loop_start = value.parse::<u64>().ok();
+        } else if tag.key.eq_ignore_ascii_case("LOOPLENGTH") {
+            loop_length = value.parse::<u64>().ok();
+        }
+    }
+
+    let start_frame = loop_start?;
+    // `LOOPSTART`/`LOOPLENGTH` are plain-text Vorbis comments, trivially
+    // forged; guard against a pair that would overflow rather than panic.
+    let end_frame = start_frame.checked_add(loop_length?)?;
+    Some(LoopPoints {
+        start_frame,
+        end_frame,
+    })
+}
+
+/// Parse `REPLAYGAIN_*` Vorbis comments into a [`ReplayGain`]. Gain tags are
+/// stored as `"x.xx dB"`; peak tags as a bare float.
+fn replay_gain_from_tags(tags: &[Tag]) -> Option<ReplayGain> {
+    let mut gain = ReplayGain::default();
+    for tag in tags {
+        let value = tag.value.to_string();
+        let parse_db = || value.trim_end_matches("dB").trim().parse::<f32>().ok();
+        match tag.key.to_ascii_uppercase().as_str() {
+            "REPLAYGAIN_TRACK_GAIN" => gain.track_gain_db = parse_db(),
+            "REPLAYGAIN_TRACK_PEAK" => gain.track_peak = value.trim().parse::<f32>().ok(),
+            "REPLAYGAIN_ALBUM_GAIN" => gain.album_gain_db = parse_db(),
+            "REPLAYGAIN_ALBUM_PEAK" => gain.album_peak = value.trim().parse::<f32>().ok(),
+            "R128_TRACK_GAIN" => {
+                // R128 gain is in Q7.8 fixed point relative to -23 LUFS.
+                gain.track_gain_db = value.trim().parse::<f32>().ok().map(|q| q / 256.0);
+            }
+            "R128_ALBUM_GAIN" => {
+                gain.album_gain_db = value.trim().parse::<f32>().ok().map(|q| q / 256.0);
+            }
+            _ => {}
+        }
+    }
+    Some(gain)
+}
+
+/// Look for embedded lyrics, either via symphonia's standard `Lyrics` key
+/// (ID3 `USLT`) or a raw `LYRICS`/`UNSYNCEDLYRICS` Vorbis comment.
+fn lyrics_from_tags(tags: &[Tag]) -> Option<String> {
+    tags.iter()
+        .find(|tag| {
+            tag.std_key == Some(StandardTagKey::Lyrics)
+                || tag.key.eq_ignore_ascii_case("LYRICS")
+                || tag.key.eq_ignore_ascii_case("UNSYNCEDLYRICS")
+        })
+        .map(|tag| tag.value.to_string())
+}
+
+/// Convert symphonia's embedded visuals into our own [`CoverArt`] list.
+fn visuals_from_metadata(visuals: &[Visual]) -> Vec<CoverArt> {
+    visuals
+        .iter()
+        .map(|visual| CoverArt {
+            media_type: visual.media_type.clone(),
+            data: visual.data.to_vec(),
+            is_front_cover: visual.usage == Some(StandardVisualKey::FrontCover),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia::core::meta::Value;
+
+    use super::*;
+
+    /// A minimal RIFF/WAVE header followed by one bogus chunk whose declared
+    /// size is `u32::MAX`, so the even-padding add would overflow if done
+    /// with plain `+`.
+    fn wav_with_oversized_chunk() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // RIFF size, unused here
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"JUNK");
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn read_wav_smpl_loop_rejects_oversized_chunk_instead_of_panicking() {
+        let bytes = wav_with_oversized_chunk();
+        let result = read_wav_smpl_loop(Cursor::new(bytes));
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[test]
+    fn loop_points_from_tags_drops_pair_that_would_overflow() {
+        let tags = vec![
+            Tag::new(None, "LOOPSTART", Value::from(u64::MAX)),
+            Tag::new(None, "LOOPLENGTH", Value::from(1u64)),
+        ];
+        assert_eq!(loop_points_from_tags(&tags), None);
+    }
+
+    #[test]
+    fn loop_points_from_tags_accepts_valid_pair() {
+        let tags = vec![
+            Tag::new(None, "LOOPSTART", Value::from(100u64)),
+            Tag::new(None, "LOOPLENGTH", Value::from(50u64)),
+        ];
+        assert_eq!(
+            loop_points_from_tags(&tags),
+            Some(LoopPoints { start_frame: 100, end_frame: 150 })
+        );
+    }
+
+    #[test]
+    fn load_raw_pcm_rejects_zero_channels() {
+        let bytes = [0u8; 4];
+        let err = load_raw_pcm(&bytes, 44_100, 0, PcmFormat::S16Le).unwrap_err();
+        assert!(matches!(err, AbloopError::DecodeFailed(_)));
+    }
+
+    #[test]
+    fn load_raw_pcm_rejects_zero_sample_rate() {
+        let bytes = [0u8; 4];
+        let err = load_raw_pcm(&bytes, 0, 2, PcmFormat::S16Le).unwrap_err();
+        assert!(matches!(err, AbloopError::DecodeFailed(_)));
+    }
+
+    #[test]
+    fn load_raw_pcm_rejects_sample_count_not_multiple_of_channels() {
+        // 3 S16LE samples (6 bytes) don't divide evenly into stereo frames.
+        let bytes = [0u8; 6];
+        let err = load_raw_pcm(&bytes, 44_100, 2, PcmFormat::S16Le).unwrap_err();
+        assert!(matches!(err, AbloopError::DecodeFailed(_)));
+    }
+}
@@ -0,0 +1,323 @@
+//! Persisted defaults loaded from `~/.config/auto-abloop/config.toml`
+//! (analysis settings, export format, language, output directory
+//! template). Any value here is just a different default for the matching
+//! CLI flag - passing the flag always wins.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{AnalysisPreset, AnalysisPresetValues, DetectionMode, LoopSelectionPolicy, NormalizationMode};
+use crate::export::ExportFormat;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub mode: DetectionMode,
+    pub normalize: NormalizationMode,
+    pub min_loop_duration: f64,
+    pub correlation_window_frames: usize,
+    pub correlation_threshold: f32,
+    pub fade_out_threshold_db: f32,
+    pub loop_selection_policy: LoopSelectionPolicy,
+    pub export_format: ExportFormat,
+    pub bit_depth: u16,
+    /// Write a `<file>.abloop.json` sidecar (and, with `--features
+    /// loop-db`, a loop database entry) right after `analyze` finds a loop,
+    /// so the result survives even if the user never runs `export`.
+    pub write_sidecar: bool,
+    /// Not read by the CLI or GUI: the message language comes from `--lang`
+    /// or the system locale (see `auto_abloop::i18n::Lang`).
+    pub language: String,
+    /// Output directory for `export` when `--output` isn't given. `{dir}`
+    /// is replaced with each input file's own directory.
+    pub output_dir_template: String,
+    /// User-defined named presets, selectable via `--preset <name>`
+    /// alongside the built-in [`AnalysisPreset`]s, or saved and applied
+    /// from the GUI's "Apply preset" menu.
+    pub presets: BTreeMap<String, NamedPreset>,
+    /// Default headphone crossfeed intensity (`0.0`-`1.0`, `0.0` is off)
+    /// for `play`/`--tui`; see `auto_abloop::player`. Unlike the other
+    /// playback toggles (`--karaoke`, `--normalize`, `--metronome`),
+    /// crossfeed is meant to stay on across sessions once a listener finds
+    /// an intensity they like, so it gets a persisted default instead of
+    /// defaulting to off every time.
+    pub crossfeed: f32,
+    /// Reopen the last file the GUI had loaded (and its loop points, from
+    /// an `.abloop.json` sidecar if one exists) on startup, via
+    /// [`load_session`]/[`save_session`]. Off by default so a fresh
+    /// install opens to an empty window rather than whatever was open
+    /// when it was last closed.
+    pub restore_last_session: bool,
+    /// RAM budget, in megabytes, for a loaded file's decoded sample buffer
+    /// before it spills to a memory-mapped temp file instead of staying on
+    /// the heap; see `auto_abloop::sample_cache::SampleStorage::new`. High
+    /// enough that ordinary files never spill, low enough that a
+    /// multi-hour recording on an 8 GB machine doesn't have to fit in RAM
+    /// twice over.
+    pub sample_cache_budget_mb: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let analysis = crate::analysis::AnalysisSettings::default();
+        Self {
+            mode: analysis.mode,
+            normalize: analysis.normalize,
+            min_loop_duration: 2.0,
+            correlation_window_frames: analysis.correlation_window_frames,
+            correlation_threshold: analysis.correlation_threshold,
+            fade_out_threshold_db: analysis.fade_out_threshold_db,
+            loop_selection_policy: analysis.loop_selection_policy,
+            export_format: ExportFormat::default(),
+            bit_depth: 16,
+            write_sidecar: false,
+            language: "en".to_string(),
+            output_dir_template: "{dir}".to_string(),
+            presets: BTreeMap::new(),
+            crossfeed: 0.0,
+            restore_last_session: false,
+            sample_cache_budget_mb: 512,
+        }
+    }
+}
+
+impl Config {
+    /// Resolve an analysis preset by name: a user-defined entry under
+    /// `[presets.<name>]` first, falling back to a built-in
+    /// [`AnalysisPreset`] matched case-insensitively against its
+    /// kebab-case name (e.g. `game-music`). Ignores a user-defined
+    /// preset's export options - see [`Config::resolve_named_preset`] for
+    /// those too.
+    pub fn resolve_preset(&self, name: &str) -> Option<AnalysisPresetValues> {
+        if let Some(preset) = self.presets.get(name) {
+            return Some(preset.analysis);
+        }
+        AnalysisPreset::from_str(name, true).ok().map(AnalysisPreset::values)
+    }
+
+    /// Like [`Config::resolve_preset`], but for a user-defined preset's
+    /// full bundle of analysis settings and export options together.
+    /// There's no built-in fallback here - the built-in [`AnalysisPreset`]s
+    /// don't carry export options, only the user-defined ones saved from
+    /// the GUI do.
+    pub fn resolve_named_preset(&self, name: &str) -> Option<NamedPreset> {
+        self.presets.get(name).copied()
+    }
+}
+
+/// Analysis settings and export options saved together under one name -
+/// what the GUI's "Save preset as..." writes out, and what "Apply preset"
+/// reads back. The CLI's `--preset <name>` flag only looks at `analysis`,
+/// via [`Config::resolve_preset`]; it already has its own `--format`/
+/// `--bit-depth` flags for export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct NamedPreset {
+    #[serde(flatten)]
+    pub analysis: AnalysisPresetValues,
+    pub export_format: ExportFormat,
+    pub bit_depth: u16,
+}
+
+/// Where [`load`] looks for a config file, if the platform has a config
+/// directory at all.
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("auto-abloop").join("config.toml"))
+}
+
+/// Load the config file, falling back to [`Config::default`] if it's
+/// missing, unreadable, or fails to parse.
+pub fn load() -> Config {
+    config_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| toml::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// The config file, read once and cached for the life of the process -
+/// used as the source of CLI flag defaults, which are computed every time
+/// `clap` builds the argument parser.
+pub fn defaults() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(load)
+}
+
+/// Write `config` to the config file as plain TOML, creating its parent
+/// directory if needed. Unlike [`init`]'s commented template, this is a
+/// full rewrite with no comments - for programmatic updates (the GUI
+/// saving a new named preset) rather than a human hand-editing the file.
+/// [`defaults`]'s cached copy is left untouched; the GUI keeps its own
+/// in-memory copy of [`Config`] for the rest of the session.
+pub fn save(config: &Config) -> Result<()> {
+    let path = config_path().context("no config directory for this platform")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let text = toml::to_string_pretty(config).context("serializing config")?;
+    std::fs::write(&path, text).with_context(|| format!("writing {}", path.display()))
+}
+
+/// What file was open last, for [`Config::restore_last_session`] to reopen
+/// on the GUI's next startup. Kept separate from [`Config`] since it's
+/// runtime state the GUI updates on every load, not a user preference -
+/// mixing it into `config.toml` would make a hand-edited settings file
+/// churn every time a different track is opened.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub last_file: Option<PathBuf>,
+}
+
+/// Where [`load_session`]/[`save_session`] read and write the last-open-file
+/// record, alongside but separate from [`config_path`]'s config file.
+pub fn session_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("auto-abloop").join("session.toml"))
+}
+
+/// Load the session file, falling back to [`Session::default`] if it's
+/// missing, unreadable, or fails to parse.
+pub fn load_session() -> Session {
+    session_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| toml::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Write `session` to the session file as plain TOML, creating its parent
+/// directory if needed.
+pub fn save_session(session: &Session) -> Result<()> {
+    let path = session_path().context("no config directory for this platform")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let text = toml::to_string_pretty(session).context("serializing session")?;
+    std::fs::write(&path, text).with_context(|| format!("writing {}", path.display()))
+}
+
+/// A commented template listing every setting at its built-in default,
+/// for `config init` to write out.
+pub fn template() -> String {
+    let defaults = Config::default();
+    format!(
+        r#"# auto-abloop configuration.
+#
+# Every setting here is just a different default for the matching CLI
+# flag - uncomment a line to change it, and it still loses to the flag
+# whenever that flag is passed explicitly.
+
+# Loop detection mode. "cross-correlation" is currently the only option.
+# mode = "{mode}"
+
+# Gain-normalize the mono buffer before detection: "none", "peak", or
+# "rms". Keeps the fixed thresholds below behaving the same on very quiet
+# and very loud masters.
+# normalize = "{normalize}"
+
+# Candidate loop starts within this many seconds of the end of the track
+# are not considered.
+# min_loop_duration = {min_loop_duration}
+
+# Length, in frames, of the window compared between candidate loop starts
+# and the track's tail.
+# correlation_window_frames = {correlation_window_frames}
+
+# Minimum normalized cross-correlation (0.0-1.0) required to accept a
+# loop point.
+# correlation_threshold = {correlation_threshold}
+
+# A drop of this many dB from the track's overall RMS level, sustained to
+# the end of the track, is treated as a fade-out.
+# fade_out_threshold_db = {fade_out_threshold_db}
+
+# When several candidates score within 0.02 of each other, which one to
+# pick: "highest-confidence" (ignore the tie and take the best score),
+# "longest" (the one starting earliest), or "latest-start" (the one
+# starting latest, i.e. the shortest of the tied candidates).
+# loop_selection_policy = "{loop_selection_policy}"
+
+# Default export container/codec.
+# export_format = "{export_format}"
+
+# Default PCM sample width in bits: 8, 16, 24, or 32.
+# bit_depth = {bit_depth}
+
+# Automatically write a `<file>.abloop.json` sidecar (and, with
+# `--features loop-db`, a loop database entry) whenever `analyze` finds a
+# loop, so the result isn't lost if you never run `export`.
+# write_sidecar = {write_sidecar}
+
+# UI language. Not read: `--lang` or the system locale picks the language.
+# language = "{language}"
+
+# Output directory for `export` when `--output` isn't given. `{{dir}}` is
+# replaced with each input file's own directory.
+# output_dir_template = "{output_dir_template}"
+
+# Named presets, selectable with `--preset <name>` alongside the built-in
+# analysis presets (`game-music`, `classical-long-tail`, `electronic`,
+# `ambient`, `jingle`), or from the GUI's "Apply preset" menu. `--preset` only reads
+# the analysis fields below; export_format/bit_depth are for the GUI's
+# "Save preset as..."/"Apply preset", which applies both at once.
+# [presets.my-preset]
+# mode = "cross-correlation"
+# normalize = "rms"
+# min_loop_duration = 3.0
+# correlation_window_frames = 4096
+# correlation_threshold = 0.85
+# fade_out_threshold_db = -20.0
+# export_format = "wav"
+# bit_depth = 16
+
+# Default headphone crossfeed intensity for `play`/`--tui` (0.0-1.0, 0.0 is
+# off), blending a bit of each channel into the other for long listening
+# sessions on headphones. Overridden by `--crossfeed`.
+# crossfeed = {crossfeed}
+
+# Reopen the GUI's last-loaded file (and its loop points, from an
+# `.abloop.json` sidecar if one exists) on startup.
+# restore_last_session = {restore_last_session}
+
+# RAM budget, in megabytes, for a loaded file's decoded samples before they
+# spill to a memory-mapped temp file instead of staying on the heap - keeps
+# multi-hour recordings usable on machines with little RAM to spare.
+# sample_cache_budget_mb = {sample_cache_budget_mb}
+"#,
+        mode = toml_string(&defaults.mode),
+        normalize = toml_string(&defaults.normalize),
+        min_loop_duration = defaults.min_loop_duration,
+        correlation_window_frames = defaults.correlation_window_frames,
+        correlation_threshold = defaults.correlation_threshold,
+        fade_out_threshold_db = defaults.fade_out_threshold_db,
+        loop_selection_policy = toml_string(&defaults.loop_selection_policy),
+        export_format = toml_string(&defaults.export_format),
+        bit_depth = defaults.bit_depth,
+        write_sidecar = defaults.write_sidecar,
+        language = defaults.language,
+        output_dir_template = defaults.output_dir_template,
+        crossfeed = defaults.crossfeed,
+        restore_last_session = defaults.restore_last_session,
+        sample_cache_budget_mb = defaults.sample_cache_budget_mb,
+    )
+}
+
+/// Render a serde value the same way it would appear as a bare TOML
+/// string, without the surrounding quotes `toml::to_string` would add.
+fn toml_string<T: Serialize>(value: &T) -> String {
+    match toml::Value::try_from(value) {
+        Ok(toml::Value::String(s)) => s,
+        _ => String::new(),
+    }
+}
+
+/// Write the commented template to `path`, creating its parent directory
+/// if needed.
+pub fn init(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+    std::fs::write(path, template()).with_context(|| format!("writing {}", path.display()))
+}
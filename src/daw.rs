@@ -0,0 +1,69 @@
+//! Exporters for DAW-native loop markers, so a sound designer can keep
+//! refining a loop in Reaper or Ardour with the detected points already
+//! placed instead of re-marking them by ear.
+//!
+//! Reaper can import the CSV [`write_reaper_regions`] writes directly, via
+//! the Region/Marker Manager's "Import regions/markers from file". Ardour
+//! has no standalone marker import - locations live in the session's own
+//! `.ardour` XML file - so [`write_ardour_locations`] writes a
+//! `<Locations>` fragment to paste into that file's existing `<Locations>`
+//! element by hand; it doesn't open or edit a session itself.
+
+use std::path::Path;
+
+use crate::audio::LoopPoints;
+use crate::error::{Context, Result};
+
+/// Write `loop_points` (as a "Loop" region) and `fade_out_start_frame` (as
+/// a "Fade out" marker), if given, to `path` as a Reaper region/marker CSV.
+/// Positions are in seconds, the unit Reaper's importer expects.
+pub fn write_reaper_regions(
+    path: &Path,
+    loop_points: Option<LoopPoints>,
+    fade_out_start_frame: Option<u64>,
+    sample_rate: u32,
+) -> Result<()> {
+    let mut out = String::from("#,Name,Start,End,Length\n");
+    if let Some(loop_points) = loop_points {
+        let start = frames_to_secs(loop_points.start_frame, sample_rate);
+        let end = frames_to_secs(loop_points.end_frame, sample_rate);
+        out.push_str(&format!("R1,Loop,{start:.6},{end:.6},{:.6}\n", end - start));
+    }
+    if let Some(fade_out_start_frame) = fade_out_start_frame {
+        let position = frames_to_secs(fade_out_start_frame, sample_rate);
+        out.push_str(&format!("M1,Fade out,{position:.6},,\n"));
+    }
+    std::fs::write(path, out).context(|| format!("writing {}", path.display()))
+}
+
+/// Write `loop_points`/`fade_out_start_frame` to `path` as an Ardour
+/// `<Locations>` XML fragment. Positions are in samples - Ardour stores
+/// location positions sample-accurately, not in seconds - so no sample
+/// rate is needed.
+pub fn write_ardour_locations(
+    path: &Path,
+    loop_points: Option<LoopPoints>,
+    fade_out_start_frame: Option<u64>,
+) -> Result<()> {
+    let mut out = String::from("<Locations>\n");
+    if let Some(loop_points) = loop_points {
+        out.push_str(&format!(
+            "  <Location id=\"1\" name=\"Loop\" start=\"{}\" end=\"{}\" flags=\"IsRangeMarker\" \
+             locked=\"no\" position-lock-style=\"AudioTime\"/>\n",
+            loop_points.start_frame, loop_points.end_frame
+        ));
+    }
+    if let Some(fade_out_start_frame) = fade_out_start_frame {
+        out.push_str(&format!(
+            "  <Location id=\"2\" name=\"Fade out\" start=\"{fade_out_start_frame}\" \
+             end=\"{fade_out_start_frame}\" flags=\"IsMark\" locked=\"no\" \
+             position-lock-style=\"AudioTime\"/>\n"
+        ));
+    }
+    out.push_str("</Locations>\n");
+    std::fs::write(path, out).context(|| format!("writing {}", path.display()))
+}
+
+fn frames_to_secs(frame: u64, sample_rate: u32) -> f64 {
+    frame as f64 / sample_rate as f64
+}
@@ -0,0 +1,90 @@
+//! Per-phase timing for analysis, recorded from the `tracing` spans in
+//! [`crate::analysis`] (preprocessing, the correlation search, fade-out
+//! detection) when the `tracing` feature is enabled, so a slow file's time
+//! can be broken down by phase instead of only seeing one opaque total.
+//! [`install`] wires a [`TimingLayer`] into the global subscriber and hands
+//! back the [`PhaseTimings`] sink it writes into; the GUI's diagnostics
+//! panel reads that sink each frame to show where time went for the file
+//! currently loaded.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::span;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// One phase's most recently recorded wall-clock duration, keyed by the
+/// span's name (`"analysis::preprocess"`, `"analysis::correlation_search"`,
+/// `"analysis::fade_out"` - see [`crate::analysis`]'s `#[tracing::instrument]`
+/// attributes).
+#[derive(Debug, Clone)]
+pub struct PhaseTiming {
+    pub name: &'static str,
+    pub duration: Duration,
+}
+
+/// Where [`TimingLayer`] records timings and callers (the GUI diagnostics
+/// panel, a future CLI summary) read them back from. Cheap to clone - every
+/// clone shares the same underlying timings.
+#[derive(Default, Clone)]
+pub struct PhaseTimings(Arc<Mutex<Vec<PhaseTiming>>>);
+
+impl PhaseTimings {
+    /// The most recently completed run's phase timings, in the order each
+    /// phase's span first closed (a nested phase closes before the phase
+    /// around it). Overwritten in place on the next analysis run, so this
+    /// always reflects the latest file, not a history across files.
+    pub fn recent(&self) -> Vec<PhaseTiming> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn record(&self, name: &'static str, duration: Duration) {
+        let mut timings = self.0.lock().unwrap();
+        match timings.iter_mut().find(|t| t.name == name) {
+            Some(existing) => existing.duration = duration,
+            None => timings.push(PhaseTiming { name, duration }),
+        }
+    }
+}
+
+/// Timer started when a span opens, stashed in the span's extensions until
+/// [`TimingLayer::on_close`] reads it back off.
+struct SpanStart(Instant);
+
+/// A `tracing_subscriber` [`Layer`] that times every span closed under it
+/// and records the result into a [`PhaseTimings`] sink instead of writing a
+/// log line, so the numbers can be read back and rendered directly.
+struct TimingLayer {
+    sink: PhaseTimings,
+}
+
+impl<S> Layer<S> for TimingLayer
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some(start) = span.extensions().get::<SpanStart>().map(|s| s.0) else { return };
+        self.sink.record(span.name(), start.elapsed());
+    }
+}
+
+/// Install a [`TimingLayer`] as the global `tracing` subscriber and return
+/// its [`PhaseTimings`] sink. Only the first call per process actually
+/// installs anything - `tracing`'s global subscriber can only be set once -
+/// so call this once near startup (the GUI does, in [`crate::gui::AbloopApp::default`])
+/// and hold onto the sink it returns rather than calling it again per file.
+pub fn install() -> PhaseTimings {
+    let sink = PhaseTimings::default();
+    let layer = TimingLayer { sink: sink.clone() };
+    let _ = tracing_subscriber::registry().with(layer).try_init();
+    sink
+}
@@ -0,0 +1,69 @@
+//! A typed error for the library's decode/analyze/export pipeline, so
+//! downstream crates (and the binaries' own exit-code logic) can match on
+//! what went wrong instead of inspecting an opaque error's message text.
+
+use thiserror::Error;
+
+/// What can go wrong loading, analyzing, or exporting audio.
+#[derive(Debug, Error)]
+pub enum AbloopError {
+    /// A filesystem or network operation failed.
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The input doesn't look like a format we know how to read at all, as
+    /// opposed to a recognized format that failed to decode for some other
+    /// reason (see [`AbloopError::DecodeFailed`]).
+    #[error("unsupported format: {0}")]
+    UnsupportedFormat(String),
+    /// The input is a recognized format, but decoding it failed.
+    #[error("failed to decode audio: {0}")]
+    DecodeFailed(String),
+    /// Analysis found no loop point meeting the confidence threshold.
+    #[error("no loop point found")]
+    NoLoopFound,
+    /// Export failed for a reason specific to the export pipeline (bad
+    /// settings, or a step beyond plain file I/O).
+    #[error("export failed: {0}")]
+    ExportFailed(String),
+    /// A settings builder (e.g. [`crate::analysis::AnalysisSettingsBuilder`])
+    /// was given a value outside the range it can act on.
+    #[error("invalid settings: {0}")]
+    InvalidSettings(String),
+    /// [`crate::import::parse_loop_points`] couldn't make sense of the
+    /// input as any supported loop import format.
+    #[error("import failed: {0}")]
+    ImportFailed(String),
+}
+
+impl From<std::io::Error> for AbloopError {
+    fn from(source: std::io::Error) -> Self {
+        AbloopError::Io {
+            context: "I/O error".to_string(),
+            source: Box::new(source),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, AbloopError>;
+
+/// Like `anyhow::Context`, but attaches to [`AbloopError::Io`] instead of an
+/// opaque `anyhow::Error`.
+pub(crate) trait Context<T> {
+    fn context(self, context: impl FnOnce() -> String) -> Result<T>;
+}
+
+impl<T, E> Context<T> for std::result::Result<T, E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|source| AbloopError::Io {
+            context: context(),
+            source: Box::new(source),
+        })
+    }
+}
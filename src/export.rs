@@ -0,0 +1,965 @@
+//! Writing analyzed audio back out as a looped WAV or AIFF file, with the
+//! loop region stored as a `smpl` chunk (WAV) or `MARK`/`INST` chunks
+//! (AIFF) so downstream tools and engines pick it up the same way we read
+//! it in [`crate::audio`]. WAV renders whose PCM data would overflow a
+//! plain RIFF chunk's 32-bit size are written as RF64 instead; see
+//! [`write_wav`].
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{AudioData, LoopPoints};
+use crate::error::{AbloopError, Result};
+
+/// Supported export containers/codecs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[value(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Wav,
+    /// Uncompressed AIFF, with the loop region (if any) stored as `MARK`/
+    /// `INST` chunks instead of WAV's `smpl`, for hardware samplers and
+    /// older DAWs that expect AIFF-style loop metadata.
+    Aiff,
+}
+
+impl ExportFormat {
+    /// The file extension (without the dot) this format is conventionally
+    /// saved under.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Wav => "wav",
+            ExportFormat::Aiff => "aiff",
+        }
+    }
+}
+
+/// What to do when the rendered output contains samples that would clip
+/// (exceed full scale, `|sample| > 1.0`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipHandling {
+    /// Export the render unchanged and log a warning; the caller decides
+    /// what, if anything, to do about it.
+    #[default]
+    Warn,
+    /// Hard-limit: clamp any clipping peak to full scale, leaving samples
+    /// that don't clip untouched.
+    Limiter,
+    /// Scale the whole buffer down uniformly so its loudest sample just
+    /// touches full scale.
+    ReduceGain,
+}
+
+/// Tunable parameters for [`export`].
+#[derive(Debug, Clone)]
+pub struct ExportSettings {
+    pub format: ExportFormat,
+    /// PCM sample width: 8, 16, 24, or 32 bits.
+    pub bit_depth: u16,
+    /// Only meaningful for lossy formats; `Wav` rejects it with an error.
+    pub bitrate_kbps: Option<u32>,
+    /// Blend this many frames across the loop seam so it reads as one
+    /// continuous transition instead of a hard cut.
+    pub crossfade_frames: u64,
+    /// If set, render a fixed-length file by repeating the loop region
+    /// until this many frames are reached, instead of exporting a single
+    /// pass with loop metadata.
+    pub target_duration_frames: Option<u64>,
+    /// Drop everything before the loop start instead of exporting the
+    /// intro, so the render begins mid-groove; the loop region (now
+    /// starting at frame 0) is otherwise unchanged.
+    pub skip_intro: bool,
+    /// What to do if the render clips; see [`ClipHandling`].
+    pub clip_handling: ClipHandling,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::default(),
+            bit_depth: 16,
+            bitrate_kbps: None,
+            crossfade_frames: 0,
+            target_duration_frames: None,
+            skip_intro: false,
+            clip_handling: ClipHandling::default(),
+        }
+    }
+}
+
+/// Named bundles of [`ExportSettings`] tuned for a specific game engine's
+/// loop conventions, so a modder who isn't an audio expert gets a correct
+/// export by picking an engine instead of having to know which container,
+/// bit depth, and intro handling it wants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportPreset {
+    /// Unity (`AudioClip` import) and standalone FMOD projects: 48 kHz,
+    /// 16-bit WAV with the loop in a `smpl` chunk, which both read
+    /// natively.
+    UnityFmod,
+    /// RPG Maker MV/MZ: 44.1 kHz, 16-bit WAV with the intro dropped, since
+    /// RPG Maker's BGM loop handling always repeats the whole file rather
+    /// than honoring embedded loop metadata.
+    RpgMaker,
+    /// Godot: 44.1 kHz, 16-bit WAV with the loop in a `smpl` chunk, which
+    /// `AudioStreamWAV` reads directly as its own `loop_begin`/`loop_end`.
+    Godot,
+}
+
+impl ExportPreset {
+    /// The export settings this preset bundles. Crossfade, target
+    /// duration, and bitrate are left at their defaults - those are
+    /// per-export creative choices, not part of an engine's convention.
+    pub fn settings(self) -> ExportSettings {
+        match self {
+            ExportPreset::UnityFmod => ExportSettings {
+                bit_depth: 16,
+                ..ExportSettings::default()
+            },
+            ExportPreset::RpgMaker => ExportSettings {
+                bit_depth: 16,
+                skip_intro: true,
+                ..ExportSettings::default()
+            },
+            ExportPreset::Godot => ExportSettings {
+                bit_depth: 16,
+                ..ExportSettings::default()
+            },
+        }
+    }
+
+    /// The sample rate this preset's target engine conventionally expects.
+    /// Exporting audio at a different rate still works - this exporter
+    /// doesn't resample - but a caller may want to warn the user instead
+    /// of silently shipping a mismatched asset.
+    pub fn expected_sample_rate(self) -> u32 {
+        match self {
+            ExportPreset::UnityFmod => 48_000,
+            ExportPreset::RpgMaker | ExportPreset::Godot => 44_100,
+        }
+    }
+
+    /// The human-readable name this preset is shown as in the GUI and in
+    /// CLI warnings. The CLI's `--preset` takes the kebab-case value name
+    /// instead (e.g. `unity-fmod`).
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportPreset::UnityFmod => "Unity/FMOD",
+            ExportPreset::RpgMaker => "RPG Maker",
+            ExportPreset::Godot => "Godot",
+        }
+    }
+}
+
+/// Write `audio` as a looped render at `path`, per `settings`. When
+/// `loop_points` is given (and no `target_duration_frames` render is
+/// requested), it is embedded as a `smpl` chunk loop for WAV (the same
+/// shape [`crate::audio::load_audio_from_path`] already knows how to read
+/// back), or as `MARK`/`INST` chunks for AIFF.
+pub fn export(
+    audio: &AudioData,
+    loop_points: Option<LoopPoints>,
+    settings: &ExportSettings,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    export_to_writer(
+        audio,
+        loop_points,
+        settings,
+        BufWriter::new(File::create(path)?),
+    )
+}
+
+/// Like [`export`], but writes to any `Write` destination (e.g. a pipe or
+/// stdout) instead of a file on disk.
+pub fn export_to_writer(
+    audio: &AudioData,
+    loop_points: Option<LoopPoints>,
+    settings: &ExportSettings,
+    writer: impl Write,
+) -> Result<()> {
+    if !matches!(settings.bit_depth, 8 | 16 | 24 | 32) {
+        return Err(AbloopError::ExportFailed(format!(
+            "unsupported bit depth {} (expected 8, 16, 24, or 32)",
+            settings.bit_depth
+        )));
+    }
+    if settings.bitrate_kbps.is_some() {
+        return Err(AbloopError::ExportFailed(
+            "--bitrate only applies to lossy export formats; wav/aiff are lossless".to_string(),
+        ));
+    }
+    if settings.crossfade_frames > 0 && loop_points.is_none() {
+        return Err(AbloopError::ExportFailed(
+            "--crossfade requires a loop point to crossfade across".to_string(),
+        ));
+    }
+    if settings.target_duration_frames.is_some() && loop_points.is_none() {
+        return Err(AbloopError::ExportFailed(
+            "--target-duration requires a loop point to repeat".to_string(),
+        ));
+    }
+    if settings.skip_intro && loop_points.is_none() {
+        return Err(AbloopError::ExportFailed(
+            "--skip-intro requires a loop point to start from".to_string(),
+        ));
+    }
+    if let Some(points) = loop_points {
+        if points.start_frame >= points.end_frame || points.end_frame > audio.frame_count() {
+            return Err(AbloopError::ExportFailed(format!(
+                "loop points (start={}, end={}) are out of range for a {}-frame track",
+                points.start_frame,
+                points.end_frame,
+                audio.frame_count()
+            )));
+        }
+    }
+
+    let (samples, loop_points) = render(audio, loop_points, settings);
+    match settings.format {
+        ExportFormat::Wav => write_wav(
+            &samples,
+            audio.sample_rate,
+            audio.channels,
+            settings.bit_depth,
+            loop_points,
+            writer,
+        ),
+        ExportFormat::Aiff => write_aiff(
+            &samples,
+            audio.sample_rate,
+            audio.channels,
+            settings.bit_depth,
+            loop_points,
+            writer,
+        ),
+    }
+}
+
+/// The frame count and loop points of what [`export`] would actually write
+/// for `audio`/`loop_points` under `settings`, without writing anything -
+/// for callers that need to describe a render (e.g. an audio-sprite
+/// descriptor) rather than produce one.
+pub fn rendered_shape(
+    audio: &AudioData,
+    loop_points: Option<LoopPoints>,
+    settings: &ExportSettings,
+) -> (u64, Option<LoopPoints>) {
+    let (samples, loop_points) = render(audio, loop_points, settings);
+    (samples.len() as u64 / audio.channels as u64, loop_points)
+}
+
+/// Write `audio` as a 16-bit PCM WAV file at `path` with no crossfade or
+/// duration targeting; a thin convenience wrapper over [`export`] for
+/// callers that don't need the rest of [`ExportSettings`].
+pub fn export_wav(
+    audio: &AudioData,
+    loop_points: Option<LoopPoints>,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    export(audio, loop_points, &ExportSettings::default(), path)
+}
+
+/// Like [`export_wav`], streaming to any `Write` destination.
+pub fn export_wav_to_writer(
+    audio: &AudioData,
+    loop_points: Option<LoopPoints>,
+    writer: impl Write,
+) -> Result<()> {
+    export_to_writer(audio, loop_points, &ExportSettings::default(), writer)
+}
+
+/// Render each of `candidates` (a loop point paired with the confidence it
+/// was found at) into its own file under `dir`, named with a timestamp and
+/// that confidence so several runs don't clobber each other and the best
+/// can be picked by listening outside the app. `preview_seconds > 0.0`
+/// renders just the seam - [`export_seam_preview`] - so comparing many
+/// candidates doesn't mean rendering (or listening through) the full track
+/// that many times; `0.0` renders each candidate's full loop instead, via
+/// [`export`].
+pub fn export_candidate_previews(
+    audio: &AudioData,
+    candidates: &[(LoopPoints, f32)],
+    preview_seconds: f64,
+    match_loudness: bool,
+    settings: &ExportSettings,
+    dir: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+
+    let mut paths = Vec::with_capacity(candidates.len());
+    for (rank, &(loop_points, confidence)) in candidates.iter().enumerate() {
+        let path = dir.join(format!(
+            "candidate_{rank:02}_conf{confidence:.3}_{timestamp}.{}",
+            settings.format.extension()
+        ));
+        if preview_seconds > 0.0 {
+            export_seam_preview(audio, loop_points, preview_seconds, match_loudness, settings, &path)?;
+        } else {
+            export(audio, Some(loop_points), settings, &path)?;
+        }
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Write just `loop_points`' seam - the `preview_seconds` immediately
+/// before `end_frame` spliced directly to the `preview_seconds`
+/// immediately after `start_frame` - rather than the whole track, for
+/// quickly auditioning whether a candidate's transition is clean without
+/// rendering (or listening through) the full loop. The written file has no
+/// loop metadata of its own; it's a one-shot preview, not something meant
+/// to be cycled by a player.
+///
+/// `match_loudness` gain-matches the post-jump segment's RMS to the
+/// pre-jump segment's before writing, so a level difference between the
+/// two (the pre-jump segment trailing off into a quieter passage, say)
+/// doesn't mask the timing/phase discontinuity the preview exists to
+/// surface.
+pub fn export_seam_preview(
+    audio: &AudioData,
+    loop_points: LoopPoints,
+    preview_seconds: f64,
+    match_loudness: bool,
+    settings: &ExportSettings,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    if !matches!(settings.bit_depth, 8 | 16 | 24 | 32) {
+        return Err(AbloopError::ExportFailed(format!(
+            "unsupported bit depth {} (expected 8, 16, 24, or 32)",
+            settings.bit_depth
+        )));
+    }
+
+    let channels = audio.channels as usize;
+    let frame_count = audio.samples.len() as u64 / channels as u64;
+    let preview_frames = ((preview_seconds * audio.sample_rate as f64) as u64).max(1);
+    let before_end = loop_points.end_frame.saturating_sub(preview_frames);
+    let after_start = (loop_points.start_frame + preview_frames).min(frame_count);
+
+    let pre_jump = &audio.samples[before_end as usize * channels..loop_points.end_frame as usize * channels];
+    let post_jump = &audio.samples[loop_points.start_frame as usize * channels..after_start as usize * channels];
+
+    let mut samples = pre_jump.to_vec();
+    if match_loudness {
+        samples.extend(gain_matched(post_jump, rms(pre_jump)));
+    } else {
+        samples.extend_from_slice(post_jump);
+    }
+
+    let writer = BufWriter::new(File::create(path)?);
+    match settings.format {
+        ExportFormat::Wav => {
+            write_wav(&samples, audio.sample_rate, audio.channels, settings.bit_depth, None, writer)
+        }
+        ExportFormat::Aiff => {
+            write_aiff(&samples, audio.sample_rate, audio.channels, settings.bit_depth, None, writer)
+        }
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Scale `samples` so its RMS matches `target_rms`, clamped to avoid
+/// blowing up a near-silent segment into a huge gain spike. A no-op on
+/// silence, since there's no sensible gain to apply.
+fn gain_matched(samples: &[f32], target_rms: f32) -> Vec<f32> {
+    let level = rms(samples);
+    if level == 0.0 || target_rms == 0.0 {
+        return samples.to_vec();
+    }
+    let gain = (target_rms / level).clamp(0.0, 8.0);
+    samples.iter().map(|&s| s * gain).collect()
+}
+
+/// Apply crossfade/target-duration shaping, returning the samples to write
+/// and the loop points (if any) still worth embedding in the output. Plain
+/// exports with neither setting borrow `audio.samples` rather than copying
+/// it; only the settings that actually mutate the buffer pay for a copy.
+fn render<'a>(
+    audio: &'a AudioData,
+    mut loop_points: Option<LoopPoints>,
+    settings: &ExportSettings,
+) -> (Cow<'a, [f32]>, Option<LoopPoints>) {
+    let channels = audio.channels as usize;
+    let mut samples = Cow::Borrowed(audio.samples.as_ref());
+
+    if settings.skip_intro {
+        if let Some(points) = loop_points {
+            let (rendered, rebased) = skip_intro(&samples, channels, points);
+            samples = Cow::Owned(rendered);
+            loop_points = Some(rebased);
+        }
+    }
+
+    if settings.crossfade_frames > 0 {
+        if let Some(loop_points) = loop_points {
+            apply_loop_crossfade(samples.to_mut(), channels, loop_points, settings.crossfade_frames);
+        }
+    }
+
+    let (mut samples, loop_points) = match (settings.target_duration_frames, loop_points) {
+        (Some(target_frames), Some(loop_points)) => {
+            let rendered = render_to_duration(&samples, channels, loop_points, target_frames);
+            // The file is now a finished fixed-length render, not a loop to
+            // be cycled by a player, so there's no loop point left to embed.
+            (Cow::Owned(rendered), None)
+        }
+        _ => (samples, loop_points),
+    };
+    debug_assert!(
+        samples.len().is_multiple_of(channels),
+        "rendered {} samples is not a whole number of {channels}-channel frames",
+        samples.len()
+    );
+
+    if let Some(peak) = clip_peak(&samples) {
+        handle_clipping(samples.to_mut(), peak, settings.clip_handling);
+    }
+    (samples, loop_points)
+}
+
+/// The highest absolute sample value in `samples`, if any sample clips
+/// (`|sample| > 1.0`) - `None` means a clean render, so callers can skip
+/// touching the buffer at all.
+fn clip_peak(samples: &[f32]) -> Option<f32> {
+    let peak = samples.iter().fold(0.0f32, |peak, &sample| peak.max(sample.abs()));
+    (peak > 1.0).then_some(peak)
+}
+
+/// Act on a render that [`clip_peak`] found to clip. Always logs a warning,
+/// even when `handling` goes on to fix it, so a creative choice (crossfade,
+/// loudness normalization, ...) that pushed the render over full scale
+/// doesn't pass silently.
+fn handle_clipping(samples: &mut [f32], peak: f32, handling: ClipHandling) {
+    log::warn!(
+        "render clips at {:.2} dBFS{}",
+        20.0 * peak.log10(),
+        match handling {
+            ClipHandling::Warn => "",
+            ClipHandling::Limiter => "; limiting to full scale",
+            ClipHandling::ReduceGain => "; reducing gain to fit",
+        }
+    );
+
+    match handling {
+        ClipHandling::Warn => {}
+        ClipHandling::Limiter => {
+            for sample in samples.iter_mut() {
+                *sample = sample.clamp(-1.0, 1.0);
+            }
+        }
+        ClipHandling::ReduceGain => {
+            let scale = 1.0 / peak;
+            for sample in samples.iter_mut() {
+                *sample *= scale;
+            }
+        }
+    }
+}
+
+/// Blend the `crossfade_frames` immediately before the loop end with the
+/// same span immediately before the loop start, so the seam isn't a hard
+/// cut.
+fn apply_loop_crossfade(
+    samples: &mut [f32],
+    channels: usize,
+    loop_points: LoopPoints,
+    crossfade_frames: u64,
+) {
+    let loop_len = loop_points.end_frame.saturating_sub(loop_points.start_frame);
+    let crossfade_frames = crossfade_frames
+        .min(loop_points.start_frame)
+        .min(loop_len) as usize;
+    if crossfade_frames == 0 {
+        return;
+    }
+    debug_assert!(samples.len().is_multiple_of(channels), "PCM buffer is not frame-aligned");
+
+    let end_start = (loop_points.end_frame as usize - crossfade_frames) * channels;
+    let start_start = (loop_points.start_frame as usize - crossfade_frames) * channels;
+    let span = crossfade_frames * channels;
+
+    let outgoing = samples[end_start..end_start + span].to_vec();
+    let blended = blend_frames(&outgoing, &samples[start_start..start_start + span], channels, crossfade_frames);
+    samples[end_start..end_start + span].copy_from_slice(&blended);
+}
+
+/// Linearly blend `incoming` into `outgoing` over `frame_count` frames, one
+/// frame (all of its channels together) at a time, so a crossfade can never
+/// advance a channel index without also advancing the frame it belongs to -
+/// the source of the channel swaps that slip in when a crossfade is
+/// re-derived by hand at each call site instead of sharing one
+/// implementation. Used both for in-track loop seams ([`apply_loop_crossfade`])
+/// and for blending across two tracks' buffers in album playback.
+pub(crate) fn blend_frames(outgoing: &[f32], incoming: &[f32], channels: usize, frame_count: usize) -> Vec<f32> {
+    debug_assert_eq!(outgoing.len(), frame_count * channels);
+    debug_assert_eq!(incoming.len(), frame_count * channels);
+
+    let mut blended = Vec::with_capacity(frame_count * channels);
+    for (outgoing_frame, incoming_frame) in outgoing.chunks_exact(channels).zip(incoming.chunks_exact(channels)) {
+        let t = (blended.len() / channels + 1) as f32 / (frame_count + 1) as f32;
+        for (&o, &i) in outgoing_frame.iter().zip(incoming_frame) {
+            blended.push(o * (1.0 - t) + i * t);
+        }
+    }
+    blended
+}
+
+/// Drop everything before `loop_points.start_frame`, rebasing the loop to
+/// start at frame 0 of the returned buffer. Frames after `end_frame` (a
+/// tail past the loop, if any) are kept as-is.
+fn skip_intro(samples: &[f32], channels: usize, loop_points: LoopPoints) -> (Vec<f32>, LoopPoints) {
+    let start = loop_points.start_frame as usize * channels;
+    let rebased = LoopPoints {
+        start_frame: 0,
+        end_frame: loop_points.end_frame - loop_points.start_frame,
+    };
+    let rendered = samples[start..].to_vec();
+    debug_assert!(rendered.len().is_multiple_of(channels), "skip_intro produced a partial frame");
+    (rendered, rebased)
+}
+
+/// Repeat the `[start_frame, end_frame)` loop region after the intro until
+/// `target_frames` is reached, then trim to exactly that length.
+fn render_to_duration(
+    samples: &[f32],
+    channels: usize,
+    loop_points: LoopPoints,
+    target_frames: u64,
+) -> Vec<f32> {
+    let intro_end = loop_points.start_frame as usize * channels;
+    let loop_region = &samples[intro_end..loop_points.end_frame as usize * channels];
+
+    let mut out = samples[..intro_end].to_vec();
+    while (out.len() / channels) < target_frames as usize && !loop_region.is_empty() {
+        out.extend_from_slice(loop_region);
+    }
+    out.truncate(target_frames as usize * channels);
+    debug_assert!(out.len().is_multiple_of(channels), "render_to_duration produced a partial frame");
+    out
+}
+
+/// Past this many bytes of PCM data, [`write_wav`] switches from plain
+/// RIFF/WAVE to RF64 - RIFF's 32-bit chunk sizes can't address a `data`
+/// chunk this large, and silently truncating them (as a `u32 as` cast
+/// would) produces a file every reader disagrees about the length of.
+/// Set a little under [`u32::MAX`] rather than right at it, so the other
+/// chunks (`fmt `, `smpl`) always fit under the limit too.
+const RF64_THRESHOLD_BYTES: u64 = u32::MAX as u64 - 1_048_576;
+
+/// `0xFFFFFFFF` is RF64's sentinel for "see the `ds64` chunk instead" in
+/// every slot that would otherwise hold a real 32-bit size.
+const RF64_SIZE_SENTINEL: u32 = u32::MAX;
+
+/// Whether `data_size` bytes of PCM data needs RF64 instead of plain
+/// RIFF/WAVE. Split out from [`write_wav`] so the 4 GB boundary decision
+/// is testable without actually writing gigabytes of samples.
+fn needs_rf64(data_size: u64) -> bool {
+    data_size > RF64_THRESHOLD_BYTES
+}
+
+fn write_wav(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    bit_depth: u16,
+    loop_points: Option<LoopPoints>,
+    mut writer: impl Write,
+) -> Result<()> {
+    let bytes_per_sample = (bit_depth / 8) as u64;
+    let block_align = channels as u32 * bytes_per_sample as u32;
+    let byte_rate = sample_rate * block_align;
+    let data_size = samples.len() as u64 * bytes_per_sample;
+    let frame_count = samples.len() as u64 / channels as u64;
+
+    let smpl_chunk = loop_points.map(|loop_points| build_smpl_chunk(sample_rate, loop_points));
+    let smpl_size = smpl_chunk.as_ref().map_or(0u64, |c| 8 + c.len() as u64);
+
+    let use_rf64 = needs_rf64(data_size);
+
+    if use_rf64 {
+        // ds64 payload: 64-bit riffSize, dataSize, sampleCount, then a
+        // table-length of 0 (no per-chunk overrides beyond `data` itself).
+        const DS64_PAYLOAD_SIZE: u64 = 8 + 8 + 8 + 4;
+        let riff_size = 4 + (8 + DS64_PAYLOAD_SIZE) + (8 + 16) + (8 + data_size) + smpl_size;
+
+        writer.write_all(b"RF64")?;
+        writer.write_u32::<LittleEndian>(RF64_SIZE_SENTINEL)?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"ds64")?;
+        writer.write_u32::<LittleEndian>(DS64_PAYLOAD_SIZE as u32)?;
+        writer.write_u64::<LittleEndian>(riff_size)?;
+        writer.write_u64::<LittleEndian>(data_size)?;
+        writer.write_u64::<LittleEndian>(frame_count)?;
+        writer.write_u32::<LittleEndian>(0)?; // table length
+    } else {
+        let riff_size = 4 + (8 + 16) + (8 + data_size as u32) + smpl_size as u32;
+        writer.write_all(b"RIFF")?;
+        writer.write_u32::<LittleEndian>(riff_size)?;
+        writer.write_all(b"WAVE")?;
+    }
+
+    writer.write_all(b"fmt ")?;
+    writer.write_u32::<LittleEndian>(16)?;
+    writer.write_u16::<LittleEndian>(1)?; // PCM
+    writer.write_u16::<LittleEndian>(channels)?;
+    writer.write_u32::<LittleEndian>(sample_rate)?;
+    writer.write_u32::<LittleEndian>(byte_rate)?;
+    writer.write_u16::<LittleEndian>(block_align as u16)?;
+    writer.write_u16::<LittleEndian>(bit_depth)?;
+
+    if let Some(smpl_chunk) = &smpl_chunk {
+        writer.write_all(b"smpl")?;
+        writer.write_u32::<LittleEndian>(smpl_chunk.len() as u32)?;
+        writer.write_all(smpl_chunk)?;
+    }
+
+    writer.write_all(b"data")?;
+    writer.write_u32::<LittleEndian>(if use_rf64 { RF64_SIZE_SENTINEL } else { data_size as u32 })?;
+    for &sample in samples {
+        let sample = sample.clamp(-1.0, 1.0);
+        match bit_depth {
+            8 => writer.write_u8((sample * i8::MAX as f32 + 128.0) as u8)?,
+            16 => writer.write_i16::<LittleEndian>((sample * i16::MAX as f32) as i16)?,
+            24 => {
+                let value = (sample * 8_388_607.0) as i32;
+                writer.write_all(&value.to_le_bytes()[..3])?;
+            }
+            32 => writer.write_i32::<LittleEndian>((sample * i32::MAX as f32) as i32)?,
+            _ => unreachable!("bit depth validated in export_to_writer"),
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write `samples` as an uncompressed AIFF file. The loop region, if any,
+/// is carried as a pair of `MARK` chunk markers referenced by an `INST`
+/// chunk's sustain loop, rather than WAV's single-chunk `smpl` encoding.
+fn write_aiff(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    bit_depth: u16,
+    loop_points: Option<LoopPoints>,
+    mut writer: impl Write,
+) -> Result<()> {
+    let bytes_per_sample = (bit_depth / 8) as u32;
+    let frame_count = samples.len() as u32 / channels as u32;
+    let data_size = samples.len() as u32 * bytes_per_sample;
+
+    let comm_chunk = build_comm_chunk(channels, frame_count, bit_depth, sample_rate);
+    let mark_and_inst = loop_points.map(|loop_points| (build_mark_chunk(loop_points), build_inst_chunk()));
+
+    let ssnd_size = 8 + data_size;
+    let mut form_size = 4; // "AIFF"
+    form_size += chunk_size(&comm_chunk);
+    if let Some((mark_chunk, inst_chunk)) = &mark_and_inst {
+        form_size += chunk_size(mark_chunk);
+        form_size += chunk_size(inst_chunk);
+    }
+    form_size += 8 + ssnd_size + ssnd_size % 2;
+
+    writer.write_all(b"FORM")?;
+    writer.write_u32::<BigEndian>(form_size)?;
+    writer.write_all(b"AIFF")?;
+
+    write_chunk(&mut writer, b"COMM", &comm_chunk)?;
+    if let Some((mark_chunk, inst_chunk)) = &mark_and_inst {
+        write_chunk(&mut writer, b"MARK", mark_chunk)?;
+        write_chunk(&mut writer, b"INST", inst_chunk)?;
+    }
+
+    writer.write_all(b"SSND")?;
+    writer.write_u32::<BigEndian>(ssnd_size)?;
+    writer.write_u32::<BigEndian>(0)?; // data offset
+    writer.write_u32::<BigEndian>(0)?; // block size
+    for &sample in samples {
+        let sample = sample.clamp(-1.0, 1.0);
+        match bit_depth {
+            // Unlike WAV, AIFF's 8-bit samples are signed.
+            8 => writer.write_i8((sample * i8::MAX as f32) as i8)?,
+            16 => writer.write_i16::<BigEndian>((sample * i16::MAX as f32) as i16)?,
+            24 => {
+                let value = (sample * 8_388_607.0) as i32;
+                writer.write_all(&value.to_be_bytes()[1..])?;
+            }
+            32 => writer.write_i32::<BigEndian>((sample * i32::MAX as f32) as i32)?,
+            _ => unreachable!("bit depth validated in export_to_writer"),
+        }
+    }
+    if ssnd_size % 2 == 1 {
+        writer.write_u8(0)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// The size an IFF chunk containing `data` occupies in a file, including
+/// its 8-byte id/size header and the pad byte added when `data` is an odd
+/// length.
+fn chunk_size(data: &[u8]) -> u32 {
+    8 + data.len() as u32 + data.len() as u32 % 2
+}
+
+/// Write one IFF chunk: a 4-byte id, a big-endian size, `data`, and (per
+/// the IFF spec) a zero pad byte if `data`'s length is odd.
+fn write_chunk(writer: &mut impl Write, id: &[u8; 4], data: &[u8]) -> Result<()> {
+    writer.write_all(id)?;
+    writer.write_u32::<BigEndian>(data.len() as u32)?;
+    writer.write_all(data)?;
+    if data.len() % 2 == 1 {
+        writer.write_u8(0)?;
+    }
+    Ok(())
+}
+
+/// Build the payload of an AIFF `COMM` chunk.
+fn build_comm_chunk(channels: u16, frame_count: u32, bit_depth: u16, sample_rate: u32) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(18);
+    chunk.write_i16::<BigEndian>(channels as i16).unwrap();
+    chunk.write_u32::<BigEndian>(frame_count).unwrap();
+    chunk.write_i16::<BigEndian>(bit_depth as i16).unwrap();
+    chunk.extend_from_slice(&ieee_extended_80(sample_rate as f64));
+    chunk
+}
+
+/// Marker IDs for the loop's begin/end markers, referenced by
+/// [`build_inst_chunk`]'s sustain loop.
+const LOOP_START_MARKER_ID: i16 = 1;
+const LOOP_END_MARKER_ID: i16 = 2;
+
+/// Build the payload of an AIFF `MARK` chunk with one marker at
+/// `loop_points.start_frame` and one at `loop_points.end_frame`.
+fn build_mark_chunk(loop_points: LoopPoints) -> Vec<u8> {
+    let mut chunk = Vec::new();
+    chunk.write_u16::<BigEndian>(2).unwrap(); // num_markers
+    write_marker(&mut chunk, LOOP_START_MARKER_ID, loop_points.start_frame as u32, "loop start");
+    write_marker(&mut chunk, LOOP_END_MARKER_ID, loop_points.end_frame as u32, "loop end");
+    chunk
+}
+
+/// Append one AIFF `Marker` record (id, sample-frame position, and a
+/// Pascal-string name) to `chunk`.
+fn write_marker(chunk: &mut Vec<u8>, id: i16, position: u32, name: &str) {
+    chunk.write_i16::<BigEndian>(id).unwrap();
+    chunk.write_u32::<BigEndian>(position).unwrap();
+    chunk.push(name.len() as u8);
+    chunk.extend_from_slice(name.as_bytes());
+    if (name.len() + 1) % 2 == 1 {
+        chunk.push(0);
+    }
+}
+
+/// Build the payload of an AIFF `INST` chunk whose sustain loop points at
+/// the markers [`build_mark_chunk`] wrote, with no release loop and a
+/// full-range, unpitched instrument (callers care about the loop, not
+/// sampler playback mapping).
+fn build_inst_chunk() -> Vec<u8> {
+    const PLAY_MODE_NO_LOOP: i16 = 0;
+    const PLAY_MODE_FORWARD_LOOP: i16 = 1;
+
+    let mut chunk = Vec::with_capacity(20);
+    chunk.write_i8(60).unwrap(); // base_note: middle C
+    chunk.write_i8(0).unwrap(); // detune
+    chunk.write_i8(0).unwrap(); // low_note
+    chunk.write_i8(127).unwrap(); // high_note
+    chunk.write_i8(0).unwrap(); // low_velocity
+    chunk.write_i8(127).unwrap(); // high_velocity
+    chunk.write_i16::<BigEndian>(0).unwrap(); // gain (dB)
+    chunk.write_i16::<BigEndian>(PLAY_MODE_FORWARD_LOOP).unwrap();
+    chunk.write_i16::<BigEndian>(LOOP_START_MARKER_ID).unwrap();
+    chunk.write_i16::<BigEndian>(LOOP_END_MARKER_ID).unwrap();
+    chunk.write_i16::<BigEndian>(PLAY_MODE_NO_LOOP).unwrap(); // release_loop
+    chunk.write_i16::<BigEndian>(0).unwrap();
+    chunk.write_i16::<BigEndian>(0).unwrap();
+    chunk
+}
+
+/// Encode `value` as an 80-bit IEEE 754 extended-precision float, the
+/// format AIFF's `COMM` chunk requires for the sample rate. Only needs to
+/// handle positive, finite, non-zero values (real sample rates).
+fn ieee_extended_80(value: f64) -> [u8; 10] {
+    let mut bytes = [0u8; 10];
+    if value == 0.0 {
+        return bytes;
+    }
+
+    // frexp's mantissa is in [0.5, 1.0); extended precision wants it
+    // normalized to [1.0, 2.0) with an explicit (not implicit, unlike
+    // `f64`) leading one bit, hence the extra factor of two below.
+    let (mantissa, exponent) = frexp(value);
+    let biased_exponent = (exponent + 16382) as u16;
+    let mantissa_bits = (mantissa * 2f64.powi(64)) as u64;
+
+    bytes[0..2].copy_from_slice(&biased_exponent.to_be_bytes());
+    bytes[2..10].copy_from_slice(&mantissa_bits.to_be_bytes());
+    bytes
+}
+
+/// Split a positive, finite `f64` into a mantissa in `[0.5, 1.0)` and an
+/// exponent such that `mantissa * 2^exponent == value`, the same contract
+/// as C's `frexp`, which Rust's standard library doesn't expose.
+fn frexp(value: f64) -> (f64, i32) {
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32 - 1022;
+    let mantissa_bits = (bits & !(0x7ffu64 << 52)) | (1022u64 << 52);
+    (f64::from_bits(mantissa_bits), exponent)
+}
+
+/// Build the payload of a `smpl` chunk containing a single forward loop.
+fn build_smpl_chunk(sample_rate: u32, loop_points: LoopPoints) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(60);
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // manufacturer
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // product
+    chunk
+        .write_u32::<LittleEndian>(1_000_000_000 / sample_rate)
+        .unwrap(); // sample_period (ns)
+    chunk.write_u32::<LittleEndian>(60).unwrap(); // midi_unity_note
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // midi_pitch_fraction
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // smpte_format
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // smpte_offset
+    chunk.write_u32::<LittleEndian>(1).unwrap(); // num_sample_loops
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // sampler_data size
+
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // cue_point_id
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // type: loop forward
+    chunk
+        .write_u32::<LittleEndian>(loop_points.start_frame as u32)
+        .unwrap();
+    chunk
+        .write_u32::<LittleEndian>(loop_points.end_frame as u32)
+        .unwrap();
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // fraction
+    chunk.write_u32::<LittleEndian>(0).unwrap(); // play_count: infinite
+
+    chunk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_rf64_at_exact_threshold_stays_plain_riff() {
+        assert!(!needs_rf64(RF64_THRESHOLD_BYTES));
+    }
+
+    #[test]
+    fn needs_rf64_one_byte_past_threshold_switches_to_rf64() {
+        assert!(needs_rf64(RF64_THRESHOLD_BYTES + 1));
+    }
+
+    #[test]
+    fn write_wav_small_buffer_stays_plain_riff() {
+        let samples = vec![0.0f32; 8];
+        let mut out = Vec::new();
+        write_wav(&samples, 44_100, 2, 16, None, &mut out).unwrap();
+        assert_eq!(&out[0..4], b"RIFF");
+        assert_eq!(&out[8..12], b"WAVE");
+    }
+
+    fn stereo_audio(frame_count: usize) -> AudioData {
+        AudioData {
+            samples: vec![0.0f32; frame_count * 2].into(),
+            sample_rate: 44_100,
+            channels: 2,
+            loop_points: None,
+            replay_gain: None,
+            lyrics: None,
+            visuals: Vec::new(),
+            original_samples: None,
+            original_channels: None,
+            decode_warnings: Default::default(),
+        }
+    }
+
+    #[test]
+    fn export_to_writer_rejects_loop_end_past_frame_count() {
+        let audio = stereo_audio(100);
+        let loop_points = LoopPoints { start_frame: 10, end_frame: 200 };
+        let result = export_to_writer(&audio, Some(loop_points), &ExportSettings::default(), Vec::new());
+        assert!(matches!(result, Err(AbloopError::ExportFailed(_))));
+    }
+
+    #[test]
+    fn export_to_writer_rejects_loop_start_not_before_end() {
+        let audio = stereo_audio(100);
+        let loop_points = LoopPoints { start_frame: 50, end_frame: 50 };
+        let result = export_to_writer(&audio, Some(loop_points), &ExportSettings::default(), Vec::new());
+        assert!(matches!(result, Err(AbloopError::ExportFailed(_))));
+    }
+
+    #[test]
+    fn export_to_writer_accepts_loop_within_range() {
+        let audio = stereo_audio(100);
+        let loop_points = LoopPoints { start_frame: 10, end_frame: 90 };
+        let result = export_to_writer(&audio, Some(loop_points), &ExportSettings::default(), Vec::new());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn write_aiff_emits_form_aiff_header() {
+        let samples = vec![0.0f32; 8];
+        let mut out = Vec::new();
+        write_aiff(&samples, 44_100, 2, 16, None, &mut out).unwrap();
+        assert_eq!(&out[0..4], b"FORM");
+        assert_eq!(&out[8..12], b"AIFF");
+    }
+
+    #[test]
+    fn write_aiff_without_loop_points_omits_mark_and_inst() {
+        let samples = vec![0.0f32; 8];
+        let mut out = Vec::new();
+        write_aiff(&samples, 44_100, 2, 16, None, &mut out).unwrap();
+        assert!(!out.windows(4).any(|w| w == b"MARK"));
+        assert!(!out.windows(4).any(|w| w == b"INST"));
+    }
+
+    #[test]
+    fn write_aiff_with_loop_points_includes_mark_and_inst() {
+        let samples = vec![0.0f32; 8];
+        let loop_points = LoopPoints { start_frame: 1, end_frame: 3 };
+        let mut out = Vec::new();
+        write_aiff(&samples, 44_100, 2, 16, Some(loop_points), &mut out).unwrap();
+        assert!(out.windows(4).any(|w| w == b"MARK"));
+        assert!(out.windows(4).any(|w| w == b"INST"));
+    }
+
+    #[test]
+    fn chunk_size_pads_odd_length_data() {
+        assert_eq!(chunk_size(&[0u8; 3]), 8 + 3 + 1);
+        assert_eq!(chunk_size(&[0u8; 4]), 8 + 4);
+    }
+
+    #[test]
+    fn build_mark_chunk_encodes_both_marker_positions() {
+        let loop_points = LoopPoints { start_frame: 10, end_frame: 2_000 };
+        let chunk = build_mark_chunk(loop_points);
+        let start_position = u32::from_be_bytes(chunk[4..8].try_into().unwrap());
+        assert_eq!(start_position, 10);
+    }
+}
@@ -0,0 +1,206 @@
+//! C-compatible bindings so the loop detector can be embedded in non-Rust
+//! audio tools and game pipelines. Build with `--features ffi`; the crate's
+//! `cdylib` output (always built, see `Cargo.toml`) then exports the
+//! `extern "C"` functions below.
+//!
+//! The general shape: load a file into an opaque [`AbAudioData`] handle,
+//! read its format/samples through [`ab_audio_view`], run
+//! [`ab_detect_loop`], then either read `samples`/loop points yourself or
+//! call [`ab_export_wav`]. Every fallible function returns null/nonzero on
+//! failure, with the message available from [`ab_last_error`].
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_float, c_int};
+use std::ptr;
+
+use crate::analysis::{self, AnalysisSettings};
+use crate::audio::{self, AudioData, LoopPoints};
+use crate::error::AbloopError;
+use crate::export;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(err: &AbloopError) {
+    let message = CString::new(err.to_string()).unwrap_or_default();
+    LAST_ERROR.with(|cell| *cell.borrow_mut() = Some(message));
+}
+
+/// The message from the most recent failing `ab_*` call on this thread, or
+/// null if none has failed yet. Valid until the next `ab_*` call on this
+/// thread; copy it out if you need it to outlive that.
+#[no_mangle]
+pub extern "C" fn ab_last_error() -> *const c_char {
+    LAST_ERROR.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Opaque handle to decoded audio. Free with [`ab_audio_free`].
+pub struct AbAudioData(AudioData);
+
+/// A read-only view of a loaded [`AbAudioData`]'s interleaved `f32` PCM and
+/// format info. `samples` points into memory owned by that handle and is
+/// only valid until it's freed.
+#[repr(C)]
+pub struct AbAudioView {
+    pub samples: *const c_float,
+    pub sample_count: usize,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Tunable parameters for [`ab_detect_loop`], mirroring
+/// [`AnalysisSettings`].
+#[repr(C)]
+pub struct AbAnalysisSettings {
+    pub min_loop_duration_frames: u64,
+    pub correlation_window_frames: usize,
+    pub correlation_threshold: c_float,
+    pub fade_out_threshold_db: c_float,
+}
+
+impl AbAnalysisSettings {
+    fn to_settings(&self) -> Result<AnalysisSettings, AbloopError> {
+        AnalysisSettings::builder()
+            .min_loop_duration_frames(self.min_loop_duration_frames)
+            .correlation_window_frames(self.correlation_window_frames)
+            .correlation_threshold(self.correlation_threshold)
+            .fade_out_threshold_db(self.fade_out_threshold_db)
+            .build()
+    }
+}
+
+/// A loop region, as returned by [`ab_detect_loop`] and accepted by
+/// [`ab_export_wav`]. `found == 0` means no loop point was detected (or
+/// none is being supplied); `start_frame`/`end_frame`/`confidence` are
+/// meaningless in that case.
+#[repr(C)]
+pub struct AbLoopResult {
+    pub found: c_int,
+    pub start_frame: u64,
+    pub end_frame: u64,
+    pub confidence: c_float,
+}
+
+const NOT_FOUND: AbLoopResult = AbLoopResult { found: 0, start_frame: 0, end_frame: 0, confidence: 0.0 };
+
+/// Decode `path` (a NUL-terminated UTF-8 string) into a new
+/// [`AbAudioData`], or null on failure (see [`ab_last_error`]).
+///
+/// # Safety
+/// `path` must be a valid pointer to a NUL-terminated string for the
+/// duration of this call.
+#[no_mangle]
+pub unsafe extern "C" fn ab_audio_load(path: *const c_char) -> *mut AbAudioData {
+    if path.is_null() {
+        set_last_error(&AbloopError::Io { context: "ab_audio_load".to_string(), source: "path is null".into() });
+        return ptr::null_mut();
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(err) => {
+            set_last_error(&AbloopError::Io { context: "path is not valid UTF-8".to_string(), source: Box::new(err) });
+            return ptr::null_mut();
+        }
+    };
+    match audio::load_audio_from_path(path) {
+        Ok(data) => Box::into_raw(Box::new(AbAudioData(data))),
+        Err(err) => {
+            set_last_error(&err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Free audio loaded by [`ab_audio_load`]. Safe to call with null.
+///
+/// # Safety
+/// `data` must be a pointer returned by [`ab_audio_load`] that hasn't
+/// already been freed, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ab_audio_free(data: *mut AbAudioData) {
+    if !data.is_null() {
+        drop(Box::from_raw(data));
+    }
+}
+
+/// Read `data`'s samples and format info.
+///
+/// # Safety
+/// `data` must be a valid, non-null pointer from [`ab_audio_load`].
+#[no_mangle]
+pub unsafe extern "C" fn ab_audio_view(data: *const AbAudioData) -> AbAudioView {
+    let data = &(*data).0;
+    AbAudioView {
+        samples: data.samples.as_ptr(),
+        sample_count: data.samples.len(),
+        sample_rate: data.sample_rate,
+        channels: data.channels,
+    }
+}
+
+/// Run loop detection over `data` with `settings`. Returns a result with
+/// `found == 0` both when no loop point clears the threshold and when
+/// `settings` is invalid (see [`ab_last_error`] to tell those apart).
+///
+/// # Safety
+/// `data` and `settings` must be valid, non-null pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ab_detect_loop(
+    data: *const AbAudioData,
+    settings: *const AbAnalysisSettings,
+) -> AbLoopResult {
+    let settings = match (*settings).to_settings() {
+        Ok(settings) => settings,
+        Err(err) => {
+            set_last_error(&err);
+            return NOT_FOUND;
+        }
+    };
+    match analysis::detect_loop(&(*data).0, &settings).loop_points {
+        Some(candidate) => AbLoopResult {
+            found: 1,
+            start_frame: candidate.start_frame,
+            end_frame: candidate.end_frame,
+            confidence: candidate.confidence,
+        },
+        None => NOT_FOUND,
+    }
+}
+
+/// Export `data` as a looped 16-bit PCM WAV at `path`, embedding
+/// `loop_points` (if non-null and `found != 0`) as a `smpl` chunk loop.
+/// Returns `0` on success, nonzero on failure (see [`ab_last_error`]).
+///
+/// # Safety
+/// `data` and `path` must be valid, non-null pointers; `loop_points` may
+/// be null.
+#[no_mangle]
+pub unsafe extern "C" fn ab_export_wav(
+    data: *const AbAudioData,
+    loop_points: *const AbLoopResult,
+    path: *const c_char,
+) -> c_int {
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(err) => {
+            set_last_error(&AbloopError::Io { context: "path is not valid UTF-8".to_string(), source: Box::new(err) });
+            return -1;
+        }
+    };
+    let loop_points = loop_points.as_ref().and_then(|lp| {
+        (lp.found != 0).then_some(LoopPoints { start_frame: lp.start_frame, end_frame: lp.end_frame })
+    });
+    match export::export_wav(&(*data).0, loop_points, path) {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(&err);
+            -1
+        }
+    }
+}
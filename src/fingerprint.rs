@@ -0,0 +1,208 @@
+//! A lightweight acoustic fingerprint for matching loop points across
+//! re-encodes of the same material - inspired by (but not compatible
+//! with) [Chromaprint](https://acoustid.org/chromaprint): coarse
+//! per-frame spectral energy, hashed into a sequence of 32-bit codes that
+//! tolerates lossy recompression and small timing offsets, unlike
+//! [`crate::loop_db`]'s exact content hash. Build with
+//! `--features fingerprint`.
+//!
+//! [`FingerprintDb`] stores confirmed loop points against fingerprints
+//! instead of exact hashes, using [`similarity`] to find the closest
+//! known match; [`lookup_remote`] queries a community server over HTTP
+//! for installations that want to share a database instead of (or in
+//! addition to) a local one.
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{AudioData, LoopPoints};
+use crate::error::{Context, Result};
+
+const FRAME_SIZE: usize = 4096;
+const HOP_SIZE: usize = 2048;
+/// Log-spaced analysis frequencies, loosely covering the range chroma
+/// features are drawn from (low bass through upper harmonics).
+const BAND_FREQUENCIES_HZ: [f32; 13] = [
+    110.0, 155.0, 220.0, 311.0, 440.0, 622.0, 880.0, 1245.0, 1760.0, 2489.0, 3520.0, 4978.0, 7040.0,
+];
+
+/// Compute a fingerprint for `audio`: one 32-bit code per analysis frame,
+/// each bit the sign of the energy difference between adjacent frequency
+/// bands (per-frame) and, in the high bits, between this frame and the
+/// last (time). Silence in, or at either end of, the track contributes
+/// frames like any other; callers matching a loop tail typically want to
+/// fingerprint the tail window rather than the whole track.
+pub fn fingerprint(audio: &AudioData) -> Vec<u32> {
+    let mono = downmix(audio);
+    let mut codes = Vec::new();
+    let mut previous_bands = [0.0f32; BAND_FREQUENCIES_HZ.len()];
+    let mut frame_start = 0;
+    while frame_start + FRAME_SIZE <= mono.len() {
+        let frame = &mono[frame_start..frame_start + FRAME_SIZE];
+        let bands = band_energies(frame, audio.sample_rate);
+        codes.push(encode_frame(&bands, &previous_bands));
+        previous_bands = bands;
+        frame_start += HOP_SIZE;
+    }
+    codes
+}
+
+fn downmix(audio: &AudioData) -> Vec<f32> {
+    if audio.channels <= 1 {
+        return audio.samples.to_vec();
+    }
+    audio
+        .samples
+        .chunks_exact(audio.channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+fn band_energies(frame: &[f32], sample_rate: u32) -> [f32; BAND_FREQUENCIES_HZ.len()] {
+    let mut bands = [0.0f32; BAND_FREQUENCIES_HZ.len()];
+    for (band, &freq) in bands.iter_mut().zip(BAND_FREQUENCIES_HZ.iter()) {
+        *band = goertzel_magnitude(frame, sample_rate, freq);
+    }
+    bands
+}
+
+/// Single-bin [Goertzel](https://en.wikipedia.org/wiki/Goertzel_algorithm)
+/// magnitude for `target_freq`, used instead of a full FFT since only a
+/// handful of fixed frequencies are needed per frame.
+fn goertzel_magnitude(frame: &[f32], sample_rate: u32, target_freq: f32) -> f32 {
+    let n = frame.len() as f32;
+    let k = (0.5 + n * target_freq / sample_rate as f32).floor();
+    let omega = 2.0 * std::f32::consts::PI * k / n;
+    let coeff = 2.0 * omega.cos();
+    let (mut s_prev, mut s_prev2) = (0.0f32, 0.0f32);
+    for &sample in frame {
+        let s = sample + coeff * s_prev - s_prev2;
+        s_prev2 = s_prev;
+        s_prev = s;
+    }
+    (s_prev2 * s_prev2 + s_prev * s_prev - coeff * s_prev * s_prev2)
+        .max(0.0)
+        .sqrt()
+}
+
+fn encode_frame(bands: &[f32; BAND_FREQUENCIES_HZ.len()], previous_bands: &[f32; BAND_FREQUENCIES_HZ.len()]) -> u32 {
+    let mut code = 0u32;
+    for (i, window) in bands.windows(2).enumerate() {
+        if window[0] > window[1] {
+            code |= 1 << i;
+        }
+    }
+    for (i, (&now, &before)) in bands.iter().zip(previous_bands.iter()).enumerate() {
+        if now > before {
+            code |= 1 << (16 + i);
+        }
+    }
+    code
+}
+
+/// How closely two fingerprints match, from `0.0` (no agreement) to `1.0`
+/// (identical). Tries every frame alignment offset in both directions so
+/// a loop fingerprinted from a slightly different start point still
+/// matches.
+pub fn similarity(a: &[u32], b: &[u32]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let forward = (0..a.len()).map(|offset| offset_similarity(a, b, offset));
+    let backward = (0..b.len()).map(|offset| offset_similarity(b, a, offset));
+    forward.chain(backward).fold(0.0f32, f32::max)
+}
+
+fn offset_similarity(a: &[u32], b: &[u32], offset: usize) -> f32 {
+    let pairs = a.iter().skip(offset).zip(b.iter());
+    let (agreement, count) = pairs.fold((0u32, 0u32), |(agreement, count), (&x, &y)| {
+        (agreement + (32 - (x ^ y).count_ones()), count + 1)
+    });
+    if count == 0 {
+        return 0.0;
+    }
+    agreement as f32 / (count as f32 * 32.0)
+}
+
+/// A confirmed loop point indexed by fingerprint, as persisted in a
+/// [`FingerprintDb`] or served by a community lookup server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FingerprintEntry {
+    fingerprint: Vec<u32>,
+    start_frame: u64,
+    end_frame: u64,
+}
+
+/// A `sled`-backed store of fingerprint -> confirmed loop point, searched
+/// by [`similarity`] rather than exact key lookup.
+pub struct FingerprintDb {
+    tree: sled::Db,
+}
+
+impl FingerprintDb {
+    /// Open (creating if needed) the database at `path`.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let tree = sled::open(path).context(|| "opening fingerprint database".to_string())?;
+        Ok(Self { tree })
+    }
+
+    /// Open the default database at the platform data directory, or
+    /// `None` if the platform has no data directory.
+    pub fn open_default() -> Option<Result<Self>> {
+        default_path().map(Self::open)
+    }
+
+    /// Find the closest match for `query` among stored fingerprints, if
+    /// any clears `threshold` (a [`similarity`] score, `0.0..=1.0`).
+    pub fn lookup(&self, query: &[u32], threshold: f32) -> Result<Option<LoopPoints>> {
+        let mut best: Option<(f32, LoopPoints)> = None;
+        for entry in self.tree.iter().values() {
+            let entry = entry.context(|| "reading fingerprint database".to_string())?;
+            let entry: FingerprintEntry =
+                serde_json::from_slice(&entry).context(|| "decoding fingerprint database entry".to_string())?;
+            let score = similarity(query, &entry.fingerprint);
+            if score >= threshold && best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                best = Some((score, LoopPoints { start_frame: entry.start_frame, end_frame: entry.end_frame }));
+            }
+        }
+        Ok(best.map(|(_, loop_points)| loop_points))
+    }
+
+    /// Store a confirmed fingerprint/loop-point pair.
+    pub fn store(&self, fingerprint: Vec<u32>, loop_points: LoopPoints) -> Result<()> {
+        let id = self.tree.generate_id().context(|| "allocating fingerprint database id".to_string())?;
+        let entry = FingerprintEntry { fingerprint, start_frame: loop_points.start_frame, end_frame: loop_points.end_frame };
+        let value = serde_json::to_vec(&entry).context(|| "encoding fingerprint database entry".to_string())?;
+        self.tree.insert(id.to_be_bytes(), value).context(|| "writing fingerprint database".to_string())?;
+        self.tree.flush().context(|| "flushing fingerprint database".to_string())?;
+        Ok(())
+    }
+}
+
+fn default_path() -> Option<std::path::PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("auto-abloop").join("fingerprints.sled"))
+}
+
+/// Query a community fingerprint server at `base_url` (expected to expose
+/// `POST {base_url}/lookup`, taking `{"fingerprint": [...]}` and replying
+/// `{"start_frame": u64, "end_frame": u64}` or `null`). Kept separate from
+/// [`FingerprintDb`] so callers can use a remote database, a local one, or
+/// both.
+pub fn lookup_remote(base_url: &str, fingerprint: &[u32]) -> Result<Option<LoopPoints>> {
+    #[derive(Deserialize)]
+    struct RemoteMatch {
+        start_frame: u64,
+        end_frame: u64,
+    }
+
+    let body = serde_json::to_string(&serde_json::json!({ "fingerprint": fingerprint }))
+        .context(|| "encoding fingerprint query".to_string())?;
+    let response = ureq::post(&format!("{base_url}/lookup"))
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+        .context(|| format!("querying fingerprint server at {base_url}"))?
+        .into_string()
+        .context(|| "reading fingerprint server response".to_string())?;
+    let response: Option<RemoteMatch> =
+        serde_json::from_str(&response).context(|| "decoding fingerprint server response".to_string())?;
+    Ok(response.map(|found| LoopPoints { start_frame: found.start_frame, end_frame: found.end_frame }))
+}
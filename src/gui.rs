@@ -0,0 +1,1564 @@
+//! The desktop/web GUI, built on `egui`/`eframe`.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc;
+
+use crate::analysis::{self, AnalysisDiff, AnalysisPreset, AnalysisPresetValues};
+use crate::audio::{self, AudioData, PcmFormat};
+use crate::config::{self, NamedPreset};
+#[cfg(feature = "tracing")]
+use crate::diagnostics;
+use crate::error::AbloopError;
+use crate::export::{self, ExportPreset};
+use crate::i18n::{self, Lang};
+use crate::import;
+use crate::report;
+use crate::waveform::{self, PeakPyramid};
+
+/// State for the "Compare analysis" window: two correlation thresholds to
+/// run [`analysis::detect_loop_debug`] with on the loaded file, and the
+/// [`AnalysisDiff`] of their last run (if any).
+struct CompareState {
+    threshold_a: f32,
+    threshold_b: f32,
+    diff: Option<AnalysisDiff>,
+}
+
+impl Default for CompareState {
+    fn default() -> Self {
+        Self {
+            threshold_a: 0.9,
+            threshold_b: 0.8,
+            diff: None,
+        }
+    }
+}
+
+/// What the GUI currently knows about a pending file load.
+enum LoadState {
+    Idle,
+    Loading {
+        label: String,
+        rx: mpsc::Receiver<LoadMessage>,
+        frames_decoded: u64,
+        total_frames: Option<u64>,
+        /// A coarse waveform of the samples decoded so far, redrawn as more
+        /// arrive so a big file shows something other than a spinner while
+        /// it loads; empty until the first throttled update (see
+        /// `PREVIEW_THROTTLE_FRAMES`).
+        preview: Vec<waveform::Peak>,
+    },
+}
+
+enum LoadMessage {
+    Progress {
+        frames_decoded: u64,
+        total_frames: Option<u64>,
+        /// `Some` only on throttled updates; carries an owned snapshot
+        /// since [`DecodeProgress::samples_so_far`] only borrows the
+        /// decoder's buffer for the duration of its callback.
+        preview: Option<Vec<waveform::Peak>>,
+    },
+    Done(Result<AudioData, AbloopError>),
+}
+
+/// How often (in newly decoded frames) to rebuild the in-progress loading
+/// preview. Rebuilding scans every sample decoded so far, so this trades
+/// off how current the preview looks against re-scanning a large, still-
+/// growing buffer too often.
+const PREVIEW_THROTTLE_FRAMES: u64 = 200_000;
+
+/// State for the "Optimize seam" window: the loop point being edited,
+/// seeded from the file's detected or embedded loop (or `0..frame_count` if
+/// it has neither, for placing one by hand), plus the seam cost of the last
+/// [`analysis::optimize_loop_points`] run, if any.
+struct LoopEditorState {
+    start_frame: u64,
+    end_frame: u64,
+    seam_cost: Option<f32>,
+}
+
+/// Which marker a loop editor nudge button moves; see
+/// [`AbloopApp::nudge_loop_editor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoopEditorEdge {
+    Start,
+    End,
+}
+
+/// How many buckets the minimap and the zoomed-in waveform are each
+/// decimated to; both are small, fixed-size strips regardless of window
+/// width, so a coarser resolution than the TUI's per-column Sparkline is
+/// plenty.
+const WAVEFORM_MINIMAP_BUCKETS: usize = 512;
+const WAVEFORM_VIEW_BUCKETS: usize = 1024;
+/// The zoomed-in view never shows less than this many frames, so zooming
+/// in doesn't degenerate into a single, meaningless bucket.
+const WAVEFORM_MIN_VIEW_FRAMES: u64 = 256;
+
+/// The "Waveform" window's zoomed-in viewport: `view_start_frame..
+/// view_end_frame` of the loaded track. Reset to the whole track whenever
+/// a new file loads; the minimap above the zoomed view always shows the
+/// full track with this range highlighted, draggable to scroll.
+struct WaveformViewState {
+    view_start_frame: u64,
+    view_end_frame: u64,
+}
+
+impl WaveformViewState {
+    fn full(frame_count: u64) -> Self {
+        Self { view_start_frame: 0, view_end_frame: frame_count.max(1) }
+    }
+}
+
+/// Pending state for the "Import raw PCM" dialog.
+struct RawPcmImport {
+    bytes: Vec<u8>,
+    sample_rate: u32,
+    channels: u16,
+    format: PcmFormat,
+}
+
+/// Top-level application state for the `eframe` window.
+pub struct AbloopApp {
+    audio: Option<AudioData>,
+    /// The currently loaded file, if any - used to derive its sidecar
+    /// path and, on every successful load, saved as [`config::Session`]'s
+    /// `last_file` so [`config::Config::restore_last_session`] can reopen
+    /// it next startup.
+    current_path: Option<std::path::PathBuf>,
+    status: String,
+    show_open_url: bool,
+    url_input: String,
+    load_state: LoadState,
+    raw_pcm_import: Option<RawPcmImport>,
+    /// Selected via the "Language" menu; drives the status strings this app
+    /// prints through [`crate::i18n`], the same table the CLI uses. The
+    /// preset and preference status messages are English-only.
+    lang: Lang,
+    show_compare: bool,
+    compare: CompareState,
+    show_loop_editor: bool,
+    loop_editor: LoopEditorState,
+    show_waveform: bool,
+    waveform_view: WaveformViewState,
+    /// Full-track peaks for the minimap, rebuilt whenever a new file
+    /// loads; `None` until then.
+    waveform_pyramid: Option<PeakPyramid>,
+    /// Whether the "Waveform" window overlays [`analysis::DebugSignals`]
+    /// (correlation curve + fade-out RMS history) on top of the peaks.
+    show_debug_signals: bool,
+    /// Computed lazily the first time `show_debug_signals` is turned on
+    /// for a given file, with the default [`analysis::AnalysisSettings`];
+    /// cleared on every new load so it's never shown stale for a
+    /// different file.
+    debug_signals: Option<analysis::DebugSignals>,
+    /// Draw the zoomed-in waveform as one stacked lane per channel instead
+    /// of a single min/max mix across all of them, so a loop seam that's
+    /// only bad on one channel is visible.
+    per_channel_waveform: bool,
+    /// Estimated tempo of the loaded file ([`analysis::estimate_bpm`]),
+    /// recomputed on every load; `None` for a track too short or too
+    /// aperiodic to estimate from.
+    waveform_bpm: Option<f32>,
+    /// Overlay a bars:beats grid (assuming 4/4 time, the same assumption
+    /// [`crate::player::mix_metronome`] makes) on the zoomed-in waveform.
+    show_beat_grid: bool,
+    show_missing_translations: bool,
+    /// Accumulated from [`i18n::take_missing_translations`] once per
+    /// frame, so a miss stays visible in the window even after the frame
+    /// it was recorded on has passed.
+    missing_translations: Vec<(Lang, &'static str)>,
+    /// The analysis settings and export options last applied via a preset
+    /// (built-in or user-defined) or an "Export" menu action - the
+    /// candidate bundle for "Save preset as...", since the GUI has no
+    /// general-purpose settings editor of its own.
+    current_preset: NamedPreset,
+    /// Loaded from [`config::defaults`] at startup and updated in place as
+    /// presets are saved this session, so "Apply preset" and "Save preset
+    /// as..." see the latest list without re-reading the config file (or
+    /// mutating its process-wide cache) on every frame.
+    presets: BTreeMap<String, NamedPreset>,
+    show_save_preset: bool,
+    save_preset_name: String,
+    /// Mirrors [`config::Config::restore_last_session`]; toggled from the
+    /// "File" menu and written straight through to the config file, the
+    /// same immediate-persist pattern as [`Self::save_current_preset`].
+    restore_last_session: bool,
+    /// Sink [`diagnostics::install`] writes analysis phase timings into, so
+    /// the "Diagnostics" window can show where time went for the loaded
+    /// file. Only present when the `tracing` feature is enabled.
+    #[cfg(feature = "tracing")]
+    phase_timings: diagnostics::PhaseTimings,
+    #[cfg(feature = "tracing")]
+    show_diagnostics: bool,
+}
+
+impl Default for AbloopApp {
+    fn default() -> Self {
+        let mut app = Self {
+            audio: None,
+            current_path: None,
+            status: i18n::gui_no_file_loaded(Lang::from_args_or_env(None)).to_string(),
+            show_open_url: false,
+            url_input: String::new(),
+            load_state: LoadState::Idle,
+            raw_pcm_import: None,
+            lang: Lang::from_args_or_env(None),
+            show_compare: false,
+            compare: CompareState::default(),
+            show_loop_editor: false,
+            loop_editor: LoopEditorState { start_frame: 0, end_frame: 0, seam_cost: None },
+            show_waveform: false,
+            waveform_view: WaveformViewState::full(1),
+            waveform_pyramid: None,
+            show_debug_signals: false,
+            per_channel_waveform: false,
+            waveform_bpm: None,
+            show_beat_grid: false,
+            debug_signals: None,
+            show_missing_translations: false,
+            missing_translations: Vec::new(),
+            current_preset: NamedPreset::default(),
+            presets: config::defaults().presets.clone(),
+            show_save_preset: false,
+            save_preset_name: String::new(),
+            restore_last_session: config::defaults().restore_last_session,
+            #[cfg(feature = "tracing")]
+            phase_timings: diagnostics::install(),
+            #[cfg(feature = "tracing")]
+            show_diagnostics: false,
+        };
+        if config::defaults().restore_last_session {
+            if let Some(last_file) = config::load_session().last_file {
+                app.load_path(last_file);
+            }
+        }
+        app
+    }
+}
+
+impl AbloopApp {
+    fn open_file_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().pick_file() {
+            self.load_path(path);
+        }
+    }
+
+    fn load_path(&mut self, path: std::path::PathBuf) {
+        self.current_path = Some(path.clone());
+        let (tx, rx) = mpsc::channel();
+        let label = i18n::gui_reading(self.lang, &path.display().to_string());
+        std::thread::spawn(move || {
+            let progress_tx = tx.clone();
+            let mut previewed_at = 0u64;
+            let result = audio::load_audio_from_path_with_progress(
+                &path,
+                audio::LoadOptions::default(),
+                move |progress| {
+                    let due = progress.frames_decoded.saturating_sub(previewed_at) >= PREVIEW_THROTTLE_FRAMES;
+                    let preview = due.then(|| {
+                        previewed_at = progress.frames_decoded;
+                        waveform::peaks_from_samples(
+                            progress.samples_so_far,
+                            progress.channels,
+                            WAVEFORM_MINIMAP_BUCKETS,
+                        )
+                    });
+                    let _ = progress_tx.send(LoadMessage::Progress {
+                        frames_decoded: progress.frames_decoded,
+                        total_frames: progress.total_frames,
+                        preview,
+                    });
+                },
+            );
+            let _ = tx.send(LoadMessage::Done(result));
+        });
+        self.load_state = LoadState::Loading {
+            label,
+            rx,
+            frames_decoded: 0,
+            total_frames: None,
+            preview: Vec::new(),
+        };
+    }
+
+    fn open_raw_pcm_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Raw PCM", &["pcm", "raw"])
+            .pick_file()
+        {
+            match std::fs::read(&path) {
+                Ok(bytes) => {
+                    self.raw_pcm_import = Some(RawPcmImport {
+                        bytes,
+                        sample_rate: 44_100,
+                        channels: 2,
+                        format: PcmFormat::S16Le,
+                    });
+                }
+                Err(err) => {
+                    self.status = i18n::gui_failed_to_read(self.lang, &path.display().to_string(), &err)
+                }
+            }
+        }
+    }
+
+    /// Open a file dialog for a loop import file (JSON sidecar, Audacity
+    /// label track, or plain `start,end` text; see
+    /// [`crate::import::parse_loop_points`]) and apply the result to
+    /// the loaded audio in place of detection.
+    fn import_loop_points_dialog(&mut self) {
+        let Some(path) = rfd::FileDialog::new().pick_file() else {
+            return;
+        };
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+        match import::import_loop_points(&path, audio.sample_rate) {
+            Ok(loop_points) => {
+                self.status =
+                    i18n::gui_imported_loop_points(self.lang, loop_points.start_frame, loop_points.end_frame);
+                audio.loop_points = Some(loop_points);
+            }
+            Err(err) => self.status = i18n::gui_failed_to_import_loop_points(self.lang, &err),
+        }
+    }
+
+    fn load_url(&mut self, url: &str) {
+        match audio::load_audio_from_url(url) {
+            Ok(data) => {
+                self.status = i18n::gui_loaded_url(self.lang, url);
+                self.set_audio(data);
+            }
+            Err(err) => self.status = i18n::gui_failed_to_fetch(self.lang, url, &err),
+        }
+    }
+
+    /// Adopt a newly loaded file: store it and reset the waveform view
+    /// (minimap pyramid + zoomed viewport) to show the whole thing. If the
+    /// file has no loop embedded in it, fill in one from its `.abloop.json`
+    /// sidecar if one exists next to it. Also records this file as the
+    /// session's `last_file`, so a startup with
+    /// [`config::Config::restore_last_session`] on reopens it next time.
+    fn set_audio(&mut self, mut data: AudioData) {
+        if data.loop_points.is_none() {
+            if let Some(path) = &self.current_path {
+                let sidecar = import::sidecar_path(path);
+                if let Ok(loop_points) = import::import_loop_points(&sidecar, data.sample_rate) {
+                    data.loop_points = Some(loop_points);
+                }
+            }
+        }
+        self.waveform_view = WaveformViewState::full(data.frame_count());
+        self.waveform_pyramid = Some(PeakPyramid::build(&data, WAVEFORM_MINIMAP_BUCKETS));
+        self.waveform_bpm = analysis::estimate_bpm(&data);
+        self.debug_signals = None;
+        self.audio = Some(data);
+        if let Some(path) = self.current_path.clone() {
+            let session = config::Session { last_file: Some(path) };
+            if let Err(err) = config::save_session(&session) {
+                log::warn!("failed to save session: {err}");
+            }
+        }
+    }
+
+    /// Drain any pending progress/completion messages from a background load.
+    fn poll_load_state(&mut self) {
+        let LoadState::Loading {
+            rx,
+            frames_decoded,
+            total_frames,
+            preview,
+            ..
+        } = &mut self.load_state
+        else {
+            return;
+        };
+
+        let mut finished = None;
+        while let Ok(message) = rx.try_recv() {
+            match message {
+                LoadMessage::Progress { frames_decoded: decoded, total_frames: total, preview: update } => {
+                    *frames_decoded = decoded;
+                    *total_frames = total;
+                    if let Some(update) = update {
+                        *preview = update;
+                    }
+                }
+                LoadMessage::Done(result) => finished = Some(result),
+            }
+        }
+
+        if let Some(result) = finished {
+            match result {
+                Ok(data) => {
+                    self.status = i18n::gui_loaded_info(
+                        self.lang,
+                        data.sample_rate,
+                        data.channels,
+                        data.frame_count(),
+                    );
+                    self.set_audio(data);
+                }
+                Err(err) => self.status = i18n::gui_failed_to_load(self.lang, &err),
+            }
+            self.load_state = LoadState::Idle;
+        }
+    }
+
+    /// Run [`analysis::detect_loop_debug`] on the loaded file with
+    /// `compare.threshold_a`/`threshold_b` (all other settings at their
+    /// default), and store the [`AnalysisDiff`] between the two runs.
+    fn run_compare(&mut self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        let settings = |threshold: f32| {
+            analysis::AnalysisSettings::builder()
+                .correlation_threshold(threshold)
+                .build()
+                .unwrap_or_default()
+        };
+        let debug_a = analysis::detect_loop_debug(audio, &settings(self.compare.threshold_a), 20);
+        let debug_b = analysis::detect_loop_debug(audio, &settings(self.compare.threshold_b), 20);
+        self.compare.diff = Some(analysis::diff_analysis(&debug_a, &debug_b));
+    }
+
+    /// Detect (or reuse an already-embedded) loop point on the loaded
+    /// file, ask where to save via a native file dialog, and export it
+    /// with `preset`'s bundled settings.
+    fn export_with_preset(&mut self, preset: ExportPreset) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        if audio.sample_rate != preset.expected_sample_rate() {
+            log::warn!(
+                "{} Hz doesn't match the {} preset's expected {} Hz; exporting without resampling",
+                audio.sample_rate,
+                preset.name(),
+                preset.expected_sample_rate()
+            );
+        }
+        let settings = preset.settings();
+        self.current_preset.export_format = settings.format;
+        self.current_preset.bit_depth = settings.bit_depth;
+        let loop_points = analysis::detect_loop(audio, &analysis::AnalysisSettings::default())
+            .loop_points
+            .map(|candidate| audio::LoopPoints {
+                start_frame: candidate.start_frame,
+                end_frame: candidate.end_frame,
+            })
+            .or(audio.loop_points);
+
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(format!("export.{}", settings.format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        match export::export(audio, loop_points, &settings, &path) {
+            Ok(()) => self.status = i18n::gui_exported(self.lang, &path.display().to_string()),
+            Err(err) => self.status = i18n::gui_failed_to_export(self.lang, &err),
+        }
+    }
+
+    /// Run detection on the loaded file and write a single-row CSV/HTML
+    /// report (the same shape the CLI's `export --report` writes for a
+    /// whole batch) to a path chosen via a native save dialog.
+    fn export_report(&mut self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        let row = report::ReportRow::new("(loaded file)", audio, &analysis::AnalysisSettings::default());
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name("report.csv")
+            .add_filter("CSV", &["csv"])
+            .add_filter("HTML", &["html"])
+            .save_file()
+        else {
+            return;
+        };
+        match report::write_report(&path, &[row]) {
+            Ok(()) => self.status = i18n::gui_report_written(self.lang, &path.display().to_string()),
+            Err(err) => self.status = i18n::gui_failed_to_write_report(self.lang, &err),
+        }
+    }
+
+    /// Compute (once per loaded file, lazily) the analysis-internal curves
+    /// behind detection, for the "Waveform" window's debug-signal overlay
+    /// toggle. Uses default [`analysis::AnalysisSettings`] - this is about
+    /// visualizing how detection reasons about the track in general, not
+    /// reproducing whatever settings a previous run used.
+    fn ensure_debug_signals(&mut self) {
+        if self.debug_signals.is_some() {
+            return;
+        }
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        let debug = analysis::detect_loop_debug(audio, &analysis::AnalysisSettings::default(), 0);
+        self.debug_signals = Some(debug.signals);
+    }
+
+    /// Re-detect the loaded file's loop point under a built-in
+    /// [`AnalysisPreset`]'s settings, adopting whatever it finds.
+    fn apply_builtin_preset(&mut self, preset: AnalysisPreset) {
+        self.apply_preset_values(preset.name(), preset.values());
+    }
+
+    /// Like [`Self::apply_builtin_preset`], but for a user-defined preset
+    /// saved from the GUI (or by hand under `[presets.<name>]` in the
+    /// config file), which also adopts its export options - see
+    /// [`Self::current_preset`].
+    fn apply_named_preset(&mut self, name: &str) {
+        let Some(preset) = self.presets.get(name).copied() else {
+            return;
+        };
+        self.current_preset = preset;
+        self.apply_preset_values(name, preset.analysis);
+    }
+
+    fn apply_preset_values(&mut self, label: &str, values: AnalysisPresetValues) {
+        self.current_preset.analysis = values;
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+        let settings = match values.into_settings(audio.sample_rate) {
+            Ok(settings) => settings,
+            Err(err) => {
+                self.status = format!("invalid preset {label}: {err}");
+                return;
+            }
+        };
+        let result = analysis::detect_loop(audio, &settings);
+        match result.loop_points {
+            Some(candidate) => {
+                audio.loop_points = Some(audio::LoopPoints {
+                    start_frame: candidate.start_frame,
+                    end_frame: candidate.end_frame,
+                });
+                self.status = format!(
+                    "{label}: loop {}..{} ({:.3} confidence)",
+                    candidate.start_frame, candidate.end_frame, candidate.confidence
+                );
+            }
+            None => {
+                self.status =
+                    format!("{label}: no loop found ({})", i18n::loop_detection_outcome_reason(self.lang, result.outcome));
+            }
+        }
+    }
+
+    /// Save [`Self::current_preset`] under `name`, in this session's
+    /// preset list and in the config file, so it shows up in "Apply
+    /// preset" (here and from the CLI's `--preset`) from now on.
+    fn save_current_preset(&mut self, name: String) {
+        self.presets.insert(name.clone(), self.current_preset);
+        let mut config = config::defaults().clone();
+        config.presets = self.presets.clone();
+        config.restore_last_session = self.restore_last_session;
+        match config::save(&config) {
+            Ok(()) => self.status = format!("saved preset {name:?}"),
+            Err(err) => self.status = format!("failed to save preset {name:?}: {err}"),
+        }
+    }
+
+    /// Persist [`config::Config::restore_last_session`] immediately when the
+    /// "Reopen last file on startup" checkbox is toggled.
+    fn set_restore_last_session(&mut self, enabled: bool) {
+        let mut config = config::defaults().clone();
+        config.presets = self.presets.clone();
+        config.restore_last_session = enabled;
+        if let Err(err) = config::save(&config) {
+            self.status = format!("failed to save preference: {err}");
+        }
+    }
+
+    /// Open the "Optimize seam" window, seeded with the loaded file's
+    /// detected or embedded loop point, or `0..frame_count` if it has
+    /// neither, so there's always something sensible to nudge or overwrite
+    /// by hand.
+    fn open_loop_editor(&mut self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        let seed = analysis::detect_loop(audio, &analysis::AnalysisSettings::default())
+            .loop_points
+            .map(|candidate| audio::LoopPoints {
+                start_frame: candidate.start_frame,
+                end_frame: candidate.end_frame,
+            })
+            .or(audio.loop_points)
+            .unwrap_or(audio::LoopPoints { start_frame: 0, end_frame: audio.frame_count() });
+        self.loop_editor = LoopEditorState {
+            start_frame: seed.start_frame,
+            end_frame: seed.end_frame,
+            seam_cost: None,
+        };
+        self.show_loop_editor = true;
+    }
+
+    /// Run [`analysis::optimize_loop_points`] on the editor's current
+    /// start/end and adopt whatever it comes back with.
+    fn optimize_seam(&mut self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        let optimized = analysis::optimize_loop_points(
+            audio,
+            audio::LoopPoints {
+                start_frame: self.loop_editor.start_frame,
+                end_frame: self.loop_editor.end_frame,
+            },
+        );
+        self.loop_editor.start_frame = optimized.start_frame;
+        self.loop_editor.end_frame = optimized.end_frame;
+        self.loop_editor.seam_cost = Some(optimized.seam_cost);
+    }
+
+    /// Nudge the editor's start or end marker by `delta_frames` (negative
+    /// moves it earlier) and immediately recompute its seam cost, so the
+    /// score updates the instant a nudge button is clicked. The GUI has no
+    /// live audio output to actually play the seam, so this score - the
+    /// same click-discontinuity proxy [`analysis::optimize_loop_points`]
+    /// minimizes - stands in as the "audition": the fastest feedback this
+    /// window can give without inventing GUI playback from scratch.
+    fn nudge_loop_editor(&mut self, edge: LoopEditorEdge, delta_frames: i64) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        let frame_count = audio.frame_count();
+        let field = match edge {
+            LoopEditorEdge::Start => &mut self.loop_editor.start_frame,
+            LoopEditorEdge::End => &mut self.loop_editor.end_frame,
+        };
+        *field = (*field as i64 + delta_frames).clamp(0, frame_count as i64) as u64;
+        self.loop_editor.seam_cost = Some(analysis::seam_cost_at(
+            audio,
+            audio::LoopPoints {
+                start_frame: self.loop_editor.start_frame,
+                end_frame: self.loop_editor.end_frame,
+            },
+        ));
+    }
+
+    /// Snap the editor's `edge` to the nearest frame on the track's
+    /// estimated beat grid (see [`analysis::beat_period_frames`]), then
+    /// recompute its seam cost the same way [`Self::nudge_loop_editor`]
+    /// does. A no-op if no tempo could be estimated for the loaded file.
+    fn snap_loop_editor(&mut self, edge: LoopEditorEdge) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        let Some(bpm) = self.waveform_bpm else {
+            return;
+        };
+        let beat_period_frames = analysis::beat_period_frames(bpm, audio.sample_rate);
+        if beat_period_frames == 0 {
+            return;
+        }
+        let frame_count = audio.frame_count();
+        let field = match edge {
+            LoopEditorEdge::Start => &mut self.loop_editor.start_frame,
+            LoopEditorEdge::End => &mut self.loop_editor.end_frame,
+        };
+        let nearest_beat = (*field + beat_period_frames / 2) / beat_period_frames;
+        *field = (nearest_beat * beat_period_frames).min(frame_count);
+        self.loop_editor.seam_cost = Some(analysis::seam_cost_at(
+            audio,
+            audio::LoopPoints {
+                start_frame: self.loop_editor.start_frame,
+                end_frame: self.loop_editor.end_frame,
+            },
+        ));
+    }
+
+    /// Adopt the editor's current start/end as the loaded file's loop
+    /// point, e.g. so a later export picks it up.
+    fn apply_loop_editor(&mut self) {
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+        audio.loop_points = Some(audio::LoopPoints {
+            start_frame: self.loop_editor.start_frame,
+            end_frame: self.loop_editor.end_frame,
+        });
+    }
+
+    /// Draw the "Diagnostics" window: how long each analysis phase took on
+    /// its last run, from [`diagnostics::PhaseTimings`]. Only built with
+    /// the `tracing` feature.
+    #[cfg(feature = "tracing")]
+    fn draw_diagnostics_window(&mut self, ctx: &egui::Context) {
+        if !self.show_diagnostics {
+            return;
+        }
+        let mut open = self.show_diagnostics;
+        egui::Window::new("Diagnostics").open(&mut open).show(ctx, |ui| {
+            let timings = self.phase_timings.recent();
+            if timings.is_empty() {
+                ui.label("No analysis has run yet this session.");
+            } else {
+                egui::Grid::new("diagnostics_phase_timings").striped(true).show(ui, |ui| {
+                    ui.strong("Phase");
+                    ui.strong("Last run");
+                    ui.end_row();
+                    for timing in &timings {
+                        ui.label(timing.name);
+                        ui.label(format!("{:.1} ms", timing.duration.as_secs_f64() * 1000.0));
+                        ui.end_row();
+                    }
+                });
+            }
+        });
+        self.show_diagnostics = open;
+    }
+
+    /// Draw the "Waveform" window: a full-track minimap (drag to scroll)
+    /// above a zoomed-in view of [`WaveformViewState`]'s current range,
+    /// both with loop markers indicated.
+    fn draw_waveform_window(&mut self, ctx: &egui::Context) {
+        if !self.show_waveform {
+            return;
+        }
+        let Some(audio) = &self.audio else {
+            self.show_waveform = false;
+            return;
+        };
+        let Some(pyramid) = &self.waveform_pyramid else {
+            return;
+        };
+        let frame_count = audio.frame_count().max(1);
+        let loop_points = audio.loop_points;
+        let minimap_peaks = pyramid.peaks(WAVEFORM_MINIMAP_BUCKETS);
+        let view_peaks = waveform::peaks_in_range(
+            audio,
+            self.waveform_view.view_start_frame,
+            self.waveform_view.view_end_frame,
+            WAVEFORM_VIEW_BUCKETS,
+        );
+
+        let mut open = self.show_waveform;
+        let mut drag_to_frame = None;
+        let mut zoom_factor = None;
+        let mut reset = false;
+        let mut enable_debug_signals = false;
+        let signals = self.debug_signals.as_ref();
+
+        egui::Window::new("Waveform")
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                if ui
+                    .checkbox(&mut self.show_debug_signals, "Show analysis signals (debug)")
+                    .on_hover_text(
+                        "Overlay the coarse correlation search (yellow) and the fade-out \
+                         detector's RMS history (orange) used to find this file's loop point.",
+                    )
+                    .changed()
+                    && self.show_debug_signals
+                {
+                    enable_debug_signals = true;
+                }
+                ui.checkbox(&mut self.per_channel_waveform, "Per-channel lanes")
+                    .on_hover_text("Draw the zoomed-in view as one lane per channel instead of a combined mix.");
+                ui.add_enabled(self.waveform_bpm.is_some(), egui::Checkbox::new(&mut self.show_beat_grid, "Beat gridlines"))
+                    .on_hover_text(match self.waveform_bpm {
+                        Some(bpm) => format!("Bars:beats ruler at the track's estimated tempo (~{bpm:.0} BPM), assuming 4/4 time."),
+                        None => "No tempo could be estimated for this track.".to_string(),
+                    });
+
+                ui.label("Minimap (drag to scroll)");
+                let (minimap_rect, minimap_response) =
+                    paint_waveform_strip(ui, &minimap_peaks, 48.0, egui::Color32::from_gray(110));
+                paint_viewport_overlay(
+                    ui.painter(),
+                    minimap_rect,
+                    frame_count,
+                    self.waveform_view.view_start_frame,
+                    self.waveform_view.view_end_frame,
+                );
+                paint_loop_markers(ui.painter(), minimap_rect, 0, frame_count, loop_points);
+                if let Some(signals) = signals.filter(|_| self.show_debug_signals) {
+                    paint_debug_signals(ui.painter(), minimap_rect, signals, 0, frame_count);
+                }
+                if minimap_response.dragged() {
+                    if let Some(pos) = minimap_response.interact_pointer_pos() {
+                        let fraction =
+                            ((pos.x - minimap_rect.left()) / minimap_rect.width().max(1.0)).clamp(0.0, 1.0);
+                        drag_to_frame = Some((fraction as f64 * frame_count as f64) as u64);
+                    }
+                }
+
+                ui.separator();
+                let view_len = self.waveform_view.view_end_frame - self.waveform_view.view_start_frame;
+                ui.label(format!(
+                    "Zoomed: frame {} .. {} ({view_len} frames)",
+                    self.waveform_view.view_start_frame, self.waveform_view.view_end_frame,
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Zoom in").clicked() {
+                        zoom_factor = Some(0.5);
+                    }
+                    if ui.button("Zoom out").clicked() {
+                        zoom_factor = Some(2.0);
+                    }
+                    if ui.button("Reset").clicked() {
+                        reset = true;
+                    }
+                });
+                let beat_period_frames = self
+                    .show_beat_grid
+                    .then_some(self.waveform_bpm)
+                    .flatten()
+                    .map(|bpm| analysis::beat_period_frames(bpm, audio.sample_rate));
+                let mut any_hovered = false;
+                if self.per_channel_waveform && audio.channels > 1 {
+                    let channel_peaks = waveform::peaks_in_range_per_channel(
+                        audio,
+                        self.waveform_view.view_start_frame,
+                        self.waveform_view.view_end_frame,
+                        WAVEFORM_VIEW_BUCKETS,
+                    );
+                    let lane_height = 120.0 / channel_peaks.len().max(1) as f32;
+                    for (channel, peaks) in channel_peaks.iter().enumerate() {
+                        let (lane_rect, lane_response) =
+                            paint_waveform_strip(ui, peaks, lane_height, waveform_channel_color(channel));
+                        paint_loop_markers(
+                            ui.painter(),
+                            lane_rect,
+                            self.waveform_view.view_start_frame,
+                            self.waveform_view.view_end_frame,
+                            loop_points,
+                        );
+                        if let Some(beat_period_frames) = beat_period_frames {
+                            paint_beat_grid(
+                                ui.painter(),
+                                lane_rect,
+                                self.waveform_view.view_start_frame,
+                                self.waveform_view.view_end_frame,
+                                beat_period_frames,
+                            );
+                        }
+                        any_hovered |= lane_response.hovered();
+                    }
+                } else {
+                    let (view_rect, view_response) =
+                        paint_waveform_strip(ui, &view_peaks, 120.0, egui::Color32::from_rgb(100, 170, 255));
+                    paint_loop_markers(
+                        ui.painter(),
+                        view_rect,
+                        self.waveform_view.view_start_frame,
+                        self.waveform_view.view_end_frame,
+                        loop_points,
+                    );
+                    if let Some(beat_period_frames) = beat_period_frames {
+                        paint_beat_grid(
+                            ui.painter(),
+                            view_rect,
+                            self.waveform_view.view_start_frame,
+                            self.waveform_view.view_end_frame,
+                            beat_period_frames,
+                        );
+                    }
+                    if let Some(signals) = signals.filter(|_| self.show_debug_signals) {
+                        paint_debug_signals(
+                            ui.painter(),
+                            view_rect,
+                            signals,
+                            self.waveform_view.view_start_frame,
+                            self.waveform_view.view_end_frame,
+                        );
+                    }
+                    any_hovered = view_response.hovered();
+                }
+                if any_hovered {
+                    let scroll = ui.input(|i| i.raw_scroll_delta.y);
+                    if scroll > 0.0 {
+                        zoom_factor = Some(0.8);
+                    } else if scroll < 0.0 {
+                        zoom_factor = Some(1.25);
+                    }
+                }
+            });
+        self.show_waveform = open;
+        if enable_debug_signals {
+            self.ensure_debug_signals();
+        }
+
+        if let Some(frame) = drag_to_frame {
+            self.scroll_waveform_view_to(frame, frame_count);
+        }
+        if let Some(factor) = zoom_factor {
+            self.zoom_waveform_view(factor, frame_count);
+        }
+        if reset {
+            self.waveform_view = WaveformViewState::full(frame_count);
+        }
+    }
+
+    /// Recenter the zoomed view on `frame`, keeping its current width -
+    /// what dragging the minimap's viewport does.
+    fn scroll_waveform_view_to(&mut self, frame: u64, frame_count: u64) {
+        let width = (self.waveform_view.view_end_frame - self.waveform_view.view_start_frame).min(frame_count);
+        let half = width / 2;
+        let start = frame.saturating_sub(half).min(frame_count.saturating_sub(width));
+        self.waveform_view.view_start_frame = start;
+        self.waveform_view.view_end_frame = start + width;
+    }
+
+    /// Scale the zoomed view's width by `factor` (`<1` zooms in, `>1`
+    /// zooms out), keeping its center fixed and clamping width to
+    /// `WAVEFORM_MIN_VIEW_FRAMES..=frame_count`.
+    fn zoom_waveform_view(&mut self, factor: f64, frame_count: u64) {
+        let start = self.waveform_view.view_start_frame;
+        let end = self.waveform_view.view_end_frame;
+        let center = start + (end - start) / 2;
+        let min_width = WAVEFORM_MIN_VIEW_FRAMES.min(frame_count);
+        let width = (((end - start) as f64 * factor) as u64).clamp(min_width, frame_count);
+        let half = width / 2;
+        let start = center.saturating_sub(half).min(frame_count.saturating_sub(width));
+        self.waveform_view.view_start_frame = start;
+        self.waveform_view.view_end_frame = start + width;
+    }
+}
+
+/// A row of "-10ms/-1ms/-1/+1/+1ms/+10ms" buttons for one loop marker.
+/// Clicking one records `(edge, delta_frames)` into `nudge` rather than
+/// mutating state directly, so the caller can apply it (and recompute the
+/// seam cost) once after the whole window has finished drawing.
+fn nudge_buttons(ui: &mut egui::Ui, edge: LoopEditorEdge, ms_frames: i64, nudge: &mut Option<(LoopEditorEdge, i64)>) {
+    ui.horizontal(|ui| {
+        for (label, delta_frames) in [
+            ("-10ms", -10 * ms_frames),
+            ("-1ms", -ms_frames),
+            ("-1", -1),
+            ("+1", 1),
+            ("+1ms", ms_frames),
+            ("+10ms", 10 * ms_frames),
+        ] {
+            if ui.button(label).clicked() {
+                *nudge = Some((edge, delta_frames));
+            }
+        }
+    });
+}
+
+/// A distinct color for each per-channel waveform lane, cycling past
+/// stereo's two colors for anything with more channels.
+fn waveform_channel_color(channel: usize) -> egui::Color32 {
+    const COLORS: [egui::Color32; 4] = [
+        egui::Color32::from_rgb(100, 170, 255),
+        egui::Color32::from_rgb(255, 170, 100),
+        egui::Color32::from_rgb(140, 220, 140),
+        egui::Color32::from_rgb(220, 140, 220),
+    ];
+    COLORS[channel % COLORS.len()]
+}
+
+/// Paint `peaks` as a min/max bar strip spanning the available width at
+/// `height`, and allocate it as a draggable response so callers can turn
+/// drags into a scroll (the minimap) or just ignore it (the zoomed view).
+fn paint_waveform_strip(
+    ui: &mut egui::Ui,
+    peaks: &[waveform::Peak],
+    height: f32,
+    color: egui::Color32,
+) -> (egui::Rect, egui::Response) {
+    let width = ui.available_width();
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, height), egui::Sense::drag());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+    let mid_y = rect.center().y;
+    let half_height = rect.height() / 2.0;
+    let bucket_width = rect.width() / peaks.len().max(1) as f32;
+    for (index, &(min, max)) in peaks.iter().enumerate() {
+        let x = rect.left() + index as f32 * bucket_width;
+        let y_top = mid_y - max.clamp(-1.0, 1.0) * half_height;
+        let y_bottom = mid_y - min.clamp(-1.0, 1.0) * half_height;
+        painter.rect_filled(
+            egui::Rect::from_min_max(egui::pos2(x, y_top), egui::pos2(x + bucket_width.max(1.0), y_bottom)),
+            0.0,
+            color,
+        );
+    }
+    (rect, response)
+}
+
+/// Highlight the zoomed view's `view_start_frame..view_end_frame` range
+/// within a full-track strip covering `0..frame_count` - the minimap's
+/// "you are here" rectangle.
+fn paint_viewport_overlay(painter: &egui::Painter, rect: egui::Rect, frame_count: u64, view_start_frame: u64, view_end_frame: u64) {
+    let frame_count = frame_count.max(1) as f32;
+    let x0 = rect.left() + (view_start_frame as f32 / frame_count) * rect.width();
+    let x1 = rect.left() + (view_end_frame as f32 / frame_count) * rect.width();
+    let viewport = egui::Rect::from_min_max(egui::pos2(x0, rect.top()), egui::pos2(x1.max(x0 + 1.0), rect.bottom()));
+    painter.rect_filled(viewport, 0.0, egui::Color32::from_rgba_unmultiplied(255, 255, 0, 60));
+    painter.rect_stroke(viewport, 0.0, egui::Stroke::new(1.0, egui::Color32::YELLOW));
+}
+
+/// Draw vertical lines for `loop_points`' start (green) and end (red)
+/// within a strip covering `range_start..range_end`; markers outside that
+/// range (e.g. zoomed away from them) are simply skipped.
+fn paint_loop_markers(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    range_start: u64,
+    range_end: u64,
+    loop_points: Option<audio::LoopPoints>,
+) {
+    let Some(loop_points) = loop_points else {
+        return;
+    };
+    let range_len = range_end.saturating_sub(range_start).max(1) as f32;
+    for (frame, color) in [
+        (loop_points.start_frame, egui::Color32::GREEN),
+        (loop_points.end_frame, egui::Color32::RED),
+    ] {
+        if frame < range_start || frame > range_end {
+            continue;
+        }
+        let x = rect.left() + ((frame - range_start) as f32 / range_len) * rect.width();
+        painter.line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())], egui::Stroke::new(2.0, color));
+    }
+}
+
+/// Beats per bar for the waveform window's bars:beats ruler; this crate
+/// has no time signature detection, so 4/4 is the only assumption
+/// [`paint_beat_grid`] makes.
+const BEATS_PER_BAR: u64 = 4;
+
+/// Overlay a bars:beats grid over a strip covering `range_start..range_end`,
+/// anchored to frame 0 the same way [`crate::player::mix_metronome`]'s
+/// click track is, so a loop point landing off the grid is visible at a
+/// glance. Bar lines are brighter than plain beat lines and labeled
+/// `<bar>:1`.
+fn paint_beat_grid(painter: &egui::Painter, rect: egui::Rect, range_start: u64, range_end: u64, beat_period_frames: u64) {
+    if beat_period_frames == 0 {
+        return;
+    }
+    let range_len = range_end.saturating_sub(range_start).max(1) as f32;
+    let mut beat_index = range_start / beat_period_frames;
+    loop {
+        let frame = beat_index * beat_period_frames;
+        if frame > range_end {
+            break;
+        }
+        if frame >= range_start {
+            let x = rect.left() + ((frame - range_start) as f32 / range_len) * rect.width();
+            let is_bar_start = beat_index.is_multiple_of(BEATS_PER_BAR);
+            let color = if is_bar_start { egui::Color32::from_gray(160) } else { egui::Color32::from_gray(70) };
+            painter.line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())], egui::Stroke::new(1.0, color));
+            if is_bar_start {
+                painter.text(
+                    egui::pos2(x + 2.0, rect.top()),
+                    egui::Align2::LEFT_TOP,
+                    format!("{}:1", beat_index / BEATS_PER_BAR + 1),
+                    egui::FontId::monospace(9.0),
+                    egui::Color32::from_gray(200),
+                );
+            }
+        }
+        beat_index += 1;
+    }
+}
+
+/// Overlay [`analysis::DebugSignals`] on a waveform strip covering
+/// `range_start..range_end`: the coarse correlation curve (yellow) and the
+/// fade-out detector's RMS history (orange), each rescaled to its own
+/// peak so both stay visible regardless of the track's absolute levels.
+fn paint_debug_signals(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    signals: &analysis::DebugSignals,
+    range_start: u64,
+    range_end: u64,
+) {
+    let correlation_points: Vec<(u64, f32)> = signals
+        .correlation_curve
+        .iter()
+        .map(|candidate| (candidate.start_frame, candidate.confidence.clamp(0.0, 1.0)))
+        .collect();
+    paint_curve(painter, rect, &correlation_points, range_start, range_end, 1.0, egui::Color32::YELLOW);
+
+    let fade_max = signals.fade_rms_history.iter().cloned().fold(0.0f32, f32::max);
+    let fade_points: Vec<(u64, f32)> = signals
+        .fade_rms_history
+        .iter()
+        .enumerate()
+        .map(|(index, &value)| (index as u64 * signals.fade_rms_chunk_frames, value))
+        .collect();
+    paint_curve(painter, rect, &fade_points, range_start, range_end, fade_max, egui::Color32::from_rgb(255, 140, 0));
+}
+
+/// Draw `points` (frame, value) as a connected line across `rect`, scaling
+/// `value` against `max_value` (no-op curve if `max_value` is zero) and
+/// `frame` against `range_start..range_end`. Points outside the range are
+/// dropped rather than clamped, so a curve doesn't falsely flatten at the
+/// zoomed view's edges.
+fn paint_curve(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    points: &[(u64, f32)],
+    range_start: u64,
+    range_end: u64,
+    max_value: f32,
+    color: egui::Color32,
+) {
+    if max_value <= 0.0 {
+        return;
+    }
+    let range_len = range_end.saturating_sub(range_start).max(1) as f32;
+    let to_pos = |(frame, value): (u64, f32)| {
+        let x = rect.left() + ((frame.saturating_sub(range_start)) as f32 / range_len) * rect.width();
+        let y = rect.bottom() - (value / max_value).clamp(0.0, 1.0) * rect.height();
+        egui::pos2(x, y)
+    };
+    let visible: Vec<egui::Pos2> = points
+        .iter()
+        .filter(|&&(frame, _)| frame >= range_start && frame <= range_end)
+        .map(|&point| to_pos(point))
+        .collect();
+    for pair in visible.windows(2) {
+        painter.line_segment([pair[0], pair[1]], egui::Stroke::new(1.5, color));
+    }
+}
+
+/// One line describing a [`analysis::LoopCandidate`] for the compare
+/// window, or "none" if the run didn't find one.
+fn describe_loop_candidate(candidate: Option<analysis::LoopCandidate>) -> String {
+    match candidate {
+        Some(candidate) => format!(
+            "{}..{} ({:.3} confidence)",
+            candidate.start_frame, candidate.end_frame, candidate.confidence
+        ),
+        None => "none".to_string(),
+    }
+}
+
+impl eframe::App for AbloopApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_load_state();
+        if matches!(self.load_state, LoadState::Loading { .. }) {
+            ctx.request_repaint();
+        }
+        self.missing_translations.extend(i18n::take_missing_translations());
+
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("Open...").clicked() {
+                        self.open_file_dialog();
+                        ui.close_menu();
+                    }
+                    if ui.button("Open URL...").clicked() {
+                        self.show_open_url = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Import raw PCM...").clicked() {
+                        self.open_raw_pcm_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(self.audio.is_some(), egui::Button::new("Import loop point..."))
+                        .clicked()
+                    {
+                        self.import_loop_points_dialog();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui
+                        .checkbox(&mut self.restore_last_session, "Reopen last file on startup")
+                        .changed()
+                    {
+                        self.set_restore_last_session(self.restore_last_session);
+                    }
+                });
+                ui.menu_button("Export", |ui| {
+                    for preset in [ExportPreset::UnityFmod, ExportPreset::RpgMaker, ExportPreset::Godot] {
+                        if ui
+                            .add_enabled(self.audio.is_some(), egui::Button::new(preset.name()))
+                            .clicked()
+                        {
+                            self.export_with_preset(preset);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui
+                        .add_enabled(self.audio.is_some(), egui::Button::new("Export report..."))
+                        .clicked()
+                    {
+                        self.export_report();
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Analysis", |ui| {
+                    if ui
+                        .add_enabled(self.audio.is_some(), egui::Button::new("Compare settings..."))
+                        .clicked()
+                    {
+                        self.show_compare = true;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(self.audio.is_some(), egui::Button::new("Optimize seam..."))
+                        .clicked()
+                    {
+                        self.open_loop_editor();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(self.audio.is_some(), egui::Button::new("Waveform..."))
+                        .clicked()
+                    {
+                        self.show_waveform = true;
+                        ui.close_menu();
+                    }
+                    #[cfg(feature = "tracing")]
+                    if ui.button("Diagnostics...").clicked() {
+                        self.show_diagnostics = true;
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Apply preset", |ui| {
+                        for preset in [
+                            AnalysisPreset::GameMusic,
+                            AnalysisPreset::ClassicalLongTail,
+                            AnalysisPreset::Electronic,
+                            AnalysisPreset::Ambient,
+                            AnalysisPreset::Jingle,
+                        ] {
+                            if ui
+                                .add_enabled(self.audio.is_some(), egui::Button::new(preset.name()))
+                                .clicked()
+                            {
+                                self.apply_builtin_preset(preset);
+                                ui.close_menu();
+                            }
+                        }
+                        let user_presets: Vec<String> = self.presets.keys().cloned().collect();
+                        if !user_presets.is_empty() {
+                            ui.separator();
+                            for name in user_presets {
+                                if ui
+                                    .add_enabled(self.audio.is_some(), egui::Button::new(&name))
+                                    .clicked()
+                                {
+                                    self.apply_named_preset(&name);
+                                    ui.close_menu();
+                                }
+                            }
+                        }
+                    });
+                    if ui.button("Save preset as...").clicked() {
+                        self.show_save_preset = true;
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Language", |ui| {
+                    for lang in Lang::ALL {
+                        if ui
+                            .selectable_label(self.lang == lang, lang.native_name())
+                            .clicked()
+                        {
+                            self.lang = lang;
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui
+                        .button(format!("Missing translations ({})...", self.missing_translations.len()))
+                        .clicked()
+                    {
+                        self.show_missing_translations = true;
+                        ui.close_menu();
+                    }
+                });
+            });
+        });
+
+        if self.show_missing_translations {
+            let mut open = self.show_missing_translations;
+            egui::Window::new("Missing translations").open(&mut open).show(ctx, |ui| {
+                if self.missing_translations.is_empty() {
+                    ui.label("No missing translations recorded this session.");
+                } else {
+                    for (lang, key) in &self.missing_translations {
+                        ui.label(format!("{} - {key}", lang.native_name()));
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.missing_translations.clear();
+                    }
+                }
+            });
+            self.show_missing_translations = open;
+        }
+
+        if self.show_save_preset {
+            let mut open = self.show_save_preset;
+            let mut submitted_name = None;
+            egui::Window::new("Save preset as").open(&mut open).show(ctx, |ui| {
+                ui.label("Saves the currently applied analysis settings and export options.");
+                ui.text_edit_singleline(&mut self.save_preset_name);
+                if ui
+                    .add_enabled(!self.save_preset_name.trim().is_empty(), egui::Button::new("Save"))
+                    .clicked()
+                {
+                    submitted_name = Some(self.save_preset_name.trim().to_string());
+                }
+            });
+            self.show_save_preset = open;
+            if let Some(name) = submitted_name {
+                self.save_current_preset(name);
+                self.save_preset_name.clear();
+                self.show_save_preset = false;
+            }
+        }
+
+        if self.show_open_url {
+            let mut open = self.show_open_url;
+            let mut submitted_url = None;
+            egui::Window::new("Open URL")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.text_edit_singleline(&mut self.url_input);
+                    if ui.button("Load").clicked() {
+                        submitted_url = Some(self.url_input.clone());
+                    }
+                });
+            self.show_open_url = open;
+            if let Some(url) = submitted_url {
+                self.load_url(&url);
+                self.show_open_url = false;
+            }
+        }
+
+        if let Some(import) = &mut self.raw_pcm_import {
+            let mut open = true;
+            let mut confirmed = false;
+            egui::Window::new("Import raw PCM")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut import.sample_rate)
+                            .prefix("Sample rate: ")
+                            .clamp_range(1..=192_000),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut import.channels)
+                            .prefix("Channels: ")
+                            .clamp_range(1..=8u16),
+                    );
+                    egui::ComboBox::from_label("Format")
+                        .selected_text(format!("{:?}", import.format))
+                        .show_ui(ui, |ui| {
+                            for format in [
+                                PcmFormat::U8,
+                                PcmFormat::S16Le,
+                                PcmFormat::S24Le,
+                                PcmFormat::S32Le,
+                                PcmFormat::F32Le,
+                            ] {
+                                ui.selectable_value(
+                                    &mut import.format,
+                                    format,
+                                    format!("{format:?}"),
+                                );
+                            }
+                        });
+                    if ui.button("Import").clicked() {
+                        confirmed = true;
+                    }
+                });
+
+            if confirmed {
+                let import = self.raw_pcm_import.take().unwrap();
+                match audio::load_raw_pcm(
+                    &import.bytes,
+                    import.sample_rate,
+                    import.channels,
+                    import.format,
+                ) {
+                    Ok(data) => {
+                        self.status = i18n::gui_imported_raw_pcm(self.lang).to_string();
+                        self.set_audio(data);
+                    }
+                    Err(err) => self.status = i18n::gui_failed_to_import_raw_pcm(self.lang, &err),
+                }
+            } else if !open {
+                self.raw_pcm_import = None;
+            }
+        }
+
+        if self.show_compare {
+            let mut open = self.show_compare;
+            let mut run = false;
+            egui::Window::new("Compare analysis settings")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.add(
+                        egui::Slider::new(&mut self.compare.threshold_a, 0.0..=1.0)
+                            .text("Run A: correlation threshold"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut self.compare.threshold_b, 0.0..=1.0)
+                            .text("Run B: correlation threshold"),
+                    );
+                    if ui.button("Run comparison").clicked() {
+                        run = true;
+                    }
+
+                    if let Some(diff) = &self.compare.diff {
+                        ui.separator();
+                        ui.label(format!(
+                            "A loop point: {}",
+                            describe_loop_candidate(diff.loop_points_a)
+                        ));
+                        ui.label(format!(
+                            "B loop point: {}",
+                            describe_loop_candidate(diff.loop_points_b)
+                        ));
+                        ui.separator();
+                        ui.label(format!(
+                            "{} matched candidate(s), {} only in A, {} only in B",
+                            diff.matched.len(),
+                            diff.only_in_a.len(),
+                            diff.only_in_b.len()
+                        ));
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            for delta in &diff.matched {
+                                ui.label(format!(
+                                    "frame {} (A {:.3} -> B {:.3}, {:+.3})",
+                                    delta.start_frame_a,
+                                    delta.confidence_a,
+                                    delta.confidence_b,
+                                    delta.confidence_delta()
+                                ));
+                            }
+                        });
+                    }
+                });
+            self.show_compare = open;
+            if run {
+                self.run_compare();
+            }
+        }
+
+        if self.show_loop_editor {
+            let mut open = self.show_loop_editor;
+            let mut optimize = false;
+            let mut apply = false;
+            let mut nudge = None;
+            let mut snap = None;
+            let frame_count = self.audio.as_ref().map_or(0, AudioData::frame_count);
+            let ms_frames = self.audio.as_ref().map_or(1, |audio| (audio.sample_rate / 1_000).max(1) as i64);
+            let can_snap = self.waveform_bpm.is_some();
+            egui::Window::new("Optimize seam")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.loop_editor.start_frame)
+                            .prefix("Start frame: ")
+                            .clamp_range(0..=frame_count),
+                    );
+                    nudge_buttons(ui, LoopEditorEdge::Start, ms_frames, &mut nudge);
+                    if ui.add_enabled(can_snap, egui::Button::new("Snap to beat")).clicked() {
+                        snap = Some(LoopEditorEdge::Start);
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut self.loop_editor.end_frame)
+                            .prefix("End frame: ")
+                            .clamp_range(0..=frame_count),
+                    );
+                    nudge_buttons(ui, LoopEditorEdge::End, ms_frames, &mut nudge);
+                    if ui.add_enabled(can_snap, egui::Button::new("Snap to beat")).clicked() {
+                        snap = Some(LoopEditorEdge::End);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Optimize").clicked() {
+                            optimize = true;
+                        }
+                        if ui.button("Apply").clicked() {
+                            apply = true;
+                        }
+                    });
+                    if let Some(seam_cost) = self.loop_editor.seam_cost {
+                        ui.label(format!("seam cost: {seam_cost:.4} (lower is smoother)"));
+                    }
+                });
+            self.show_loop_editor = open;
+            if let Some((edge, delta_frames)) = nudge {
+                self.nudge_loop_editor(edge, delta_frames);
+            }
+            if let Some(edge) = snap {
+                self.snap_loop_editor(edge);
+            }
+            if optimize {
+                self.optimize_seam();
+            }
+            if apply {
+                self.apply_loop_editor();
+            }
+        }
+
+        self.draw_waveform_window(ctx);
+        #[cfg(feature = "tracing")]
+        self.draw_diagnostics_window(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            match &self.load_state {
+                LoadState::Loading {
+                    label,
+                    frames_decoded,
+                    total_frames,
+                    preview,
+                    ..
+                } => {
+                    ui.label(label);
+                    match total_frames {
+                        Some(total) if *total > 0 => {
+                            let fraction = (*frames_decoded as f32 / *total as f32).min(1.0);
+                            ui.add(egui::ProgressBar::new(fraction).show_percentage());
+                        }
+                        _ => {
+                            ui.spinner();
+                        }
+                    }
+                    if !preview.is_empty() {
+                        paint_waveform_strip(ui, preview, 64.0, egui::Color32::from_gray(110));
+                    }
+                }
+                LoadState::Idle => {
+                    ui.label(&self.status);
+                    if let Some(audio) = &self.audio {
+                        ui.label(format!(
+                            "{} Hz, {} channel(s), {} frames",
+                            audio.sample_rate,
+                            audio.channels,
+                            audio.frame_count()
+                        ));
+                        if audio.decode_warnings.dropped_packets > 0 {
+                            ui.colored_label(
+                                egui::Color32::YELLOW,
+                                format!(
+                                    "Dropped {} of {} packets while decoding ({:.1}%) - the file may have gaps",
+                                    audio.decode_warnings.dropped_packets,
+                                    audio.decode_warnings.total_packets,
+                                    audio.decode_warnings.dropped_fraction() * 100.0
+                                ),
+                            );
+                        }
+                        if let Some(lyrics) = &audio.lyrics {
+                            ui.collapsing("Lyrics", |ui| {
+                                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                                    ui.label(lyrics);
+                                });
+                            });
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
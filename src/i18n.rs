@@ -0,0 +1,410 @@
+//! CLI message translations, selected by `--lang` or the system locale
+//! (via `sys-locale`: `LANG`/`LC_ALL` on Unix, the Windows/macOS locale
+//! APIs, or `navigator.language` on wasm). Adding a language means adding
+//! a variant to [`Lang`], an entry in [`Lang::ALL`] and [`Lang::parse`],
+//! and a match arm to each function here - nothing more.
+
+/// A supported CLI/GUI display language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+    Ja,
+    Ko,
+    De,
+}
+
+impl Lang {
+    /// Every supported language, in the order the GUI's language selector
+    /// lists them.
+    pub const ALL: [Lang; 5] = [Lang::En, Lang::Es, Lang::Ja, Lang::Ko, Lang::De];
+
+    /// This language's name, in itself - what the GUI's language selector
+    /// shows for each entry.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Lang::En => "English",
+            Lang::Es => "Espanol",
+            Lang::Ja => "日本語",
+            Lang::Ko => "한국어",
+            Lang::De => "Deutsch",
+        }
+    }
+
+    /// Parse a `--lang` value or a `LANG`/`LC_ALL`-style locale string
+    /// (`es`, `es_ES.UTF-8`, ...), defaulting to English for anything
+    /// unrecognized.
+    pub fn parse(s: &str) -> Lang {
+        match s.split(['_', '.', '-']).next().unwrap_or(s) {
+            "es" => Lang::Es,
+            "ja" => Lang::Ja,
+            "ko" => Lang::Ko,
+            "de" => Lang::De,
+            _ => Lang::En,
+        }
+    }
+
+    /// The UI's language: `--lang` if given, else the system locale, else
+    /// English.
+    pub fn from_args_or_env(lang_flag: Option<&str>) -> Lang {
+        if let Some(lang) = lang_flag {
+            return Lang::parse(lang);
+        }
+        sys_locale::get_locale()
+            .map(|locale| Lang::parse(&locale))
+            .unwrap_or_default()
+    }
+}
+
+pub fn decoding(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "decoding",
+        Lang::Es => "decodificando",
+        Lang::Ja => "デコード中",
+        Lang::Ko => "디코딩 중",
+        Lang::De => "dekodiere",
+    }
+}
+
+pub fn analyzing(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "analyzing",
+        Lang::Es => "analizando",
+        Lang::Ja => "解析中",
+        Lang::Ko => "분석 중",
+        Lang::De => "analysiere",
+    }
+}
+
+pub fn exporting(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "exporting",
+        Lang::Es => "exportando",
+        Lang::Ja => "書き出し中",
+        Lang::Ko => "내보내는 중",
+        Lang::De => "exportiere",
+    }
+}
+
+pub fn loop_found(lang: Lang, start_frame: u64, end_frame: u64, confidence: f32) -> String {
+    match lang {
+        Lang::En => format!("loop: {start_frame}..{end_frame} (confidence {confidence:.2})"),
+        Lang::Es => format!("bucle: {start_frame}..{end_frame} (confianza {confidence:.2})"),
+        Lang::Ja => format!("ループ: {start_frame}..{end_frame} (信頼度 {confidence:.2})"),
+        Lang::Ko => format!("루프: {start_frame}..{end_frame} (신뢰도 {confidence:.2})"),
+        Lang::De => format!("Schleife: {start_frame}..{end_frame} (Konfidenz {confidence:.2})"),
+    }
+}
+
+pub fn loop_not_found(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "loop: none found",
+        Lang::Es => "bucle: no encontrado",
+        Lang::Ja => "ループ: 見つかりませんでした",
+        Lang::Ko => "루프: 찾을 수 없음",
+        Lang::De => "Schleife: keine gefunden",
+    }
+}
+
+/// Why [`loop_not_found`] came back empty, for the reason
+/// [`crate::analysis::AnalysisResult::outcome`] gives when `loop_points` is
+/// `None` - printed alongside it instead of leaving a bare "none found" to
+/// tell a legitimately loop-free jingle apart from a track that just needs
+/// a different threshold. Never called with
+/// [`crate::analysis::LoopDetectionOutcome::Found`].
+pub fn loop_detection_outcome_reason(lang: Lang, outcome: crate::analysis::LoopDetectionOutcome) -> &'static str {
+    use crate::analysis::LoopDetectionOutcome;
+    match (lang, outcome) {
+        (_, LoopDetectionOutcome::Found) => "",
+        (Lang::En, LoopDetectionOutcome::TooShort) => "file too short",
+        (Lang::Es, LoopDetectionOutcome::TooShort) => "archivo demasiado corto",
+        (Lang::Ja, LoopDetectionOutcome::TooShort) => "ファイルが短すぎます",
+        (Lang::Ko, LoopDetectionOutcome::TooShort) => "파일이 너무 짧음",
+        (Lang::De, LoopDetectionOutcome::TooShort) => "Datei zu kurz",
+        (Lang::En, LoopDetectionOutcome::Silent) => "tail is silent",
+        (Lang::Es, LoopDetectionOutcome::Silent) => "el final esta en silencio",
+        (Lang::Ja, LoopDetectionOutcome::Silent) => "末尾が無音です",
+        (Lang::Ko, LoopDetectionOutcome::Silent) => "끝부분이 무음",
+        (Lang::De, LoopDetectionOutcome::Silent) => "Ende ist still",
+        (Lang::En, LoopDetectionOutcome::BelowThreshold) => "no match above confidence threshold",
+        (Lang::Es, LoopDetectionOutcome::BelowThreshold) => "ninguna coincidencia supera el umbral de confianza",
+        (Lang::Ja, LoopDetectionOutcome::BelowThreshold) => "信頼度のしきい値を超える一致がありません",
+        (Lang::Ko, LoopDetectionOutcome::BelowThreshold) => "신뢰도 임계값을 넘는 일치 없음",
+        (Lang::De, LoopDetectionOutcome::BelowThreshold) => "keine Ubereinstimmung uber dem Konfidenzschwellenwert",
+        (Lang::En, LoopDetectionOutcome::Cancelled) => "search cancelled",
+        (Lang::Es, LoopDetectionOutcome::Cancelled) => "busqueda cancelada",
+        (Lang::Ja, LoopDetectionOutcome::Cancelled) => "検索はキャンセルされました",
+        (Lang::Ko, LoopDetectionOutcome::Cancelled) => "검색이 취소됨",
+        (Lang::De, LoopDetectionOutcome::Cancelled) => "Suche abgebrochen",
+    }
+}
+
+pub fn fade_out_found(lang: Lang, start_frame: u64, confidence: f32) -> String {
+    match lang {
+        Lang::En => format!("fade-out: starts at frame {start_frame} (confidence {confidence:.2})"),
+        Lang::Es => format!("desvanecimiento: comienza en el fotograma {start_frame} (confianza {confidence:.2})"),
+        Lang::Ja => format!("フェードアウト: フレーム {start_frame} から開始 (信頼度 {confidence:.2})"),
+        Lang::Ko => format!("페이드아웃: 프레임 {start_frame}에서 시작 (신뢰도 {confidence:.2})"),
+        Lang::De => format!("Ausblenden: beginnt bei Frame {start_frame} (Konfidenz {confidence:.2})"),
+    }
+}
+
+pub fn fade_out_not_found(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "fade-out: none detected",
+        Lang::Es => "desvanecimiento: no detectado",
+        Lang::Ja => "フェードアウト: 検出されませんでした",
+        Lang::Ko => "페이드아웃: 감지되지 않음",
+        Lang::De => "Ausblenden: keines erkannt",
+    }
+}
+
+pub fn no_input_files_matched(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "no input files matched",
+        Lang::Es => "ningun archivo de entrada coincide",
+        Lang::Ja => "一致する入力ファイルがありません",
+        Lang::Ko => "일치하는 입력 파일이 없습니다",
+        Lang::De => "keine passenden Eingabedateien",
+    }
+}
+
+pub fn export_ok(lang: Lang, input: &str) -> String {
+    match lang {
+        Lang::En => format!("OK   {input}"),
+        Lang::Es => format!("OK   {input}"),
+        Lang::Ja => format!("OK   {input}"),
+        Lang::Ko => format!("OK   {input}"),
+        Lang::De => format!("OK   {input}"),
+    }
+}
+
+pub fn export_fail(lang: Lang, input: &str, err: &dyn std::fmt::Display) -> String {
+    match lang {
+        Lang::En => format!("FAIL {input}: {err}"),
+        Lang::Es => format!("FALLO {input}: {err}"),
+        Lang::Ja => format!("失敗 {input}: {err}"),
+        Lang::Ko => format!("실패 {input}: {err}"),
+        Lang::De => format!("FEHLER {input}: {err}"),
+    }
+}
+
+pub fn export_summary(lang: Lang, succeeded: usize, failed: usize, total: usize) -> String {
+    match lang {
+        Lang::En => format!("{succeeded} succeeded, {failed} failed, {total} total"),
+        Lang::Es => format!("{succeeded} con exito, {failed} fallidos, {total} en total"),
+        Lang::Ja => format!("成功 {succeeded} 件、失敗 {failed} 件、合計 {total} 件"),
+        Lang::Ko => format!("성공 {succeeded}건, 실패 {failed}건, 총 {total}건"),
+        Lang::De => format!("{succeeded} erfolgreich, {failed} fehlgeschlagen, {total} insgesamt"),
+    }
+}
+
+pub fn now_playing(lang: Lang, file: &str) -> String {
+    match lang {
+        Lang::En => format!("playing {file} - press Ctrl+C to stop"),
+        Lang::Es => format!("reproduciendo {file} - presiona Ctrl+C para detener"),
+        Lang::Ja => format!("再生中 {file} - 停止するには Ctrl+C を押してください"),
+        Lang::Ko => format!("재생 중 {file} - 중지하려면 Ctrl+C를 누르세요"),
+        Lang::De => format!("spiele {file} ab - zum Beenden Strg+C drucken"),
+    }
+}
+
+pub fn wrote_file(lang: Lang, path: &str) -> String {
+    match lang {
+        Lang::En => format!("wrote {path}"),
+        Lang::Es => format!("se escribio {path}"),
+        Lang::Ja => format!("{path} を書き込みました"),
+        Lang::Ko => format!("{path} 작성됨"),
+        Lang::De => format!("{path} geschrieben"),
+    }
+}
+
+pub fn gui_no_file_loaded(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "No file loaded",
+        Lang::Es => "Ningun archivo cargado",
+        Lang::Ja => "ファイルが読み込まれていません",
+        Lang::Ko => "로드된 파일 없음",
+        Lang::De => "Keine Datei geladen",
+    }
+}
+
+pub fn gui_reading(lang: Lang, path: &str) -> String {
+    match lang {
+        Lang::En => format!("Reading {path}..."),
+        Lang::Es => format!("Leyendo {path}..."),
+        Lang::Ja => format!("{path} を読み込み中..."),
+        Lang::Ko => format!("{path} 읽는 중..."),
+        Lang::De => format!("Lese {path}..."),
+    }
+}
+
+pub fn gui_failed_to_read(lang: Lang, path: &str, err: &std::io::Error) -> String {
+    match lang {
+        Lang::En => format!("Failed to read {path}: {err}"),
+        Lang::Es => format!("Error al leer {path}: {err}"),
+        Lang::Ja => format!("{path} の読み込みに失敗しました: {err}"),
+        Lang::Ko => format!("{path} 읽기 실패: {err}"),
+        Lang::De => format!("Lesen von {path} fehlgeschlagen: {err}"),
+    }
+}
+
+pub fn gui_loaded_url(lang: Lang, url: &str) -> String {
+    match lang {
+        Lang::En => format!("Loaded {url}"),
+        Lang::Es => format!("Cargado {url}"),
+        Lang::Ja => format!("{url} を読み込みました"),
+        Lang::Ko => format!("{url} 로드됨"),
+        Lang::De => format!("{url} geladen"),
+    }
+}
+
+pub fn gui_failed_to_fetch(lang: Lang, url: &str, err: &dyn std::fmt::Display) -> String {
+    match lang {
+        Lang::En => format!("Failed to fetch {url}: {err}"),
+        Lang::Es => format!("Error al obtener {url}: {err}"),
+        Lang::Ja => format!("{url} の取得に失敗しました: {err}"),
+        Lang::Ko => format!("{url} 가져오기 실패: {err}"),
+        Lang::De => format!("Abrufen von {url} fehlgeschlagen: {err}"),
+    }
+}
+
+pub fn gui_loaded_info(lang: Lang, sample_rate: u32, channels: u16, frame_count: u64) -> String {
+    match lang {
+        Lang::En => format!("Loaded {sample_rate} Hz, {channels} channel(s), {frame_count} frames"),
+        Lang::Es => format!("Cargado {sample_rate} Hz, {channels} canal(es), {frame_count} fotogramas"),
+        Lang::Ja => format!("読み込み完了 {sample_rate} Hz、{channels} チャンネル、{frame_count} フレーム"),
+        Lang::Ko => format!("로드 완료 {sample_rate} Hz, {channels}채널, {frame_count}프레임"),
+        Lang::De => format!("Geladen: {sample_rate} Hz, {channels} Kanal(e), {frame_count} Frames"),
+    }
+}
+
+pub fn gui_failed_to_load(lang: Lang, err: &dyn std::fmt::Display) -> String {
+    match lang {
+        Lang::En => format!("Failed to load: {err}"),
+        Lang::Es => format!("Error al cargar: {err}"),
+        Lang::Ja => format!("読み込みに失敗しました: {err}"),
+        Lang::Ko => format!("로드 실패: {err}"),
+        Lang::De => format!("Laden fehlgeschlagen: {err}"),
+    }
+}
+
+pub fn gui_imported_raw_pcm(lang: Lang) -> &'static str {
+    match lang {
+        Lang::En => "Imported raw PCM",
+        Lang::Es => "PCM sin procesar importado",
+        Lang::Ja => "生の PCM をインポートしました",
+        Lang::Ko => "원시 PCM 가져옴",
+        Lang::De => "Rohes PCM importiert",
+    }
+}
+
+pub fn gui_failed_to_import_raw_pcm(lang: Lang, err: &dyn std::fmt::Display) -> String {
+    match lang {
+        Lang::En => format!("Failed to import raw PCM: {err}"),
+        Lang::Es => format!("Error al importar PCM sin procesar: {err}"),
+        Lang::Ja => format!("生の PCM のインポートに失敗しました: {err}"),
+        Lang::Ko => format!("원시 PCM 가져오기 실패: {err}"),
+        Lang::De => format!("Importieren von rohem PCM fehlgeschlagen: {err}"),
+    }
+}
+
+pub fn gui_imported_loop_points(lang: Lang, start_frame: u64, end_frame: u64) -> String {
+    match lang {
+        Lang::En => format!("Imported loop {start_frame}..{end_frame}"),
+        Lang::Es => format!("Bucle importado {start_frame}..{end_frame}"),
+        Lang::Ja => format!("ループ {start_frame}..{end_frame} をインポートしました"),
+        Lang::Ko => format!("루프 {start_frame}..{end_frame} 가져옴"),
+        Lang::De => format!("Loop {start_frame}..{end_frame} importiert"),
+    }
+}
+
+pub fn gui_failed_to_import_loop_points(lang: Lang, err: &dyn std::fmt::Display) -> String {
+    match lang {
+        Lang::En => format!("Failed to import loop points: {err}"),
+        Lang::Es => format!("Error al importar puntos de bucle: {err}"),
+        Lang::Ja => format!("ループポイントのインポートに失敗しました: {err}"),
+        Lang::Ko => format!("루프 지점 가져오기 실패: {err}"),
+        Lang::De => format!("Importieren der Loop-Punkte fehlgeschlagen: {err}"),
+    }
+}
+
+pub fn gui_exported(lang: Lang, path: &str) -> String {
+    match lang {
+        Lang::En => format!("Exported to {path}"),
+        Lang::Es => format!("Exportado a {path}"),
+        Lang::Ja => format!("{path} にエクスポートしました"),
+        Lang::Ko => format!("{path}(으)로 내보냈습니다"),
+        Lang::De => format!("Nach {path} exportiert"),
+    }
+}
+
+pub fn gui_failed_to_export(lang: Lang, err: &dyn std::fmt::Display) -> String {
+    match lang {
+        Lang::En => format!("Failed to export: {err}"),
+        Lang::Es => format!("Error al exportar: {err}"),
+        Lang::Ja => format!("エクスポートに失敗しました: {err}"),
+        Lang::Ko => format!("내보내기 실패: {err}"),
+        Lang::De => format!("Exportieren fehlgeschlagen: {err}"),
+    }
+}
+
+pub fn gui_report_written(lang: Lang, path: &str) -> String {
+    match lang {
+        Lang::En => format!("Report written to {path}"),
+        Lang::Es => format!("Informe escrito en {path}"),
+        Lang::Ja => format!("レポートを {path} に書き込みました"),
+        Lang::Ko => format!("보고서를 {path}에 저장했습니다"),
+        Lang::De => format!("Bericht nach {path} geschrieben"),
+    }
+}
+
+pub fn gui_failed_to_write_report(lang: Lang, err: &dyn std::fmt::Display) -> String {
+    match lang {
+        Lang::En => format!("Failed to write report: {err}"),
+        Lang::Es => format!("Error al escribir el informe: {err}"),
+        Lang::Ja => format!("レポートの書き込みに失敗しました: {err}"),
+        Lang::Ko => format!("보고서 작성 실패: {err}"),
+        Lang::De => format!("Schreiben des Berichts fehlgeschlagen: {err}"),
+    }
+}
+
+/// `(language, key)` pairs [`tr`] has fallen back to the raw key string
+/// for, because no translation was registered for that language - see
+/// [`tr`] and [`take_missing_translations`].
+static MISSING_TRANSLATIONS: std::sync::Mutex<Vec<(Lang, &'static str)>> = std::sync::Mutex::new(Vec::new());
+
+/// Look up `key` in `table` for `lang`, recording a miss via
+/// [`take_missing_translations`] and returning `key` itself if this
+/// language has no entry for it.
+///
+/// The functions above this one are the primary way this module is used -
+/// a match arm per [`Lang`], checked exhaustively by the compiler - and
+/// should stay that way for every string that already has a translation.
+/// `tr` exists for the gap the compiler can't catch: a new key added with
+/// only some languages filled in. It's meant for call sites (a new CLI
+/// message, a new GUI label) where a developer wants to ship the English
+/// string immediately and fill in the rest later; a diagnostics panel or
+/// a startup log line drained from [`take_missing_translations`] then
+/// makes the gap visible instead of stranding it as a silent TODO.
+pub fn tr(lang: Lang, key: &'static str, table: &[(Lang, &'static str)]) -> &'static str {
+    match table.iter().find(|(l, _)| *l == lang) {
+        Some((_, value)) => value,
+        None => {
+            if let Ok(mut missing) = MISSING_TRANSLATIONS.lock() {
+                if !missing.contains(&(lang, key)) {
+                    missing.push((lang, key));
+                }
+            }
+            key
+        }
+    }
+}
+
+/// Drain and return every `(language, key)` pair recorded by [`tr`] since
+/// the last call, for a GUI diagnostics panel or a startup log line to
+/// surface incomplete translations as soon as a new string is added.
+pub fn take_missing_translations() -> Vec<(Lang, &'static str)> {
+    MISSING_TRANSLATIONS.lock().map(|mut missing| std::mem::take(&mut *missing)).unwrap_or_default()
+}
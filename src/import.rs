@@ -0,0 +1,119 @@
+//! Importing loop points set outside this crate, to apply instead of
+//! running detection: this crate's own `--write-sidecar` JSON, an
+//! Audacity label track, or plain `start,end` frame numbers.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::analysis::LoopCandidate;
+use crate::audio::LoopPoints;
+use crate::error::{AbloopError, Context, Result};
+
+/// Where `analyze --write-sidecar` writes its JSON report for `input`, and
+/// where the GUI looks for one automatically (via [`import_loop_points`])
+/// when it loads a file, e.g. when restoring a session's last-opened file.
+pub fn sidecar_path(input: &Path) -> PathBuf {
+    let mut name = input.file_name().unwrap_or_default().to_os_string();
+    name.push(".abloop.json");
+    input.with_file_name(name)
+}
+
+/// Read `path` and parse it as one of the supported loop import formats;
+/// see [`parse_loop_points`].
+pub fn import_loop_points(path: impl AsRef<Path>, sample_rate: u32) -> Result<LoopPoints> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path).context(|| format!("reading {}", path.display()))?;
+    parse_loop_points(&contents, sample_rate)
+}
+
+/// Parse `contents` as a loop point, trying each supported format in turn:
+/// the JSON sidecar `analyze --write-sidecar` writes (unambiguous, since
+/// it's the only one that's valid JSON), then an Audacity label track
+/// (`start\tend\tlabel` per line, timestamps in seconds), then plain
+/// `start,end` frame numbers. `sample_rate` converts the label track's
+/// second-based timestamps to frames; the other two formats are already in
+/// frames and ignore it.
+pub fn parse_loop_points(contents: &str, sample_rate: u32) -> Result<LoopPoints> {
+    parse_json_sidecar(contents)
+        .or_else(|| parse_audacity_labels(contents, sample_rate))
+        .or_else(|| parse_plain_frames(contents))
+        .ok_or_else(|| {
+            AbloopError::ImportFailed(
+                "unrecognized loop import format (expected a JSON sidecar, an Audacity label \
+                 track, or \"start,end\" frame numbers)"
+                    .to_string(),
+            )
+        })
+}
+
+/// The subset of `analyze`'s `AnalyzeReport` this cares about; other
+/// fields (`file`, `settings`, `fade_out`, ...) are ignored.
+#[derive(Deserialize)]
+struct SidecarLoopPoints {
+    loop_points: Option<LoopCandidate>,
+}
+
+fn parse_json_sidecar(contents: &str) -> Option<LoopPoints> {
+    let sidecar: SidecarLoopPoints = serde_json::from_str(contents).ok()?;
+    sidecar.loop_points.map(|candidate| LoopPoints {
+        start_frame: candidate.start_frame,
+        end_frame: candidate.end_frame,
+    })
+}
+
+/// Only the first (earliest) label is used; anything after the second tab
+/// on its line (the label's name) is ignored.
+fn parse_audacity_labels(contents: &str, sample_rate: u32) -> Option<LoopPoints> {
+    let line = contents.lines().find(|line| !line.trim().is_empty())?;
+    let mut fields = line.split('\t');
+    let start_secs: f64 = fields.next()?.trim().parse().ok()?;
+    let end_secs: f64 = fields.next()?.trim().parse().ok()?;
+    Some(LoopPoints {
+        start_frame: (start_secs * sample_rate as f64).round() as u64,
+        end_frame: (end_secs * sample_rate as f64).round() as u64,
+    })
+}
+
+/// `start,end` frame numbers on one line, for pasting in a loop already
+/// known exactly (e.g. read off another tool's own loop metadata) without
+/// a file format round trip.
+fn parse_plain_frames(contents: &str) -> Option<LoopPoints> {
+    let line = contents.lines().find(|line| !line.trim().is_empty())?;
+    let (start, end) = line.trim().split_once(',')?;
+    Some(LoopPoints {
+        start_frame: start.trim().parse().ok()?,
+        end_frame: end.trim().parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_loop_points_reads_sidecar_json() {
+        let json = r#"{"loop_points": {"start_frame": 100, "end_frame": 2000, "confidence": 0.9}}"#;
+        let loop_points = parse_loop_points(json, 44_100).unwrap();
+        assert_eq!(loop_points, LoopPoints { start_frame: 100, end_frame: 2000 });
+    }
+
+    #[test]
+    fn parse_loop_points_reads_audacity_labels() {
+        let labels = "1.0\t2.0\tloop\n";
+        let loop_points = parse_loop_points(labels, 44_100).unwrap();
+        assert_eq!(loop_points, LoopPoints { start_frame: 44_100, end_frame: 88_200 });
+    }
+
+    #[test]
+    fn parse_loop_points_reads_plain_frames() {
+        let loop_points = parse_loop_points("100,2000", 44_100).unwrap();
+        assert_eq!(loop_points, LoopPoints { start_frame: 100, end_frame: 2000 });
+    }
+
+    #[test]
+    fn parse_loop_points_rejects_unrecognized_format() {
+        let err = parse_loop_points("not a loop point", 44_100).unwrap_err();
+        assert!(matches!(err, AbloopError::ImportFailed(_)));
+    }
+}
@@ -0,0 +1,90 @@
+//! Core library for auto-abloop: loading audio, detecting loop points and
+//! exporting looped renders.
+
+pub mod analysis;
+pub mod audio;
+pub mod config;
+pub mod daw;
+#[cfg(feature = "tracing")]
+pub mod diagnostics;
+pub mod error;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fingerprint")]
+pub mod fingerprint;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod i18n;
+pub mod import;
+#[cfg(feature = "loop-db")]
+pub mod loop_db;
+pub mod loudness;
+#[cfg(feature = "playback")]
+pub mod player;
+#[cfg(feature = "playback")]
+pub mod record;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod report;
+#[cfg(feature = "playback")]
+pub mod rpc;
+pub mod sample_cache;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "playback")]
+pub mod spectrum;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "playback")]
+pub mod tui;
+#[cfg(all(target_family = "wasm", feature = "js-api"))]
+pub mod wasm_api;
+pub mod waveform;
+
+use std::path::Path;
+
+use crate::analysis::AnalysisResult;
+use crate::error::Result;
+
+/// Load, analyze, and return the detected loop point for `path` in one
+/// call, using `settings`. Equivalent to [`audio::load_audio_from_path`]
+/// followed by [`analysis::detect_loop`], for callers who just want an
+/// [`AnalysisResult`] without handling the intermediate [`audio::AudioData`].
+pub fn find_loop(path: impl AsRef<Path>, settings: &analysis::AnalysisSettings) -> Result<AnalysisResult> {
+    let data = audio::load_audio_from_path(path)?;
+    Ok(analysis::detect_loop(&data, settings))
+}
+
+/// Like [`find_loop`], but decodes an in-memory buffer instead of a file on
+/// disk; `ext_hint` helps format probing as in
+/// [`audio::load_audio_from_bytes`].
+pub fn find_loop_bytes(
+    bytes: Vec<u8>,
+    ext_hint: Option<&str>,
+    settings: &analysis::AnalysisSettings,
+) -> Result<AnalysisResult> {
+    let data = audio::load_audio_from_bytes(bytes, ext_hint)?;
+    Ok(analysis::detect_loop(&data, settings))
+}
+
+/// Load `path`, detect its loop point (falling back to one already
+/// embedded in the file), and render a looped WAV to `output` with default
+/// export settings. A one-call version of the load/analyze/export
+/// pipeline the CLI's `analyze`/`export` subcommands run by hand; use
+/// [`export::export`] directly if you need crossfade, bit depth, or other
+/// [`export::ExportSettings`] control.
+pub fn render_loop(
+    path: impl AsRef<Path>,
+    settings: &analysis::AnalysisSettings,
+    output: impl AsRef<Path>,
+) -> Result<AnalysisResult> {
+    let data = audio::load_audio_from_path(path)?;
+    let result = analysis::detect_loop(&data, settings);
+    let loop_points = result
+        .loop_points
+        .map(|candidate| audio::LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame })
+        .or(data.loop_points);
+    export::export_wav(&data, loop_points, output)?;
+    Ok(result)
+}
@@ -0,0 +1,102 @@
+//! A small persistent store mapping an audio file's content hash to a
+//! confirmed loop point, so a once-corrected loop never needs
+//! re-detection - on this machine or any other sharing the database file.
+//! Build with `--features loop-db`.
+//!
+//! [`LoopDb::lookup`]/[`LoopDb::store`] are the only two operations: check
+//! a hash before running [`crate::analysis::detect_loop`], and store
+//! whatever the user confirms (or edits) afterwards.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{AudioData, LoopPoints};
+use crate::error::{Context, Result};
+
+/// A confirmed loop point, as persisted in a [`LoopDb`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConfirmedLoop {
+    pub start_frame: u64,
+    pub end_frame: u64,
+}
+
+impl From<ConfirmedLoop> for LoopPoints {
+    fn from(confirmed: ConfirmedLoop) -> Self {
+        LoopPoints { start_frame: confirmed.start_frame, end_frame: confirmed.end_frame }
+    }
+}
+
+impl From<LoopPoints> for ConfirmedLoop {
+    fn from(points: LoopPoints) -> Self {
+        ConfirmedLoop { start_frame: points.start_frame, end_frame: points.end_frame }
+    }
+}
+
+/// A `sled`-backed key-value store of content hash -> confirmed loop
+/// point. Safe to share a single database file across machines: the key
+/// is derived purely from the decoded PCM content, not the file path.
+pub struct LoopDb {
+    tree: sled::Db,
+}
+
+impl LoopDb {
+    /// Open (creating if needed) the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let tree = sled::open(path).context(|| "opening loop database".to_string())?;
+        Ok(Self { tree })
+    }
+
+    /// Open the default database at the platform data directory (e.g.
+    /// `~/.local/share/auto-abloop/loops.sled` on Linux), or `None` if the
+    /// platform has no data directory.
+    pub fn open_default() -> Option<Result<Self>> {
+        default_path().map(Self::open)
+    }
+
+    /// Look up a previously confirmed loop point for `audio`'s content.
+    pub fn lookup(&self, audio: &AudioData) -> Result<Option<ConfirmedLoop>> {
+        let key = content_hash(audio).to_be_bytes();
+        let Some(value) = self.tree.get(key).context(|| "reading loop database".to_string())? else {
+            return Ok(None);
+        };
+        let confirmed = serde_json::from_slice(&value).context(|| "decoding loop database entry".to_string())?;
+        Ok(Some(confirmed))
+    }
+
+    /// Store a confirmed (or user-edited) loop point for `audio`'s
+    /// content, overwriting whatever was there before.
+    pub fn store(&self, audio: &AudioData, loop_points: LoopPoints) -> Result<()> {
+        let key = content_hash(audio).to_be_bytes();
+        let value = serde_json::to_vec(&ConfirmedLoop::from(loop_points))
+            .context(|| "encoding loop database entry".to_string())?;
+        self.tree.insert(key, value).context(|| "writing loop database".to_string())?;
+        self.tree.flush().context(|| "flushing loop database".to_string())?;
+        Ok(())
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("auto-abloop").join("loops.sled"))
+}
+
+/// A stable (cross-run, cross-machine) hash of `audio`'s PCM content plus
+/// its format, so re-encodes of the same material still match as long as
+/// the decoded samples are bit-identical. Plain FNV-1a: fast, and stable
+/// across Rust versions, unlike `std`'s default hasher.
+fn content_hash(audio: &AudioData) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let bytes = audio
+        .sample_rate
+        .to_le_bytes()
+        .into_iter()
+        .chain(audio.channels.to_le_bytes())
+        .chain(audio.samples.iter().flat_map(|sample| sample.to_le_bytes()));
+    let mut hash = FNV_OFFSET;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
@@ -0,0 +1,217 @@
+//! Simplified EBU R128 / ITU-R BS.1770 loudness metering: momentary,
+//! short-term, and integrated LUFS, fed incrementally as new samples become
+//! available (e.g. from [`crate::player::Player::tick`]) so the numbers can
+//! be displayed live instead of only at export time.
+//!
+//! This isn't a certified BS.1770 implementation: blocks don't overlap (the
+//! spec steps a 400 ms window every 100 ms), and integration applies the
+//! absolute -70 LUFS and relative -10 LU gates to those 100 ms blocks.
+//! Close enough to judge levels before committing to a long render, not to
+//! certify loudness compliance.
+
+const BLOCK_SECONDS: f64 = 0.1;
+const MOMENTARY_BLOCKS: usize = 4; // 0.4s / 0.1s
+const SHORT_TERM_BLOCKS: usize = 30; // 3.0s / 0.1s
+const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+const RELATIVE_GATE_LU: f32 = -10.0;
+
+/// Momentary (400ms), short-term (3s), and integrated (whole track so far)
+/// loudness, in LUFS. `f32::NEG_INFINITY` when there isn't yet enough audio
+/// (or it's all silence) to report a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    pub momentary: f32,
+    pub short_term: f32,
+    pub integrated: f32,
+}
+
+/// One-pole-pair IIR stage, direct form 1; used for both K-weighting
+/// filters.
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    fn process(&mut self, x0: f32) -> f32 {
+        let y0 = self.b0 * x0 + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x0;
+        self.y2 = self.y1;
+        self.y1 = y0;
+        y0
+    }
+}
+
+/// The two-stage K-weighting filter applied to each channel before
+/// measuring: a high-shelf "pre-filter" followed by the RLB high-pass.
+/// Coefficients derived per BS.1770-4 annex 1, parameterized by
+/// `sample_rate` so non-48kHz sources are weighted correctly too.
+#[derive(Clone, Copy, Default)]
+struct KWeighting {
+    pre_filter: Biquad,
+    rlb: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f64;
+
+        let pre_filter = {
+            let f0 = 1_681.974_450_955_532;
+            let g = 3.999_843_853_97_f64;
+            let q = 0.707_175_236_955_419_3;
+            let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+            let vh = 10f64.powf(g / 20.0);
+            let vb = vh.powf(0.499_666_774_154_541_6);
+            let a0 = 1.0 + k / q + k * k;
+            Biquad {
+                b0: ((vh + vb * k / q + k * k) / a0) as f32,
+                b1: (2.0 * (k * k - vh) / a0) as f32,
+                b2: ((vh - vb * k / q + k * k) / a0) as f32,
+                a1: (2.0 * (k * k - 1.0) / a0) as f32,
+                a2: ((1.0 - k / q + k * k) / a0) as f32,
+                ..Default::default()
+            }
+        };
+
+        let rlb = {
+            let f0 = 38.135_470_876_139_82;
+            let q = 0.500_327_037_323_877_3;
+            let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+            let a0 = 1.0 + k / q + k * k;
+            Biquad {
+                b0: (1.0 / a0) as f32,
+                b1: (-2.0 / a0) as f32,
+                b2: (1.0 / a0) as f32,
+                a1: (2.0 * (k * k - 1.0) / a0) as f32,
+                a2: ((1.0 - k / q + k * k) / a0) as f32,
+                ..Default::default()
+            }
+        };
+
+        KWeighting { pre_filter, rlb }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        self.rlb.process(self.pre_filter.process(sample))
+    }
+}
+
+/// Incremental loudness meter: call [`LoudnessMeter::feed`] with newly
+/// available interleaved samples as they arrive, then [`LoudnessMeter::current`]
+/// for the latest readings.
+pub struct LoudnessMeter {
+    channels: usize,
+    block_frames: usize,
+    weighting: Vec<KWeighting>,
+    block_sum: f32,
+    block_filled: usize,
+    /// Mean square of each completed block, oldest first.
+    blocks: Vec<f32>,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: usize, sample_rate: u32) -> Self {
+        LoudnessMeter {
+            channels,
+            block_frames: ((sample_rate as f64 * BLOCK_SECONDS) as usize).max(1),
+            weighting: vec![KWeighting::new(sample_rate); channels],
+            block_sum: 0.0,
+            block_filled: 0,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Feed newly played interleaved samples (`channels` per frame) into the
+    /// meter.
+    pub fn feed(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            let mut frame_sum = 0.0f32;
+            for (channel, &sample) in frame.iter().enumerate() {
+                let weighted = self.weighting[channel].process(sample);
+                frame_sum += weighted * weighted;
+            }
+            self.block_sum += frame_sum;
+            self.block_filled += 1;
+            if self.block_filled == self.block_frames {
+                self.blocks.push(self.block_sum / (self.block_frames * self.channels) as f32);
+                self.block_sum = 0.0;
+                self.block_filled = 0;
+            }
+        }
+    }
+
+    /// The latest momentary/short-term/integrated readings.
+    pub fn current(&self) -> Loudness {
+        Loudness {
+            momentary: windowed_lufs(&self.blocks, MOMENTARY_BLOCKS),
+            short_term: windowed_lufs(&self.blocks, SHORT_TERM_BLOCKS),
+            integrated: gated_integrated_lufs(&self.blocks),
+        }
+    }
+}
+
+/// Measure the integrated loudness of an already-decoded buffer in one
+/// shot, for callers that want a single LUFS figure up front (e.g. static
+/// gain normalization) rather than the incremental readings [`LoudnessMeter`]
+/// is built for.
+pub fn measure_integrated_lufs(samples: &[f32], channels: usize, sample_rate: u32) -> f32 {
+    let mut meter = LoudnessMeter::new(channels, sample_rate);
+    meter.feed(samples);
+    meter.current().integrated
+}
+
+fn mean_square_to_lufs(mean_square: f32) -> f32 {
+    if mean_square <= 0.0 {
+        f32::NEG_INFINITY
+    } else {
+        -0.691 + 10.0 * mean_square.log10()
+    }
+}
+
+fn windowed_lufs(blocks: &[f32], window: usize) -> f32 {
+    if blocks.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let window = &blocks[blocks.len().saturating_sub(window)..];
+    let mean = window.iter().sum::<f32>() / window.len() as f32;
+    mean_square_to_lufs(mean)
+}
+
+/// Mean loudness over every block at least `ABSOLUTE_GATE_LUFS` loud, then
+/// re-averaged over only the blocks within `RELATIVE_GATE_LU` of that mean -
+/// the two-pass gating BS.1770-4 uses to keep silence from dragging the
+/// integrated figure down.
+fn gated_integrated_lufs(blocks: &[f32]) -> f32 {
+    let absolute_gated: Vec<f32> = blocks
+        .iter()
+        .copied()
+        .filter(|&block| mean_square_to_lufs(block) >= ABSOLUTE_GATE_LUFS)
+        .collect();
+    if absolute_gated.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let ungated_mean = absolute_gated.iter().sum::<f32>() / absolute_gated.len() as f32;
+    let relative_gate = mean_square_to_lufs(ungated_mean) + RELATIVE_GATE_LU;
+
+    let relative_gated: Vec<f32> = absolute_gated
+        .into_iter()
+        .filter(|&block| mean_square_to_lufs(block) >= relative_gate)
+        .collect();
+    if relative_gated.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let mean = relative_gated.iter().sum::<f32>() / relative_gated.len() as f32;
+    mean_square_to_lufs(mean)
+}
@@ -0,0 +1,1769 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use auto_abloop::analysis::{self, AnalysisSettings, DetectionMode, LoopSelectionPolicy, NormalizationMode};
+use auto_abloop::audio;
+use auto_abloop::config;
+use auto_abloop::error::AbloopError;
+use auto_abloop::export;
+use auto_abloop::report;
+#[cfg(feature = "gui")]
+use auto_abloop::gui::AbloopApp;
+use auto_abloop::i18n::{self, Lang};
+#[cfg(feature = "loop-db")]
+use auto_abloop::loop_db::LoopDb;
+#[cfg(feature = "playback")]
+use auto_abloop::player::{Player, PlayerOptions};
+#[cfg(feature = "playback")]
+use auto_abloop::tui;
+use clap::{Args, Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// CLI flags mirroring [`AnalysisSettings`], shared by `analyze` and
+/// `export`.
+#[derive(Args, Clone)]
+struct AnalysisArgs {
+    /// Loop point detection mode.
+    #[arg(long, value_enum, default_value_t = config::defaults().mode)]
+    mode: DetectionMode,
+    /// Gain-normalize the mono buffer before detection, so the thresholds
+    /// below behave the same on very quiet and very loud masters.
+    #[arg(long, value_enum, default_value_t = config::defaults().normalize)]
+    normalize: NormalizationMode,
+    /// Candidate loop starts within this many seconds of the end of the
+    /// track are not considered.
+    #[arg(long, default_value_t = config::defaults().min_loop_duration)]
+    min_loop_duration: f64,
+    /// Length, in frames, of the window compared between candidate loop
+    /// starts and the track's tail.
+    #[arg(long, default_value_t = config::defaults().correlation_window_frames)]
+    correlation_window_frames: usize,
+    /// Minimum normalized cross-correlation (0.0-1.0) required to accept a
+    /// loop point.
+    #[arg(long, default_value_t = config::defaults().correlation_threshold)]
+    correlation_threshold: f32,
+    /// A drop of this many dB from the track's overall RMS level, sustained
+    /// to the end of the track, is treated as a fade-out.
+    #[arg(long, default_value_t = config::defaults().fade_out_threshold_db)]
+    fade_out_threshold_db: f32,
+    /// When several candidates score within 0.02 of each other, which one
+    /// to pick.
+    #[arg(long, value_enum, default_value_t = config::defaults().loop_selection_policy)]
+    loop_selection_policy: LoopSelectionPolicy,
+    /// Preset name - a user-defined one from `[presets.<name>]` in the
+    /// config file, or a built-in (`game-music`, `classical-long-tail`,
+    /// `electronic`, `ambient`, `jingle`) - that sets every flag above at once;
+    /// overrides all of them when given.
+    #[arg(long)]
+    preset: Option<String>,
+}
+
+impl AnalysisArgs {
+    fn into_settings(self, sample_rate: u32) -> Result<AnalysisSettings> {
+        if let Some(name) = &self.preset {
+            let values = config::defaults()
+                .resolve_preset(name)
+                .with_context(|| format!("unknown analysis preset {name:?}"))?;
+            return values.into_settings(sample_rate).map_err(Into::into);
+        }
+        AnalysisSettings::builder()
+            .mode(self.mode)
+            .normalize(self.normalize)
+            .min_loop_duration_frames((self.min_loop_duration * sample_rate as f64) as u64)
+            .correlation_window_frames(self.correlation_window_frames)
+            .correlation_threshold(self.correlation_threshold)
+            .fade_out_threshold_db(self.fade_out_threshold_db)
+            .loop_selection_policy(self.loop_selection_policy)
+            .build()
+            .map_err(Into::into)
+    }
+}
+
+/// CLI flags mirroring [`export::ExportSettings`].
+#[derive(Args, Clone)]
+struct ExportArgs {
+    /// Output container/codec.
+    #[arg(long, value_enum, default_value_t = config::defaults().export_format)]
+    format: export::ExportFormat,
+    /// PCM sample width in bits: 8, 16, 24, or 32.
+    #[arg(long, default_value_t = config::defaults().bit_depth)]
+    bit_depth: u16,
+    /// Export for a specific game engine's loop convention instead of
+    /// picking `--format`/`--bit-depth`/`--skip-intro` by hand; overrides
+    /// all three when given.
+    #[arg(long, value_enum)]
+    preset: Option<export::ExportPreset>,
+    /// Bitrate in kbps; only valid for lossy formats.
+    #[arg(long)]
+    bitrate: Option<u32>,
+    /// Crossfade this many seconds across the loop seam, instead of a hard
+    /// cut.
+    #[arg(long)]
+    crossfade: Option<f64>,
+    /// Render a fixed-length file of this many seconds by repeating the
+    /// loop region, instead of embedding loop metadata for a player to
+    /// cycle itself.
+    #[arg(long)]
+    target_duration: Option<f64>,
+    /// Start the render at the loop's start point instead of the track's
+    /// beginning, dropping the intro entirely.
+    #[arg(long)]
+    skip_intro: bool,
+    /// What to do if the render clips (exceeds full scale): just warn, hard
+    /// limit the peaks, or reduce the whole render's gain to fit.
+    #[arg(long, value_enum, default_value_t = export::ClipHandling::default())]
+    clip_handling: export::ClipHandling,
+    /// Save the loop point used for this export to the loop database
+    /// (`--features loop-db`), so later runs on the same audio content
+    /// reuse it instead of re-detecting.
+    #[cfg(feature = "loop-db")]
+    #[arg(long)]
+    confirm_loop: bool,
+    /// Also write a `<file>.json` Howler.js-style audio-sprite descriptor
+    /// (offset, duration, and loop flag, in the `{urls, sprite}`
+    /// convention) alongside the export, for dropping straight into a web
+    /// game engine.
+    #[arg(long)]
+    audiosprite: bool,
+    /// Also write a `<file>.reaper-regions.csv` with the loop region and
+    /// fade-out marked, importable via Reaper's Region/Marker Manager.
+    #[arg(long)]
+    reaper_markers: bool,
+    /// Also write a `<file>.ardour-locations.xml` `<Locations>` fragment
+    /// with the loop region and fade-out marked, to paste into an Ardour
+    /// session's own XML file.
+    #[arg(long)]
+    ardour_markers: bool,
+}
+
+impl ExportArgs {
+    fn into_settings(self, sample_rate: u32) -> export::ExportSettings {
+        let mut settings = match self.preset {
+            Some(preset) => {
+                if sample_rate != preset.expected_sample_rate() {
+                    log::warn!(
+                        "{} Hz doesn't match the {} preset's expected {} Hz; exporting without resampling",
+                        sample_rate,
+                        preset.name(),
+                        preset.expected_sample_rate()
+                    );
+                }
+                preset.settings()
+            }
+            None => export::ExportSettings {
+                format: self.format,
+                bit_depth: self.bit_depth,
+                skip_intro: self.skip_intro,
+                ..export::ExportSettings::default()
+            },
+        };
+        settings.bitrate_kbps = self.bitrate;
+        settings.crossfade_frames = self
+            .crossfade
+            .map_or(0, |seconds| (seconds * sample_rate as f64) as u64);
+        settings.target_duration_frames = self
+            .target_duration
+            .map(|seconds| (seconds * sample_rate as f64) as u64);
+        settings.clip_handling = self.clip_handling;
+        settings
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "auto-abloop", version, about)]
+struct Cli {
+    /// How to report a failure: a human-readable line, or a JSON object
+    /// with `error` and `kind` fields, for scripts to parse.
+    #[arg(long, value_enum, default_value = "text", global = true)]
+    errors: ErrorFormat,
+    /// Silence progress bars and status messages, printing only results
+    /// and errors.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Print more detail (repeat for more: -v is debug, -vv is trace).
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Language for CLI messages (e.g. `en`, `es`). Defaults to the
+    /// system locale, falling back to English.
+    #[arg(long, global = true)]
+    lang: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Start the logger at a level controlled by `-q`/`-v`/`-vv`, overridable
+/// with `RUST_LOG` for ad hoc debugging.
+fn init_logging(quiet: bool, verbose: u8) {
+    let default_level = if quiet {
+        "warn"
+    } else {
+        match verbose {
+            0 => "info",
+            1 => "debug",
+            _ => "trace",
+        }
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level))
+        .format_timestamp(None)
+        .format_target(false)
+        .init();
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+#[value(rename_all = "lowercase")]
+enum ErrorFormat {
+    Text,
+    Json,
+}
+
+/// What `play` does when a detected loop's confidence is below
+/// `--min-confidence`, instead of trusting any match that merely cleared
+/// `--correlation-threshold`. Has no effect on a loop confirmed via the
+/// loop database/fingerprint match or already embedded in the file, since
+/// those carry no detector confidence to distrust.
+#[cfg(feature = "playback")]
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+enum LowConfidenceAction {
+    /// Don't auto-start looped playback; play the track straight through
+    /// instead.
+    #[default]
+    Skip,
+    /// Loop anyway, but print a warning (and, with `--tui`, show one in
+    /// the status line) about the low confidence.
+    Warn,
+    /// Repeat the whole track instead of just the detected loop region.
+    FullTrackRepeat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Detect loop points (and fade-out) in a file and print a report.
+    Analyze {
+        /// Audio file to analyze, or `-` to read from stdin.
+        file: PathBuf,
+        /// Print a machine-readable JSON report instead of a summary.
+        #[arg(long)]
+        json: bool,
+        /// Format hint (e.g. `flac`) used when reading from stdin, where
+        /// there's no file extension to probe with.
+        #[arg(long)]
+        hint: Option<String>,
+        /// Print decode/analysis timing and the top candidate loop starts
+        /// considered, not just the winner, to stderr.
+        #[arg(long)]
+        debug_analysis: bool,
+        /// Print codec, duration, sample rate, channels, and tags without
+        /// decoding or analyzing the file.
+        #[arg(long)]
+        probe_only: bool,
+        /// After a loop is found, nudge its start/end within a small
+        /// window to minimize the seam's audible discontinuity, instead of
+        /// keeping the best cross-correlation match as-is.
+        #[arg(long)]
+        optimize_seam: bool,
+        /// When a loop is found, write a `<file>.abloop.json` sidecar (and,
+        /// with `--features loop-db`, a loop database entry) so the result
+        /// survives without an explicit `export` step.
+        #[arg(long, default_value_t = config::defaults().write_sidecar)]
+        write_sidecar: bool,
+        /// Render each of the top candidate loop starts (the same ones
+        /// `--debug-analysis` prints) as its own seam-preview file in this
+        /// directory, named with a timestamp and confidence, so candidates
+        /// can be compared by ear outside the app instead of by confidence
+        /// score alone.
+        #[arg(long)]
+        export_candidates: Option<PathBuf>,
+        /// Length, in seconds, of the seam preview `--export-candidates`
+        /// renders around each candidate's loop point (the audio just
+        /// before the loop end spliced to the audio just after its start).
+        /// 0 renders each candidate's full loop instead of just the seam.
+        #[arg(long, default_value_t = 3.0)]
+        candidate_preview_seconds: f64,
+        /// Gain-match the audio just after the loop point to the level of
+        /// the audio just before it in `--export-candidates` previews, so a
+        /// level difference between the two doesn't mask a timing or phase
+        /// problem at the seam.
+        #[arg(long)]
+        match_preview_loudness: bool,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+    },
+    /// Analyze and export looped WAV/AIFF renders for a batch of files.
+    ///
+    /// Inputs may be files, directories (searched recursively), or glob
+    /// patterns (e.g. `./ost/**/*.flac`).
+    Export {
+        /// Files, directories, or glob patterns to process.
+        inputs: Vec<String>,
+        /// Directory to write looped `.wav`/`.aiff` files into, or `-` to stream a
+        /// single input's render to stdout. Defaults to the config file's
+        /// `output_dir_template`, resolved per input file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Process files concurrently instead of one at a time.
+        #[arg(long)]
+        parallel: bool,
+        /// Print the resolved queue (codec, duration, sample rate, channels)
+        /// and exit without analyzing or exporting anything.
+        #[arg(long)]
+        list: bool,
+        /// After processing the batch, write a CSV (or HTML, if the path
+        /// ends in `.html`/`.htm`) report of each file's duration, detected
+        /// loop point, confidence, seam cost, and fade-out.
+        #[arg(long)]
+        report: Option<PathBuf>,
+        /// Memory-map each input instead of reading it into a buffer up
+        /// front (see `auto_abloop::audio::LoadOptions::mmap`), lowering
+        /// peak memory and speeding repeated runs over the same large
+        /// files. Assumes nothing else modifies an input while it's mapped.
+        #[arg(long)]
+        mmap: bool,
+        /// Use a loop point from this file instead of detecting one - a
+        /// JSON sidecar (`analyze --write-sidecar`'s format), an Audacity
+        /// label track, or plain `start,end` frame numbers (see
+        /// `auto_abloop::import::parse_loop_points`). Applied to every
+        /// input in the batch, so this is normally used with a single file.
+        #[arg(long)]
+        import_loop: Option<PathBuf>,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Play one or more files locally, looping each the same way `export`
+    /// would and moving on to the next once it ends (or is skipped via
+    /// `--remote`). While a track plays, the next one in the queue is
+    /// decoded and analyzed in the background so switching is instant.
+    #[cfg(feature = "playback")]
+    Play {
+        /// Audio file(s) to play, in order.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Use the interactive terminal UI (ASCII waveform, loop markers,
+        /// play/pause/seek) instead of plain playback.
+        #[arg(long)]
+        tui: bool,
+        /// Start playback at this position instead of the beginning, e.g.
+        /// `2:45`, `1:02:03`, or a plain number of seconds.
+        #[arg(long, value_parser = parse_timestamp)]
+        seek: Option<f64>,
+        /// Remove the center channel (mid-side decomposition, keeping only
+        /// the stereo difference) so vocals mixed dead center drop out,
+        /// for looping instrumentals of vocal tracks to practice over.
+        /// Has no effect on anything other than 2-channel audio.
+        #[arg(long)]
+        karaoke: bool,
+        /// Measure the track's integrated loudness up front and apply a
+        /// static gain to bring it to -16 LUFS, so looping a mixed
+        /// playlist of old game rips and modern masters plays back at
+        /// consistent volume instead of needing per-track manual volume
+        /// adjustments.
+        #[arg(long)]
+        normalize: bool,
+        /// Prefer a lower-buffering output device configuration where the
+        /// backend reports one, for loop-seam auditioning and marker
+        /// nudging that feels immediate. `cpal`'s portable API has no way
+        /// to request an exact buffer size or true exclusive mode (WASAPI
+        /// exclusive, small CoreAudio/ALSA buffers), so this only narrows
+        /// the choice among what the device already advertises.
+        #[arg(long)]
+        low_latency: bool,
+        /// Mix a click track into playback at the track's estimated tempo
+        /// (see [`auto_abloop::analysis::estimate_bpm`]), so a loop point
+        /// landing off-beat is audible at a glance. This is a coarse,
+        /// simplified tempo estimate, not full beat-tracking - it can drift
+        /// on tracks with a weak or changing pulse.
+        #[arg(long)]
+        metronome: bool,
+        /// Headphone crossfeed intensity (0.0-1.0, 0.0 is off): blend this
+        /// fraction of each stereo channel into the other, for long
+        /// listening sessions on headphones. Persisted via `crossfeed` in
+        /// `config.toml`.
+        #[arg(long, default_value_t = config::defaults().crossfeed)]
+        crossfeed: f32,
+        /// Cycle the detected loop only this many times, then let playback
+        /// run past its end instead of looping forever. With `--tui`, the
+        /// status line counts down "Loop N / M - MM:SS remaining".
+        #[arg(long)]
+        loop_count: Option<u64>,
+        /// Stream whatever plays to this path as Ogg Vorbis, encoded
+        /// on the fly with bounded memory so e.g. `--loop-count 200
+        /// --record session.ogg` doesn't mean pre-rendering hours of
+        /// audio into RAM first. Not compatible with `--album`.
+        #[arg(long)]
+        record: Option<PathBuf>,
+        /// Reject a detected loop whose confidence is below this (0.0-1.0)
+        /// per `--on-low-confidence`, instead of trusting any match that
+        /// merely cleared `--correlation-threshold`. Has no effect on a
+        /// loop confirmed via the loop database/fingerprint match or
+        /// already embedded in the file.
+        #[arg(long)]
+        min_confidence: Option<f32>,
+        /// What to do when `--min-confidence` rejects the detected loop.
+        #[arg(long, value_enum, default_value_t = LowConfidenceAction::default())]
+        on_low_confidence: LowConfidenceAction,
+        /// Also listen on this address for a line-delimited JSON control
+        /// protocol (load/play/pause/seek/set_loop/set_volume), so show or
+        /// lighting software can drive playback.
+        #[cfg(feature = "remote")]
+        #[arg(long)]
+        remote: Option<String>,
+        /// Play the queue as an endless background-music mix instead of
+        /// stopping after the last file: each track cycles its detected
+        /// loop `--loop-repeats` times, then crossfades into the next,
+        /// wrapping back to the first file forever. Not compatible with
+        /// `--tui`.
+        #[arg(long)]
+        album: bool,
+        /// With `--album`, how many times each track's loop repeats before
+        /// crossfading into the next track.
+        #[arg(long, default_value_t = 2)]
+        loop_repeats: u32,
+        /// With `--album`, how many seconds to crossfade between tracks.
+        #[arg(long, default_value_t = 4.0)]
+        crossfade: f64,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+    },
+    /// Manage the `~/.config/auto-abloop/config.toml` defaults file.
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Run an HTTP server exposing analysis/render over `POST /analyze`
+    /// and `POST /render`, for sites that want to tag or render looping
+    /// audio without embedding the library.
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Speak a small JSON-RPC 2.0 protocol over stdin/stdout (load,
+    /// analyze, get_result, export, play, stop), for editors and other
+    /// apps to embed this tool as a child process instead of parsing
+    /// human-oriented output.
+    #[cfg(feature = "playback")]
+    Rpc,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Write a commented config.toml template with every default spelled
+    /// out for editing.
+    Init {
+        /// Where to write the template; defaults to the platform config
+        /// directory.
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Overwrite an existing file.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Parse a playback offset given as seconds (`165`), `MM:SS` (`2:45`), or
+/// `HH:MM:SS` (`1:02:03`).
+#[cfg(feature = "playback")]
+fn parse_timestamp(s: &str) -> Result<f64, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let parse_part = |part: &str| part.parse::<f64>().map_err(|_| format!("invalid timestamp `{s}`"));
+    match parts.as_slice() {
+        [seconds] => parse_part(seconds),
+        [minutes, seconds] => Ok(parse_part(minutes)? * 60.0 + parse_part(seconds)?),
+        [hours, minutes, seconds] => {
+            Ok(parse_part(hours)? * 3600.0 + parse_part(minutes)? * 60.0 + parse_part(seconds)?)
+        }
+        _ => Err(format!("invalid timestamp `{s}` (expected SS, MM:SS, or HH:MM:SS)")),
+    }
+}
+
+/// Failure categories a wrapping script can branch on by exit code, without
+/// parsing error text.
+#[derive(Debug, Clone, Copy)]
+enum CliErrorKind {
+    NoLoopFound,
+    DecodeFailure,
+    UnsupportedFormat,
+    ExportError,
+    ImportError,
+}
+
+impl CliErrorKind {
+    fn exit_code(self) -> i32 {
+        match self {
+            CliErrorKind::NoLoopFound => 2,
+            CliErrorKind::DecodeFailure => 3,
+            CliErrorKind::UnsupportedFormat => 4,
+            CliErrorKind::ExportError => 5,
+            CliErrorKind::ImportError => 6,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CliErrorKind::NoLoopFound => "no_loop_found",
+            CliErrorKind::DecodeFailure => "decode_failure",
+            CliErrorKind::UnsupportedFormat => "unsupported_format",
+            CliErrorKind::ExportError => "export_error",
+            CliErrorKind::ImportError => "import_error",
+        }
+    }
+}
+
+/// Wraps an error with the [`CliErrorKind`] that decides its exit code,
+/// while keeping the original message for display.
+#[derive(Debug)]
+struct CliError {
+    kind: CliErrorKind,
+    message: String,
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Classify a decode/probe failure from [`audio`] as an unsupported
+/// format (no matching demuxer/codec) or a more general decode failure
+/// (a truncated or corrupt file of an otherwise-supported format).
+fn classify_load_error(err: AbloopError) -> anyhow::Error {
+    let kind = match err {
+        AbloopError::UnsupportedFormat(_) => CliErrorKind::UnsupportedFormat,
+        _ => CliErrorKind::DecodeFailure,
+    };
+    CliError { kind, message: format!("{err}") }.into()
+}
+
+/// Map an [`AbloopError`] returned by the export pipeline onto the
+/// [`CliErrorKind`] a wrapping script can branch on, the same way
+/// [`classify_load_error`] does for the load path.
+fn classify_export_error(err: AbloopError) -> anyhow::Error {
+    CliError { kind: CliErrorKind::ExportError, message: format!("{err}") }.into()
+}
+
+/// Map an [`AbloopError`] returned by [`auto_abloop::import::import_loop_points`]
+/// onto the [`CliErrorKind`] a wrapping script can branch on.
+fn classify_import_error(err: AbloopError) -> anyhow::Error {
+    CliError { kind: CliErrorKind::ImportError, message: format!("{err}") }.into()
+}
+
+fn exit_code_for(err: &anyhow::Error) -> i32 {
+    err.downcast_ref::<CliError>().map_or(1, |err| err.kind.exit_code())
+}
+
+fn report_error(err: &anyhow::Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {err:#}"),
+        ErrorFormat::Json => {
+            let kind = err.downcast_ref::<CliError>().map_or("other", |err| err.kind.as_str());
+            println!(
+                "{}",
+                serde_json::json!({ "error": format!("{err:#}"), "kind": kind })
+            );
+        }
+    }
+}
+
+fn main() -> std::process::ExitCode {
+    let cli = Cli::parse();
+    let errors = cli.errors;
+    let lang = Lang::from_args_or_env(cli.lang.as_deref());
+    init_logging(cli.quiet, cli.verbose);
+    let result = match cli.command {
+        Some(Command::Analyze {
+            file,
+            json,
+            hint,
+            debug_analysis,
+            probe_only,
+            optimize_seam,
+            write_sidecar,
+            export_candidates,
+            candidate_preview_seconds,
+            match_preview_loudness,
+            analysis,
+        }) => {
+            if probe_only {
+                probe_only_report(&file, hint.as_deref(), json)
+            } else {
+                analyze(
+                    &file,
+                    hint.as_deref(),
+                    AnalyzeOptions {
+                        json,
+                        debug_analysis,
+                        optimize_seam,
+                        write_sidecar,
+                        export_candidates,
+                        candidate_preview_seconds,
+                        match_preview_loudness,
+                    },
+                    cli.quiet,
+                    lang,
+                    analysis,
+                )
+            }
+        }
+        Some(Command::Export {
+            inputs,
+            output,
+            parallel,
+            list,
+            report,
+            mmap,
+            import_loop,
+            analysis,
+            export,
+        }) => {
+            if list {
+                list_queue(&inputs)
+            } else {
+                export_batch(
+                    &inputs,
+                    ExportBatchOptions { output, parallel, report, mmap, import_loop },
+                    cli.quiet,
+                    lang,
+                    analysis,
+                    export,
+                )
+            }
+        }
+        #[cfg(all(feature = "playback", feature = "remote"))]
+        Some(Command::Play {
+            files, tui, seek, karaoke, normalize, low_latency, metronome, crossfeed, loop_count, record,
+            min_confidence, on_low_confidence, remote, album, loop_repeats, crossfade, analysis,
+        }) => {
+            if album {
+                play_album(&files, tui, loop_repeats, crossfade, lang, &analysis)
+            } else {
+                play(
+                    &files, tui, seek, karaoke, normalize, low_latency, metronome, crossfeed, loop_count, record,
+                    min_confidence, on_low_confidence, remote, lang, analysis,
+                )
+            }
+        }
+        #[cfg(all(feature = "playback", not(feature = "remote")))]
+        Some(Command::Play {
+            files, tui, seek, karaoke, normalize, low_latency, metronome, crossfeed, loop_count, record,
+            min_confidence, on_low_confidence, album, loop_repeats, crossfade, analysis,
+        }) => {
+            if album {
+                play_album(&files, tui, loop_repeats, crossfade, lang, &analysis)
+            } else {
+                play(
+                    &files, tui, seek, karaoke, normalize, low_latency, metronome, crossfeed, loop_count, record,
+                    min_confidence,
+                    on_low_confidence, lang, analysis,
+                )
+            }
+        }
+        Some(Command::Config {
+            action: ConfigCommand::Init { path, force },
+        }) => config_init(path, force, lang),
+        #[cfg(feature = "server")]
+        Some(Command::Serve { addr }) => auto_abloop::server::serve(&addr).map_err(Into::into),
+        #[cfg(feature = "playback")]
+        Some(Command::Rpc) => auto_abloop::rpc::serve_stdio(),
+        #[cfg(feature = "gui")]
+        None => run_gui(),
+        #[cfg(not(feature = "gui"))]
+        None => {
+            use clap::CommandFactory;
+            Cli::command().print_help().context("printing help")
+        }
+    };
+
+    match result {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(err) => {
+            let code = exit_code_for(&err);
+            report_error(&err, errors);
+            std::process::ExitCode::from(code as u8)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+fn run_gui() -> Result<()> {
+    eframe::run_native(
+        "auto-abloop",
+        eframe::NativeOptions::default(),
+        Box::new(|_cc| Box::<AbloopApp>::default()),
+    )
+    .map_err(|err| anyhow::anyhow!(err.to_string()))
+}
+
+/// What `analyze --json` prints: the result plus the settings used to
+/// produce it, so the output is reproducible without out-of-band context.
+#[derive(Serialize)]
+struct AnalyzeReport {
+    file: PathBuf,
+    settings: AnalysisSettings,
+    #[serde(flatten)]
+    result: analysis::AnalysisResult,
+}
+
+/// How many top candidates `--debug-analysis` dumps.
+const DEBUG_TOP_CANDIDATES: usize = 10;
+
+/// The `analyze` subcommand's non-analysis, non-input flags, grouped so
+/// `analyze()` doesn't take them all separately.
+struct AnalyzeOptions {
+    json: bool,
+    debug_analysis: bool,
+    optimize_seam: bool,
+    write_sidecar: bool,
+    export_candidates: Option<PathBuf>,
+    candidate_preview_seconds: f64,
+    match_preview_loudness: bool,
+}
+
+fn analyze(
+    file: &PathBuf,
+    hint: Option<&str>,
+    options: AnalyzeOptions,
+    quiet: bool,
+    lang: Lang,
+    analysis_args: AnalysisArgs,
+) -> Result<()> {
+    let AnalyzeOptions {
+        json,
+        debug_analysis,
+        optimize_seam,
+        write_sidecar,
+        export_candidates,
+        candidate_preview_seconds,
+        match_preview_loudness,
+    } = options;
+    let need_candidates = debug_analysis || export_candidates.is_some();
+    let decode_start = Instant::now();
+    let data = if file == Path::new("-") {
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut bytes)
+            .context("reading stdin")?;
+        audio::load_audio_from_bytes(bytes, hint).map_err(classify_load_error)?
+    } else if need_candidates {
+        audio::load_audio_from_path(file).map_err(classify_load_error)?
+    } else {
+        let decode_bar = progress_bar(i18n::decoding(lang), quiet);
+        let data = audio::load_audio_from_path_with_progress(
+            file,
+            audio::LoadOptions::default(),
+            |progress| {
+                if let Some(total) = progress.total_frames {
+                    decode_bar.set_length(total);
+                }
+                decode_bar.set_position(progress.frames_decoded);
+            },
+        )
+        .map_err(classify_load_error)?;
+        decode_bar.finish_and_clear();
+        data
+    };
+    let decode_elapsed = decode_start.elapsed();
+
+    let settings = analysis_args.into_settings(data.sample_rate)?;
+
+    let analysis_start = Instant::now();
+    let mut top_candidates = Vec::new();
+    let mut result = if need_candidates {
+        let debug = analysis::detect_loop_debug(&data, &settings, DEBUG_TOP_CANDIDATES);
+        let analysis_elapsed = analysis_start.elapsed();
+
+        if debug_analysis {
+            eprintln!("decode:   {decode_elapsed:?}");
+            eprintln!("analysis: {analysis_elapsed:?}");
+            eprintln!("top {} loop-start candidates:", debug.top_candidates.len());
+            for (rank, candidate) in debug.top_candidates.iter().enumerate() {
+                eprintln!(
+                    "  #{:<2} frame {:>10} confidence {:.4} rms_ratio {:.3}",
+                    rank + 1,
+                    candidate.start_frame,
+                    candidate.confidence,
+                    candidate.rms_ratio
+                );
+            }
+        }
+
+        top_candidates = debug.top_candidates;
+        debug.result
+    } else {
+        let analysis_bar = progress_bar(i18n::analyzing(lang), quiet);
+        analysis_bar.set_length(1000);
+        let result = analysis::detect_loop_with_progress(&data, &settings, |fraction| {
+            analysis_bar.set_position((fraction * 1000.0) as u64);
+        });
+        analysis_bar.finish_and_clear();
+        result
+    };
+
+    if let (true, Some(candidate)) = (optimize_seam, result.loop_points) {
+        let optimized = analysis::optimize_loop_points(
+            &data,
+            audio::LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame },
+        );
+        if !quiet {
+            eprintln!(
+                "optimized seam: start {} -> {}, end {} -> {} (seam cost {:.4})",
+                candidate.start_frame, optimized.start_frame,
+                candidate.end_frame, optimized.end_frame,
+                optimized.seam_cost
+            );
+        }
+        result.loop_points = Some(analysis::LoopCandidate {
+            start_frame: optimized.start_frame,
+            end_frame: optimized.end_frame,
+            confidence: candidate.confidence,
+        });
+    }
+
+    let found_loop = result.loop_points.is_some();
+
+    if found_loop && write_sidecar {
+        let report = AnalyzeReport {
+            file: file.clone(),
+            settings,
+            result: result.clone(),
+        };
+        if let Err(err) = write_sidecar_file(file, &report) {
+            log::warn!("failed to write sidecar for {}: {err:#}", file.display());
+        }
+        #[cfg(feature = "loop-db")]
+        if let Some(candidate) = result.loop_points {
+            confirm_loop(
+                &data,
+                audio::LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame },
+            );
+            #[cfg(feature = "fingerprint")]
+            confirm_fingerprint(
+                &data,
+                audio::LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame },
+            );
+        }
+    }
+
+    if let Some(dir) = &export_candidates {
+        let frame_count = data.samples.len() as u64 / data.channels as u64;
+        let effective_end = result.fade_out.map_or(frame_count, |fade_out| fade_out.start_frame);
+        let candidates: Vec<(audio::LoopPoints, f32)> = top_candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    audio::LoopPoints { start_frame: candidate.start_frame, end_frame: effective_end },
+                    candidate.confidence,
+                )
+            })
+            .collect();
+        let paths = export::export_candidate_previews(
+            &data,
+            &candidates,
+            candidate_preview_seconds,
+            match_preview_loudness,
+            &export::ExportSettings::default(),
+            dir,
+        )?;
+        if !quiet {
+            eprintln!("wrote {} candidate preview(s) to {}", paths.len(), dir.display());
+        }
+    }
+
+    if json {
+        let report = AnalyzeReport {
+            file: file.clone(),
+            settings,
+            result,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return no_loop_found_err(found_loop);
+    }
+
+    match result.loop_points {
+        Some(loop_points) => println!(
+            "{}",
+            i18n::loop_found(
+                lang,
+                loop_points.start_frame,
+                loop_points.end_frame,
+                loop_points.confidence
+            )
+        ),
+        None => println!(
+            "{} ({})",
+            i18n::loop_not_found(lang),
+            i18n::loop_detection_outcome_reason(lang, result.outcome)
+        ),
+    }
+    match result.fade_out {
+        Some(fade_out) => println!(
+            "{}",
+            i18n::fade_out_found(lang, fade_out.start_frame, fade_out.confidence)
+        ),
+        None => println!("{}", i18n::fade_out_not_found(lang)),
+    }
+
+    no_loop_found_err(found_loop)
+}
+
+#[derive(Serialize)]
+struct ProbeReport {
+    file: PathBuf,
+    codec: &'static str,
+    sample_rate: u32,
+    channels: u16,
+    duration_secs: Option<f64>,
+    title: Option<String>,
+    artist: Option<String>,
+}
+
+impl ProbeReport {
+    fn new(file: PathBuf, info: audio::FormatInfo) -> Self {
+        let audio::FormatInfo { codec, sample_rate, channels, duration_secs, title, artist } = info;
+        Self { file, codec, sample_rate, channels, duration_secs, title, artist }
+    }
+}
+
+/// `analyze --probe-only`: print a file's container/codec summary without
+/// decoding or analyzing it.
+fn probe_only_report(file: &Path, hint: Option<&str>, json: bool) -> Result<()> {
+    let _ = hint; // only meaningful for stdin input, which --probe-only doesn't support
+    if file == Path::new("-") {
+        bail!("--probe-only requires a file, reading from stdin (`-`) isn't supported");
+    }
+    let info = audio::probe(file).map_err(classify_load_error)?;
+    let report = ProbeReport::new(file.to_path_buf(), info);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "{}: {} Hz, {} channel(s), {}{}{}",
+            report.file.display(),
+            report.sample_rate,
+            report.channels,
+            report.codec,
+            report.duration_secs.map(|secs| format!(", {secs:.1}s")).unwrap_or_default(),
+            report
+                .title
+                .as_deref()
+                .map(|title| format!(" - {title}"))
+                .unwrap_or_default(),
+        );
+    }
+    Ok(())
+}
+
+/// `Ok(())` if a loop was found, otherwise a [`CliErrorKind::NoLoopFound`]
+/// error, so scripts can tell "ran fine, nothing to loop" apart from
+/// success by exit code alone.
+fn no_loop_found_err(found_loop: bool) -> Result<()> {
+    if found_loop {
+        Ok(())
+    } else {
+        Err(CliError { kind: CliErrorKind::NoLoopFound, message: "no loop point found".into() }.into())
+    }
+}
+
+/// Write `report` to `input`'s sidecar path as pretty-printed JSON, the same
+/// shape `analyze --json` prints to stdout.
+fn write_sidecar_file(input: &Path, report: &AnalyzeReport) -> Result<()> {
+    let path = auto_abloop::import::sidecar_path(input);
+    std::fs::write(&path, serde_json::to_string_pretty(report)?)
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// A progress bar with an ETA, labeled with what it's tracking. Written to
+/// stderr by default, so it never ends up mixed into `--json` output;
+/// hidden entirely under `--quiet`.
+fn progress_bar(label: &str, quiet: bool) -> ProgressBar {
+    if quiet {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template(&format!(
+            "{{spinner}} {label} [{{bar:30}}] {{pos}}/{{len}} (eta {{eta}})"
+        ))
+        .unwrap(),
+    );
+    bar
+}
+
+/// Expand CLI inputs (files, directories, or glob patterns) into a sorted,
+/// deduplicated list of files.
+fn expand_inputs(inputs: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let pattern = if Path::new(input).is_dir() {
+            format!("{}/**/*", input.trim_end_matches('/'))
+        } else {
+            input.clone()
+        };
+        for entry in glob::glob(&pattern).with_context(|| format!("invalid pattern {input}"))? {
+            let entry = entry?;
+            if entry.is_file() {
+                files.push(entry);
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+struct ExportOutcome {
+    input: PathBuf,
+    result: Result<()>,
+}
+
+/// Load and analyze a single input, returning the decoded audio, the loop
+/// points to export, and (only when those loop points came from
+/// detection, not a confirmed or embedded match) the detector's
+/// confidence in them. Order of preference: an explicit `--import-loop`
+/// file (the user said exactly what to use), a confirmed entry in the
+/// loop database (`--features loop-db`, exact content match), a
+/// confirmed fingerprint match (`--features fingerprint`, tolerant of
+/// re-encodes), then detection, then any loop already embedded in the
+/// file.
+fn analyze_for_export(
+    input: &Path,
+    analysis_args: &AnalysisArgs,
+    mmap: bool,
+    import_loop: Option<&Path>,
+) -> Result<(audio::AudioData, Option<audio::LoopPoints>, Option<f32>)> {
+    let options = audio::LoadOptions { mmap, ..Default::default() };
+    let data = audio::load_audio_from_path_with_progress(input, options, |_| {})
+        .map_err(classify_load_error)?;
+
+    if let Some(import_loop) = import_loop {
+        let loop_points = auto_abloop::import::import_loop_points(import_loop, data.sample_rate)
+            .map_err(classify_import_error)?;
+        return Ok((data, Some(loop_points), None));
+    }
+
+    #[cfg(feature = "loop-db")]
+    if let Some(confirmed) = confirmed_loop(&data) {
+        return Ok((data, Some(confirmed.into()), None));
+    }
+
+    #[cfg(feature = "fingerprint")]
+    if let Some(loop_points) = fingerprint_match(&data) {
+        return Ok((data, Some(loop_points), None));
+    }
+
+    let settings = analysis_args.clone().into_settings(data.sample_rate)?;
+    let analysis = analysis::detect_loop(&data, &settings);
+    let confidence = analysis.loop_points.map(|candidate| candidate.confidence);
+    let loop_points = analysis
+        .loop_points
+        .map(|candidate| audio::LoopPoints {
+            start_frame: candidate.start_frame,
+            end_frame: candidate.end_frame,
+        })
+        .or(data.loop_points);
+    Ok((data, loop_points, confidence))
+}
+
+/// Apply `--on-low-confidence` to `loop_points` if `confidence` is below
+/// `min_confidence` (no-op if either threshold or confidence is `None` -
+/// confirmed/embedded loops have no detector score to distrust). Returns
+/// the (possibly replaced) loop points plus a warning message to log and,
+/// with `--tui`, show in the status line.
+#[cfg(feature = "playback")]
+fn gate_low_confidence(
+    data: &audio::AudioData,
+    loop_points: Option<audio::LoopPoints>,
+    confidence: Option<f32>,
+    min_confidence: Option<f32>,
+    action: LowConfidenceAction,
+) -> (Option<audio::LoopPoints>, Option<String>) {
+    let (Some(min_confidence), Some(confidence)) = (min_confidence, confidence) else {
+        return (loop_points, None);
+    };
+    if confidence >= min_confidence {
+        return (loop_points, None);
+    }
+    match action {
+        LowConfidenceAction::Skip => (
+            None,
+            Some(format!(
+                "loop confidence {confidence:.2} is below --min-confidence {min_confidence:.2}; playing straight through"
+            )),
+        ),
+        LowConfidenceAction::Warn => (
+            loop_points,
+            Some(format!(
+                "loop confidence {confidence:.2} is below --min-confidence {min_confidence:.2}; looping anyway"
+            )),
+        ),
+        LowConfidenceAction::FullTrackRepeat => (
+            Some(audio::LoopPoints { start_frame: 0, end_frame: data.frame_count() }),
+            Some(format!(
+                "loop confidence {confidence:.2} is below --min-confidence {min_confidence:.2}; repeating the whole track instead"
+            )),
+        ),
+    }
+}
+
+/// Look up `data`'s content hash in the default loop database, logging
+/// (rather than failing the run on) any error opening or reading it.
+#[cfg(feature = "loop-db")]
+fn confirmed_loop(data: &audio::AudioData) -> Option<auto_abloop::loop_db::ConfirmedLoop> {
+    match LoopDb::open_default()? {
+        Ok(db) => match db.lookup(data) {
+            Ok(confirmed) => confirmed,
+            Err(err) => {
+                log::warn!("loop database lookup failed: {err}");
+                None
+            }
+        },
+        Err(err) => {
+            log::warn!("failed to open loop database: {err}");
+            None
+        }
+    }
+}
+
+/// Store `loop_points` for `data`'s content in the default loop database,
+/// logging (rather than failing the run on) any error.
+#[cfg(feature = "loop-db")]
+fn confirm_loop(data: &audio::AudioData, loop_points: audio::LoopPoints) {
+    let db = match LoopDb::open_default() {
+        Some(Ok(db)) => db,
+        Some(Err(err)) => {
+            log::warn!("failed to open loop database: {err}");
+            return;
+        }
+        None => {
+            log::warn!("no platform data directory; can't save to the loop database");
+            return;
+        }
+    };
+    if let Err(err) = db.store(data, loop_points) {
+        log::warn!("failed to save to loop database: {err}");
+    }
+}
+
+/// Fingerprints scoring at least this [`auto_abloop::fingerprint::similarity`]
+/// against a stored entry are treated as the same material.
+#[cfg(feature = "fingerprint")]
+const FINGERPRINT_MATCH_THRESHOLD: f32 = 0.85;
+
+/// Look up a confirmed loop point for `data` by fingerprint similarity,
+/// logging (rather than failing the run on) any error opening or reading
+/// the database.
+#[cfg(feature = "fingerprint")]
+fn fingerprint_match(data: &audio::AudioData) -> Option<audio::LoopPoints> {
+    use auto_abloop::fingerprint::FingerprintDb;
+    let query = auto_abloop::fingerprint::fingerprint(data);
+    match FingerprintDb::open_default()? {
+        Ok(db) => match db.lookup(&query, FINGERPRINT_MATCH_THRESHOLD) {
+            Ok(loop_points) => loop_points,
+            Err(err) => {
+                log::warn!("fingerprint database lookup failed: {err}");
+                None
+            }
+        },
+        Err(err) => {
+            log::warn!("failed to open fingerprint database: {err}");
+            None
+        }
+    }
+}
+
+/// Store `loop_points` under `data`'s fingerprint, logging (rather than
+/// failing the run on) any error.
+#[cfg(all(feature = "fingerprint", feature = "loop-db"))]
+fn confirm_fingerprint(data: &audio::AudioData, loop_points: audio::LoopPoints) {
+    use auto_abloop::fingerprint::FingerprintDb;
+    let db = match FingerprintDb::open_default() {
+        Some(Ok(db)) => db,
+        Some(Err(err)) => {
+            log::warn!("failed to open fingerprint database: {err}");
+            return;
+        }
+        None => {
+            log::warn!("no platform data directory; can't save to the fingerprint database");
+            return;
+        }
+    };
+    if let Err(err) = db.store(auto_abloop::fingerprint::fingerprint(data), loop_points) {
+        log::warn!("failed to save to fingerprint database: {err}");
+    }
+}
+
+/// Resolve the directory a file should be exported into: the explicit
+/// `--output` directory if given, otherwise `template` with `{dir}`
+/// replaced by the input file's own parent directory.
+fn resolve_output_dir(output: Option<&Path>, template: &str, input: &Path) -> PathBuf {
+    match output {
+        Some(dir) => dir.to_path_buf(),
+        None => {
+            let dir = input.parent().filter(|p| !p.as_os_str().is_empty());
+            let dir = dir.unwrap_or_else(|| Path::new("."));
+            PathBuf::from(template.replace("{dir}", &dir.to_string_lossy()))
+        }
+    }
+}
+
+/// Analyze and export a single file to `output_dir/<stem>.<ext>`, with the
+/// extension of the chosen `--format`.
+fn export_one(
+    input: &Path,
+    output_dir: &Path,
+    analysis_args: &AnalysisArgs,
+    export_args: &ExportArgs,
+    mmap: bool,
+    import_loop: Option<&Path>,
+) -> Result<()> {
+    let (data, loop_points, _confidence) = analyze_for_export(input, analysis_args, mmap, import_loop)?;
+    let settings = export_args.clone().into_settings(data.sample_rate);
+
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("creating {}", output_dir.display()))?;
+    let stem = input
+        .file_stem()
+        .context("input file has no name")?
+        .to_string_lossy();
+    let output_path = output_dir.join(format!("{stem}.{}", settings.format.extension()));
+    #[cfg(feature = "loop-db")]
+    if export_args.confirm_loop {
+        if let Some(loop_points) = loop_points {
+            confirm_loop(&data, loop_points);
+            #[cfg(feature = "fingerprint")]
+            confirm_fingerprint(&data, loop_points);
+        }
+    }
+    export::export(&data, loop_points, &settings, &output_path).map_err(classify_export_error)?;
+    if export_args.audiosprite {
+        write_audiosprite_json(&output_path, &data, loop_points, &settings)?;
+    }
+    if export_args.reaper_markers || export_args.ardour_markers {
+        // analyze_for_export() doesn't return fade-out info (only
+        // loop_points/confidence), so detect it again here; this only
+        // applies when loop_points came from detection, not --import-loop.
+        let fade_out_start_frame = if import_loop.is_none() {
+            let settings = analysis_args.clone().into_settings(data.sample_rate)?;
+            analysis::detect_loop(&data, &settings).fade_out.map(|fade_out| fade_out.start_frame)
+        } else {
+            None
+        };
+        if export_args.reaper_markers {
+            auto_abloop::daw::write_reaper_regions(
+                &output_path.with_extension("reaper-regions.csv"),
+                loop_points,
+                fade_out_start_frame,
+                data.sample_rate,
+            )?;
+        }
+        if export_args.ardour_markers {
+            auto_abloop::daw::write_ardour_locations(
+                &output_path.with_extension("ardour-locations.xml"),
+                loop_points,
+                fade_out_start_frame,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a Howler.js-style `{urls, sprite}` descriptor for the single
+/// sprite `output_path`'s export produced, named after its file stem.
+fn write_audiosprite_json(
+    output_path: &Path,
+    data: &audio::AudioData,
+    loop_points: Option<audio::LoopPoints>,
+    settings: &export::ExportSettings,
+) -> Result<()> {
+    let (frames, loop_points) = export::rendered_shape(data, loop_points, settings);
+    let duration_ms = frames as f64 * 1000.0 / data.sample_rate as f64;
+    let name = output_path
+        .file_stem()
+        .context("export output has no name")?
+        .to_string_lossy()
+        .into_owned();
+    let url = output_path
+        .file_name()
+        .context("export output has no name")?
+        .to_string_lossy()
+        .into_owned();
+    let descriptor = serde_json::json!({
+        "urls": [url],
+        "sprite": { name: [0.0, duration_ms, loop_points.is_some()] },
+    });
+    let json_path = output_path.with_extension("json");
+    std::fs::write(&json_path, serde_json::to_string_pretty(&descriptor)?)
+        .with_context(|| format!("writing {}", json_path.display()))
+}
+
+/// `export --list`: resolve the input files/globs and print each one's
+/// probed codec/duration/sample rate, without analyzing or exporting
+/// anything - a quick look at what a batch run would process.
+fn list_queue(inputs: &[String]) -> Result<()> {
+    let files = expand_inputs(inputs)?;
+    for file in &files {
+        match audio::probe(file) {
+            Ok(info) => println!(
+                "{}: {} Hz, {} channel(s), {}{}",
+                file.display(),
+                info.sample_rate,
+                info.channels,
+                info.codec,
+                info.duration_secs.map(|secs| format!(", {secs:.1}s")).unwrap_or_default(),
+            ),
+            Err(err) => println!("{}: {err}", file.display()),
+        }
+    }
+    Ok(())
+}
+
+/// `export`'s batch-level flags, grouped so `export_batch()` doesn't take
+/// them all separately.
+struct ExportBatchOptions {
+    output: Option<PathBuf>,
+    parallel: bool,
+    report: Option<PathBuf>,
+    mmap: bool,
+    import_loop: Option<PathBuf>,
+}
+
+fn export_batch(
+    inputs: &[String],
+    options: ExportBatchOptions,
+    quiet: bool,
+    lang: Lang,
+    analysis_args: AnalysisArgs,
+    export_args: ExportArgs,
+) -> Result<()> {
+    let ExportBatchOptions { output, parallel, report, mmap, import_loop } = options;
+    if output.as_deref() == Some(Path::new("-")) {
+        if export_args.audiosprite {
+            bail!("--audiosprite requires a file output, not stdout (`-o -`)");
+        }
+        let files = expand_inputs(inputs)?;
+        let [input] = files.as_slice() else {
+            bail!("exporting to stdout (`-o -`) requires exactly one input file");
+        };
+        let (data, loop_points, _confidence) =
+            analyze_for_export(input, &analysis_args, mmap, import_loop.as_deref())?;
+        let settings = export_args.into_settings(data.sample_rate);
+        return export::export_to_writer(&data, loop_points, &settings, std::io::stdout().lock())
+            .map_err(classify_export_error);
+    }
+
+    let files = expand_inputs(inputs)?;
+    if files.is_empty() {
+        log::info!("{}", i18n::no_input_files_matched(lang));
+        return Ok(());
+    }
+    let template = &config::defaults().output_dir_template;
+
+    let bar = progress_bar(i18n::exporting(lang), quiet);
+    bar.set_length(files.len() as u64);
+
+    let run = |input: &PathBuf| {
+        let output_dir = resolve_output_dir(output.as_deref(), template, input);
+        let outcome = ExportOutcome {
+            input: input.clone(),
+            result: export_one(input, &output_dir, &analysis_args, &export_args, mmap, import_loop.as_deref()),
+        };
+        bar.inc(1);
+        outcome
+    };
+    let outcomes: Vec<ExportOutcome> = if parallel {
+        files.par_iter().map(run).collect()
+    } else {
+        files.iter().map(run).collect()
+    };
+    bar.finish_and_clear();
+
+    if let Some(report_path) = &report {
+        let rows: Vec<report::ReportRow> = files
+            .iter()
+            .filter_map(|file| {
+                let options = audio::LoadOptions { mmap, ..Default::default() };
+                let data = audio::load_audio_from_path_with_progress(file, options, |_| {})
+                    .map_err(classify_load_error)
+                    .ok()?;
+                let settings = analysis_args.clone().into_settings(data.sample_rate).ok()?;
+                Some(report::ReportRow::new(file.clone(), &data, &settings))
+            })
+            .collect();
+        if let Err(err) = report::write_report(report_path, &rows) {
+            log::warn!("failed to write report to {}: {err}", report_path.display());
+        }
+    }
+
+    let mut failures = 0;
+    for outcome in &outcomes {
+        let input = outcome.input.display().to_string();
+        match &outcome.result {
+            Ok(()) => log::info!("{}", i18n::export_ok(lang, &input)),
+            Err(err) => {
+                failures += 1;
+                log::warn!("{}", i18n::export_fail(lang, &input, err));
+            }
+        }
+    }
+    log::info!(
+        "{}",
+        i18n::export_summary(lang, outcomes.len() - failures, failures, outcomes.len())
+    );
+
+    if failures > 0 {
+        return Err(CliError {
+            kind: CliErrorKind::ExportError,
+            message: format!("{failures} of {} exports failed", outcomes.len()),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// A single queued-up background analysis, one track ahead of whatever is
+/// currently playing - the bounded worker pool is just "one", since
+/// there's never a point decoding further ahead than the track that's
+/// about to play next. [`Prefetch::cancel`] drops the result instead of
+/// sending it, for when the queue moves on before the analysis finishes.
+#[cfg(feature = "playback")]
+struct Prefetch {
+    cancelled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    result: std::sync::mpsc::Receiver<Result<(audio::AudioData, Option<audio::LoopPoints>, Option<f32>)>>,
+}
+
+#[cfg(feature = "playback")]
+impl Prefetch {
+    fn spawn(path: PathBuf, analysis_args: AnalysisArgs) -> Self {
+        let cancelled = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let worker_cancelled = std::sync::Arc::clone(&cancelled);
+        std::thread::spawn(move || {
+            let outcome = analyze_for_export(&path, &analysis_args, false, None);
+            if !worker_cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+                let _ = tx.send(outcome);
+            }
+        });
+        Prefetch { cancelled, result: rx }
+    }
+
+    fn cancel(self) {
+        self.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Block until the background analysis finishes and take its result.
+    /// In practice this rarely waits: by the time one track finishes
+    /// playing, the next has usually long since finished analyzing.
+    fn join(self) -> Result<(audio::AudioData, Option<audio::LoopPoints>, Option<f32>)> {
+        self.result.recv().context("background track analysis thread panicked")?
+    }
+}
+
+/// Queue up the file after `index` for background analysis, if there is
+/// one.
+#[cfg(feature = "playback")]
+fn prefetch_next(files: &[PathBuf], index: usize, analysis_args: &AnalysisArgs) -> Option<Prefetch> {
+    files.get(index + 1).map(|next| Prefetch::spawn(next.clone(), analysis_args.clone()))
+}
+
+/// Play `files` locally, one after another, looping each the same way
+/// `export` would (detected loop, falling back to any loop already
+/// embedded in the file).
+#[cfg(all(feature = "playback", not(feature = "remote")))]
+#[allow(clippy::too_many_arguments)]
+fn play(
+    files: &[PathBuf],
+    tui: bool,
+    seek: Option<f64>,
+    karaoke: bool,
+    normalize: bool,
+    low_latency: bool,
+    metronome: bool,
+    crossfeed: f32,
+    loop_count: Option<u64>,
+    record: Option<PathBuf>,
+    min_confidence: Option<f32>,
+    on_low_confidence: LowConfidenceAction,
+    lang: Lang,
+    analysis_args: AnalysisArgs,
+) -> Result<()> {
+    let mut current = analyze_for_export(&files[0], &analysis_args, false, None)?;
+    let mut prefetch = prefetch_next(files, 0, &analysis_args);
+
+    for (index, file) in files.iter().enumerate() {
+        let (data, loop_points, confidence) = current;
+        let (loop_points, warning) =
+            gate_low_confidence(&data, loop_points, confidence, min_confidence, on_low_confidence);
+        if let Some(warning) = &warning {
+            log::warn!("{}: {warning}", file.display());
+        }
+        let start_frame = if index == 0 { (seek.unwrap_or(0.0) * data.sample_rate as f64).max(0.0) as u64 } else { 0 };
+        log::info!("{}", i18n::now_playing(lang, &file.display().to_string()));
+
+        let played = if tui {
+            tui::run(
+                &data, loop_points, start_frame, karaoke, normalize, low_latency, metronome, crossfeed, loop_count,
+                record.clone(), warning,
+            )
+        } else {
+            (|| {
+                let mut player = Player::new(
+                    &data,
+                    loop_points,
+                    start_frame,
+                    PlayerOptions { karaoke, normalize, low_latency, metronome, crossfeed },
+                )?;
+                log::info!("output: {}", player.output_format());
+                player.set_target_loops(loop_count);
+                if let Some(path) = &record {
+                    player.start_recording(path)?;
+                }
+                loop {
+                    player.tick()?;
+                    if player.loop_points().is_none() && player.position_frame() >= player.frame_count() {
+                        player.stop_recording()?;
+                        return Ok(());
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+            })()
+        };
+        if played.is_err() {
+            if let Some(prefetch) = prefetch.take() {
+                prefetch.cancel();
+            }
+        }
+        played?;
+
+        match prefetch.take() {
+            Some(next) => {
+                current = next.join()?;
+                prefetch = prefetch_next(files, index + 1, &analysis_args);
+            }
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+/// Like the `remote`-less [`play`], but additionally serves a remote
+/// control socket on `remote` (if given) so a playback loop shared via
+/// [`Arc<Mutex<Player>>`] can be driven by another process. Not compatible
+/// with `--tui`, which owns its own, unshared [`Player`]; a queue passed
+/// alongside `--tui` is truncated to its first file.
+#[cfg(all(feature = "playback", feature = "remote"))]
+#[allow(clippy::too_many_arguments)]
+fn play(
+    files: &[PathBuf],
+    tui: bool,
+    seek: Option<f64>,
+    karaoke: bool,
+    normalize: bool,
+    low_latency: bool,
+    metronome: bool,
+    crossfeed: f32,
+    loop_count: Option<u64>,
+    record: Option<PathBuf>,
+    min_confidence: Option<f32>,
+    on_low_confidence: LowConfidenceAction,
+    remote: Option<String>,
+    lang: Lang,
+    analysis_args: AnalysisArgs,
+) -> Result<()> {
+    let (data, loop_points, confidence) = analyze_for_export(&files[0], &analysis_args, false, None)?;
+    let (loop_points, warning) =
+        gate_low_confidence(&data, loop_points, confidence, min_confidence, on_low_confidence);
+    if let Some(warning) = &warning {
+        log::warn!("{}: {warning}", files[0].display());
+    }
+    let start_frame = (seek.unwrap_or(0.0) * data.sample_rate as f64).max(0.0) as u64;
+
+    if tui {
+        return tui::run(
+            &data, loop_points, start_frame, karaoke, normalize, low_latency, metronome, crossfeed, loop_count,
+            record, warning,
+        );
+    }
+
+    let mut initial_player = Player::new(
+        &data,
+        loop_points,
+        start_frame,
+        PlayerOptions { karaoke, normalize, low_latency, metronome, crossfeed },
+    )?;
+    log::info!("output: {}", initial_player.output_format());
+    initial_player.set_target_loops(loop_count);
+    if let Some(path) = &record {
+        initial_player.start_recording(path)?;
+    }
+    let player = std::sync::Arc::new(std::sync::Mutex::new(initial_player));
+    if let Some(addr) = remote {
+        let player = std::sync::Arc::clone(&player);
+        std::thread::spawn(move || {
+            if let Err(err) = auto_abloop::remote::serve(&addr, player) {
+                log::error!("remote control server stopped: {err}");
+            }
+        });
+    }
+
+    let mut prefetch = prefetch_next(files, 0, &analysis_args);
+    for (index, file) in files.iter().enumerate() {
+        log::info!("{}", i18n::now_playing(lang, &file.display().to_string()));
+        let played = (|| loop {
+            let mut guard = player.lock().unwrap_or_else(|err| err.into_inner());
+            guard.tick()?;
+            let done = guard.loop_points().is_none() && guard.position_frame() >= guard.frame_count();
+            drop(guard);
+            if done {
+                return Ok(());
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        })();
+        if played.is_err() {
+            if let Some(prefetch) = prefetch.take() {
+                prefetch.cancel();
+            }
+        }
+        played?;
+
+        match prefetch.take() {
+            Some(next) => {
+                let (data, loop_points, confidence) = next.join()?;
+                let (loop_points, warning) =
+                    gate_low_confidence(&data, loop_points, confidence, min_confidence, on_low_confidence);
+                if let Some(warning) = &warning {
+                    log::warn!("{}: {warning}", files[index + 1].display());
+                }
+                let mut guard = player.lock().unwrap_or_else(|err| err.into_inner());
+                guard.load(&data, loop_points, 0)?;
+                drop(guard);
+                prefetch = prefetch_next(files, index + 1, &analysis_args);
+            }
+            None => break,
+        }
+    }
+    player.lock().unwrap_or_else(|err| err.into_inner()).stop_recording()?;
+    Ok(())
+}
+
+/// One track's playable buffer for `--album` mode: its intro once, then its
+/// detected loop region repeated `repeats` times - the same intro/loop
+/// split `export::render_to_duration` uses, just repeat-count-based instead
+/// of duration-based. Tracks with no loop point just play through once.
+#[cfg(feature = "playback")]
+fn album_track_samples(data: &audio::AudioData, loop_points: Option<audio::LoopPoints>, repeats: u32) -> Vec<f32> {
+    let channels = data.channels as usize;
+    match loop_points {
+        Some(loop_points) if repeats > 0 => {
+            let intro_end = loop_points.start_frame as usize * channels;
+            let loop_region = &data.samples[intro_end..loop_points.end_frame as usize * channels];
+            let mut out = data.samples[..intro_end].to_vec();
+            for _ in 0..repeats {
+                out.extend_from_slice(loop_region);
+            }
+            out
+        }
+        _ => data.samples.to_vec(),
+    }
+}
+
+/// Play `files` as an endless background-music mix (`--album`): every file
+/// is decoded and analyzed up front, since crossfading across tracks needs
+/// each one's full buffer anyway, then cycled forever, wrapping back to the
+/// first file after the last. All tracks must share a sample rate and
+/// channel count, since the crossfaded overlap mixes their raw samples
+/// together directly.
+#[cfg(feature = "playback")]
+fn play_album(
+    files: &[PathBuf],
+    tui: bool,
+    loop_repeats: u32,
+    crossfade_seconds: f64,
+    lang: Lang,
+    analysis_args: &AnalysisArgs,
+) -> Result<()> {
+    if tui {
+        bail!("--album is not compatible with --tui");
+    }
+
+    let tracks: Vec<(audio::AudioData, Option<audio::LoopPoints>, Option<f32>)> = files
+        .iter()
+        .map(|file| analyze_for_export(file, analysis_args, false, None))
+        .collect::<Result<_>>()?;
+
+    let sample_rate = tracks[0].0.sample_rate;
+    let channels = tracks[0].0.channels;
+    for (file, (data, _, _)) in files.iter().zip(&tracks) {
+        if data.sample_rate != sample_rate || data.channels != channels {
+            bail!(
+                "--album requires every track to share a sample rate and channel count \
+                 ({} is {} Hz/{}ch, expected {} Hz/{}ch)",
+                file.display(),
+                data.sample_rate,
+                data.channels,
+                sample_rate,
+                channels
+            );
+        }
+    }
+
+    let (_stream, stream_handle) =
+        rodio::OutputStream::try_default().context("opening the default audio output device")?;
+    let sink = rodio::Sink::try_new(&stream_handle).context("creating an audio output sink")?;
+
+    let channels = channels as usize;
+    let crossfade_frames = (crossfade_seconds * sample_rate as f64).max(0.0) as usize;
+
+    let mut carry_tail: Vec<f32> = Vec::new();
+    let mut index = 0usize;
+    loop {
+        // Keep at most one track queued ahead of what's currently playing,
+        // so an endless mix doesn't decode and enqueue faster than it plays.
+        while sink.len() > 1 {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+
+        let (data, loop_points, _) = &tracks[index];
+        log::info!("{}", i18n::now_playing(lang, &files[index].display().to_string()));
+        let mut samples = album_track_samples(data, *loop_points, loop_repeats);
+
+        let fade_frames = crossfade_frames.min(samples.len() / channels / 2);
+        let fade_len = fade_frames * channels;
+        let tail = samples.split_off(samples.len() - fade_len);
+        let head = samples.drain(..fade_len).collect::<Vec<_>>();
+
+        if carry_tail.is_empty() {
+            sink.append(rodio::buffer::SamplesBuffer::new(channels as u16, sample_rate, head));
+        } else {
+            let blended = export::blend_frames(&carry_tail, &head, channels, fade_frames);
+            sink.append(rodio::buffer::SamplesBuffer::new(channels as u16, sample_rate, blended));
+        }
+        sink.append(rodio::buffer::SamplesBuffer::new(channels as u16, sample_rate, samples));
+        carry_tail = tail;
+
+        index = (index + 1) % tracks.len();
+    }
+}
+
+/// Write a fresh `config.toml` template to `path`, or the platform config
+/// directory if not given.
+fn config_init(path: Option<PathBuf>, force: bool, lang: Lang) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => config::config_path()
+            .context("could not determine a config directory on this platform")?,
+    };
+    if path.exists() && !force {
+        bail!("{} already exists (use --force to overwrite)", path.display());
+    }
+    config::init(&path)?;
+    println!("{}", i18n::wrote_file(lang, &path.display().to_string()));
+    Ok(())
+}
@@ -0,0 +1,783 @@
+//! Real-time audio output for the interactive playback modes (`play` and
+//! `--tui`), with play/pause/seek and loop-region cycling built on `rodio`.
+
+use std::path::Path;
+use crate::sample_cache::SampleStorage;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::cpal::SampleRate;
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+
+use crate::audio::{AudioData, LoopPoints};
+use crate::loudness::{self, Loudness, LoudnessMeter};
+use crate::record::Recorder;
+use crate::spectrum::SpectrumAnalyzer;
+
+/// Target loudness for `--normalize`, in LUFS - loud enough to sit
+/// comfortably next to modern masters without the peaky limiting a
+/// "loudness war" target like -14 would need.
+const TARGET_LUFS: f32 = -16.0;
+
+/// The output device's negotiated sample rate and format for the track
+/// currently playing, as picked by [`open_output_stream`].
+#[derive(Debug, Clone, Copy)]
+pub struct OutputFormat {
+    pub sample_rate: u32,
+    pub sample_format: rodio::cpal::SampleFormat,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} Hz {}", self.sample_rate, self.sample_format)
+    }
+}
+
+/// Open the default output device, preferring a config at `sample_rate`
+/// and `channels` - the track's own, decoded values - over the device's
+/// default so playback doesn't get silently resampled by the backend on
+/// every block. Falls back to the device's default config if nothing
+/// supports that exact combination.
+///
+/// If `low_latency` is set, and more than one matching config is
+/// available, the one with the smallest reported buffer-size range is
+/// preferred as a proxy for "the backend can run this device with less
+/// buffering". This is the only latency lever `cpal`'s portable API
+/// exposes through `rodio`: it has no way to request an actual fixed
+/// buffer size (`SupportedStreamConfig::config` always comes back with
+/// `BufferSize::Default`), and WASAPI/CoreAudio/ALSA exclusive mode are
+/// backend-specific extensions it doesn't surface at all.
+fn open_output_stream(
+    sample_rate: u32,
+    channels: u16,
+    low_latency: bool,
+) -> Result<(OutputStream, OutputStreamHandle, OutputFormat)> {
+    let device = rodio::cpal::default_host()
+        .default_output_device()
+        .context("opening the default audio output device")?;
+    let mut candidates: Vec<_> = device
+        .supported_output_configs()
+        .map(|configs| {
+            configs
+                .filter(|range| {
+                    range.channels() == channels
+                        && range.min_sample_rate().0 <= sample_rate
+                        && sample_rate <= range.max_sample_rate().0
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if low_latency {
+        candidates.sort_by_key(|range| buffer_size_hint(range.buffer_size()));
+    }
+    let config = candidates
+        .into_iter()
+        .next()
+        .map(|range| range.with_sample_rate(SampleRate(sample_rate)))
+        .or_else(|| device.default_output_config().ok())
+        .context("the output device has no usable configuration")?;
+
+    let format = OutputFormat {
+        sample_rate: config.sample_rate().0,
+        sample_format: config.sample_format(),
+    };
+    let (stream, handle) = OutputStream::try_from_device_config(&device, config)
+        .context("opening the default audio output device")?;
+    Ok((stream, handle, format))
+}
+
+/// The largest buffer size (in frames) a config's range admits, or `u32::MAX`
+/// if the device doesn't report one - used to rank configs smallest-first
+/// for `--low-latency`.
+fn buffer_size_hint(buffer_size: &rodio::cpal::SupportedBufferSize) -> u32 {
+    match buffer_size {
+        rodio::cpal::SupportedBufferSize::Range { max, .. } => *max,
+        rodio::cpal::SupportedBufferSize::Unknown => u32::MAX,
+    }
+}
+
+/// A `rodio::Source` over an already-decoded, interleaved f32 buffer.
+/// Shares the buffer (cheaply, via [`SampleStorage`]) with the [`Player`]
+/// so seeking never re-decodes or copies the track.
+#[derive(Clone)]
+struct SampleSource {
+    samples: SampleStorage,
+    channels: u16,
+    sample_rate: u32,
+    position: usize,
+}
+
+impl SampleSource {
+    fn at_frame(samples: SampleStorage, channels: u16, sample_rate: u32, frame: u64) -> Self {
+        let position = (frame as usize * channels as usize).min(samples.len());
+        Self {
+            samples,
+            channels,
+            sample_rate,
+            position,
+        }
+    }
+}
+
+impl Iterator for SampleSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = *self.samples.get(self.position)?;
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for SampleSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len() - self.position)
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(frame_to_duration(
+            self.samples.len() as u64 / self.channels as u64,
+            self.sample_rate,
+        ))
+    }
+}
+
+fn frame_to_duration(frame: u64, sample_rate: u32) -> Duration {
+    Duration::from_secs_f64(frame as f64 / sample_rate as f64)
+}
+
+fn duration_to_frame(duration: Duration, sample_rate: u32) -> u64 {
+    (duration.as_secs_f64() * sample_rate as f64) as u64
+}
+
+/// A real-time audio output device, abstracted behind the handful of
+/// operations [`Player`] actually needs from one: queue a source and play
+/// it, pause/resume, adjust volume, report elapsed playback time, and
+/// recover from the device disappearing. [`Player`] is written entirely
+/// against this trait so it never names `rodio` types directly, and a new
+/// backend can be dropped in without touching playback/seeking/loop logic.
+///
+/// [`RodioOutput`] is the only implementation in this tree today. A `cpal`
+/// backend would mean reimplementing the mixing/resampling/format-matching
+/// `rodio` already does for us on top of it - not worth it unless `rodio`
+/// itself becomes the problem. A Web Audio backend is real future work for
+/// the `js-api` target, but this repo has no JS/wasm build infrastructure
+/// yet (see `wasm_api`), so there's nothing to wire it into; the trait
+/// boundary is drawn here so that infrastructure, whenever it arrives, has
+/// somewhere to plug in without another pass over `Player`.
+trait AudioOutput {
+    /// Drop whatever is queued and start playing `source` from its current
+    /// position, preserving the paused/playing state.
+    fn play_source(&mut self, source: SampleSource) -> Result<()>;
+    fn is_paused(&self) -> bool;
+    fn play(&self);
+    fn pause(&self);
+    fn volume(&self) -> f32;
+    fn set_volume(&self, volume: f32);
+    /// Elapsed playback time since the most recent [`AudioOutput::play_source`] call.
+    fn position(&self) -> Duration;
+    /// The negotiated sample rate/format for the device opened by this
+    /// output; see [`open_output_stream`].
+    fn format(&self) -> OutputFormat;
+    /// Re-open on the current default device (picking up a newly plugged-in
+    /// one if the previous default disappeared), preferring a config that
+    /// matches `sample_rate`/`channels`; see [`open_output_stream`].
+    fn rebuild(&mut self, sample_rate: u32, channels: u16, low_latency: bool) -> Result<()>;
+}
+
+/// The default [`AudioOutput`]: a `rodio` output stream and sink.
+struct RodioOutput {
+    _stream: OutputStream,
+    stream_handle: OutputStreamHandle,
+    sink: Sink,
+    format: OutputFormat,
+}
+
+impl RodioOutput {
+    fn open(sample_rate: u32, channels: u16, low_latency: bool) -> Result<Self> {
+        let (stream, stream_handle, format) = open_output_stream(sample_rate, channels, low_latency)?;
+        let sink = Sink::try_new(&stream_handle).context("creating an audio output sink")?;
+        Ok(Self {
+            _stream: stream,
+            stream_handle,
+            sink,
+            format,
+        })
+    }
+}
+
+impl AudioOutput for RodioOutput {
+    fn play_source(&mut self, source: SampleSource) -> Result<()> {
+        let was_paused = self.sink.is_paused();
+        let sink = Sink::try_new(&self.stream_handle).context("creating an audio output sink")?;
+        sink.append(source);
+        if was_paused {
+            sink.pause();
+        }
+        self.sink = sink;
+        Ok(())
+    }
+
+    fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn play(&self) {
+        self.sink.play();
+    }
+
+    fn pause(&self) {
+        self.sink.pause();
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.sink.set_volume(volume);
+    }
+
+    fn position(&self) -> Duration {
+        self.sink.get_pos()
+    }
+
+    fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    fn rebuild(&mut self, sample_rate: u32, channels: u16, low_latency: bool) -> Result<()> {
+        let (stream, stream_handle, format) = open_output_stream(sample_rate, channels, low_latency)?;
+        self._stream = stream;
+        self.stream_handle = stream_handle;
+        self.format = format;
+        Ok(())
+    }
+}
+
+/// `audio`'s samples, run through [`remove_center_channel`] first if
+/// `karaoke` is set, then [`normalize_to_target_loudness`] if `normalize`
+/// is set, then [`mix_metronome`] if `metronome` is set and a tempo can be
+/// estimated, then [`apply_crossfeed`] if `crossfeed` is above `0.0`;
+/// shared with `audio` as-is if none apply.
+fn track_samples(
+    audio: &AudioData,
+    karaoke: bool,
+    normalize: bool,
+    metronome: bool,
+    crossfeed: f32,
+) -> SampleStorage {
+    if !karaoke && !normalize && !metronome && crossfeed <= 0.0 {
+        return audio.samples.clone();
+    }
+    let mut samples = if karaoke {
+        remove_center_channel(&audio.samples, audio.channels)
+    } else {
+        audio.samples.to_vec()
+    };
+    if normalize {
+        samples = normalize_to_target_loudness(&samples, audio.channels, audio.sample_rate);
+    }
+    if metronome {
+        if let Some(bpm) = crate::analysis::estimate_bpm(audio) {
+            mix_metronome(&mut samples, audio.channels, audio.sample_rate, bpm);
+        }
+    }
+    if crossfeed > 0.0 {
+        apply_crossfeed(&mut samples, audio.channels, crossfeed);
+    }
+    samples.into()
+}
+
+/// Blend a fraction of each channel into the other, the classic headphone
+/// crossfeed trick: speakers give each ear a bit of the opposite channel
+/// for free (rather than the hard isolation headphones give each ear),
+/// which is what makes a track mixed for speakers feel fatiguing on
+/// headphones over a long session. `intensity` (`0.0`-`1.0`) is the
+/// fraction of the opposite channel mixed in, scaling the own channel down
+/// to match so the overall level doesn't change. A no-op for anything
+/// other than 2-channel audio, since crossfeed is inherently a stereo
+/// effect.
+fn apply_crossfeed(samples: &mut [f32], channels: u16, intensity: f32) {
+    if channels != 2 {
+        return;
+    }
+    let intensity = intensity.clamp(0.0, 1.0);
+    for frame in samples.chunks_exact_mut(2) {
+        let (left, right) = (frame[0], frame[1]);
+        frame[0] = left * (1.0 - intensity) + right * intensity;
+        frame[1] = right * (1.0 - intensity) + left * intensity;
+    }
+}
+
+/// Scale `samples` by the static linear gain that brings their measured
+/// integrated loudness to [`TARGET_LUFS`], computed once up front rather
+/// than adjusted live, so looping a mixed playlist of old game rips and
+/// modern masters plays back at consistent volume. A buffer too short or
+/// too quiet to measure (`integrated` is `-inf`) is left untouched.
+fn normalize_to_target_loudness(samples: &[f32], channels: u16, sample_rate: u32) -> Vec<f32> {
+    let integrated = loudness::measure_integrated_lufs(samples, channels as usize, sample_rate);
+    if !integrated.is_finite() {
+        return samples.to_vec();
+    }
+    let gain = 10f32.powf((TARGET_LUFS - integrated) / 20.0);
+    samples.iter().map(|&sample| sample * gain).collect()
+}
+
+/// Peak amplitude of each metronome click mixed into playback - loud
+/// enough to cut through the track without clipping a full-scale signal
+/// once added on top.
+const METRONOME_CLICK_AMPLITUDE: f32 = 0.3;
+/// How long each click tone rings before its envelope reaches zero.
+const METRONOME_CLICK_SECONDS: f64 = 0.03;
+/// Frequency of the click tone, in Hz.
+const METRONOME_CLICK_HZ: f32 = 1_000.0;
+
+/// Mix a short decaying click at every beat of `bpm`'s grid (starting from
+/// frame 0) into `samples` in place, so a loop point landing off-beat is
+/// audible at a glance rather than something you have to count bars to
+/// notice. `channels`/`sample_rate` describe `samples`' interleaved layout.
+fn mix_metronome(samples: &mut [f32], channels: u16, sample_rate: u32, bpm: f32) {
+    let beat_period_frames = crate::analysis::beat_period_frames(bpm, sample_rate);
+    if beat_period_frames == 0 {
+        return;
+    }
+    let click_frames = ((METRONOME_CLICK_SECONDS * sample_rate as f64) as u64).max(1);
+    let frame_count = samples.len() as u64 / channels as u64;
+
+    let mut beat_frame = 0u64;
+    while beat_frame < frame_count {
+        let ring_frames = click_frames.min(frame_count - beat_frame);
+        for offset in 0..ring_frames {
+            let envelope = METRONOME_CLICK_AMPLITUDE * (1.0 - offset as f32 / click_frames as f32);
+            let tone = (2.0 * std::f32::consts::PI * METRONOME_CLICK_HZ * offset as f32 / sample_rate as f32).sin();
+            let frame_index = (beat_frame + offset) as usize;
+            for channel in 0..channels as usize {
+                let index = frame_index * channels as usize + channel;
+                samples[index] = (samples[index] + envelope * tone).clamp(-1.0, 1.0);
+            }
+        }
+        beat_frame += beat_period_frames;
+    }
+}
+
+/// Zero out the shared (center-panned) content of stereo audio via
+/// mid-side decomposition, leaving just the stereo difference - a classic
+/// "karaoke" trick for practicing over instrumentals whose vocal is mixed
+/// dead center. A no-op for anything other than 2-channel audio.
+fn remove_center_channel(samples: &[f32], channels: u16) -> Vec<f32> {
+    if channels != 2 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(2)
+        .flat_map(|frame| {
+            let side = (frame[0] - frame[1]) / 2.0;
+            [side, -side]
+        })
+        .collect()
+}
+
+/// How long playback can go without its position advancing before
+/// [`Player::tick`] treats it as the output device having disappeared
+/// rather than just a slow system under load.
+const STALL_TIMEOUT: Duration = Duration::from_millis(750);
+
+/// A transient playback-health event raised by [`Player::tick`], meant to
+/// be shown as a one-shot status toast; see [`Player::take_device_event`].
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+    /// Playback stopped advancing - most likely the output device
+    /// disappeared (headphones unplugged, a Bluetooth connection dropped) -
+    /// and rebuilding onto the current default device got it going again.
+    Reconnected,
+    /// The rebuild failed; playback is stopped until the next stall check
+    /// (e.g. the device comes back and a later hot-plug attempt succeeds).
+    Disconnected(String),
+}
+
+/// Playback feature toggles for [`Player::new`], grouped into one struct
+/// instead of a growing list of positional bools - see
+/// [`crate::export::ExportSettings`] for the same pattern. Defaults to
+/// everything off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerOptions {
+    /// Remove the center channel on load; see [`remove_center_channel`].
+    pub karaoke: bool,
+    /// Gain loaded tracks to [`TARGET_LUFS`]; see
+    /// [`normalize_to_target_loudness`].
+    pub normalize: bool,
+    /// Prefer an output device config with a smaller buffer-size range; see
+    /// [`open_output_stream`].
+    pub low_latency: bool,
+    /// Mix in a click track at the estimated beat grid; see
+    /// [`mix_metronome`].
+    pub metronome: bool,
+    /// Headphone crossfeed intensity, `0.0` for off; see
+    /// [`apply_crossfeed`].
+    pub crossfeed: f32,
+}
+
+impl Default for PlayerOptions {
+    fn default() -> Self {
+        Self { karaoke: false, normalize: false, low_latency: false, metronome: false, crossfeed: 0.0 }
+    }
+}
+
+/// A play/pause/seek-able handle onto one decoded track, which cycles its
+/// `loop_points` forever once reached via [`Player::tick`].
+pub struct Player {
+    output: Box<dyn AudioOutput>,
+    samples: SampleStorage,
+    channels: u16,
+    sample_rate: u32,
+    loop_points: Option<LoopPoints>,
+    loop_count: u64,
+    /// Frame the current sink was last seeked to; `Sink::get_pos` measures
+    /// elapsed time since then, not an absolute track position.
+    base_frame: u64,
+    meter: LoudnessMeter,
+    spectrum: SpectrumAnalyzer,
+    /// Frame up to which `meter`/`spectrum` have already seen samples, so
+    /// [`Player::tick`] only feeds them what's newly been played.
+    metered_frame: u64,
+    /// Whether loaded tracks have their center channel removed; see
+    /// [`remove_center_channel`]. Remembered so [`Player::load`] keeps
+    /// applying it to later tracks in the queue without being told again.
+    karaoke: bool,
+    /// Whether loaded tracks are gained to [`TARGET_LUFS`]; see
+    /// [`normalize_to_target_loudness`]. Remembered for the same reason as
+    /// `karaoke`.
+    normalize: bool,
+    /// Whether [`open_output_stream`] prefers a device config with a
+    /// smaller buffer-size range. Remembered for the same reason as
+    /// `karaoke`/`normalize`, and reused by
+    /// [`Player::rebuild_on_default_device`].
+    low_latency: bool,
+    /// Whether loaded tracks have a click track mixed in at the estimated
+    /// beat grid; see [`mix_metronome`]. Remembered for the same reason as
+    /// `karaoke`/`normalize`.
+    metronome: bool,
+    /// Headphone crossfeed intensity applied to loaded tracks, `0.0` for
+    /// off; see [`apply_crossfeed`]. Remembered for the same reason as
+    /// `karaoke`/`normalize`/`metronome`.
+    crossfeed: f32,
+    /// Position last seen by [`Player::check_device_health`], to detect
+    /// playback silently stalling (e.g. the output device disappearing).
+    last_observed_frame: u64,
+    /// When the position was first seen stuck at `last_observed_frame`;
+    /// cleared as soon as it advances again.
+    stalled_since: Option<Instant>,
+    /// A hot-plug status change waiting to be shown once, via
+    /// [`Player::take_device_event`].
+    device_event: Option<DeviceEvent>,
+    /// How many times to cycle `loop_points` before letting playback run
+    /// past the end instead of looping again; `None` loops forever. See
+    /// [`Player::set_target_loops`].
+    target_loops: Option<u64>,
+    /// A streaming Ogg Vorbis capture of whatever's played since
+    /// [`Player::start_recording`], fed by [`Player::feed_meter`] the same
+    /// chunks it hands the loudness meter and spectrum analyzer.
+    recorder: Option<Recorder>,
+}
+
+impl Player {
+    /// Open the default audio output device - preferring a config that
+    /// matches `audio`'s own sample rate and channel count, see
+    /// [`open_output_stream`] - and start playing from `start_frame`. See
+    /// [`PlayerOptions`] for what each toggle in `options` does.
+    pub fn new(
+        audio: &AudioData,
+        loop_points: Option<LoopPoints>,
+        start_frame: u64,
+        options: PlayerOptions,
+    ) -> Result<Self> {
+        let output = RodioOutput::open(audio.sample_rate, audio.channels, options.low_latency)?;
+
+        let mut player = Self {
+            output: Box::new(output),
+            samples: track_samples(
+                audio,
+                options.karaoke,
+                options.normalize,
+                options.metronome,
+                options.crossfeed,
+            ),
+            channels: audio.channels,
+            sample_rate: audio.sample_rate,
+            loop_points,
+            loop_count: 0,
+            base_frame: 0,
+            meter: LoudnessMeter::new(audio.channels as usize, audio.sample_rate),
+            spectrum: SpectrumAnalyzer::new(),
+            metered_frame: 0,
+            karaoke: options.karaoke,
+            normalize: options.normalize,
+            low_latency: options.low_latency,
+            metronome: options.metronome,
+            crossfeed: options.crossfeed,
+            last_observed_frame: 0,
+            stalled_since: None,
+            device_event: None,
+            target_loops: None,
+            recorder: None,
+        };
+        player.seek_to_frame(start_frame)?;
+        Ok(player)
+    }
+
+    /// The output device's negotiated sample rate/format for the current
+    /// track, for display (e.g. the TUI status line).
+    pub fn output_format(&self) -> OutputFormat {
+        self.output.format()
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.samples.len() as u64 / self.channels as u64
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn loop_points(&self) -> Option<LoopPoints> {
+        self.loop_points
+    }
+
+    pub fn loop_count(&self) -> u64 {
+        self.loop_count
+    }
+
+    /// Limit `loop_points` to cycling `target_loops` times (`None` for
+    /// unlimited, the default): once reached, [`Player::tick`] lets
+    /// playback run past the loop's end instead of seeking back.
+    pub fn set_target_loops(&mut self, target_loops: Option<u64>) {
+        self.target_loops = target_loops;
+    }
+
+    pub fn target_loops(&self) -> Option<u64> {
+        self.target_loops
+    }
+
+    /// Time left until `target_loops` is reached, if both a loop region
+    /// and a target are set and it hasn't already been reached.
+    pub fn remaining_loop_duration(&self) -> Option<Duration> {
+        let loop_points = self.loop_points?;
+        let target = self.target_loops?;
+        if self.loop_count >= target {
+            return Some(Duration::ZERO);
+        }
+        let loop_length = loop_points.end_frame.saturating_sub(loop_points.start_frame);
+        let remaining_in_current = loop_points.end_frame.saturating_sub(self.position_frame());
+        let remaining_loops_after = target - self.loop_count - 1;
+        let remaining_frames = remaining_in_current + remaining_loops_after * loop_length;
+        Some(frame_to_duration(remaining_frames, self.sample_rate))
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.output.is_paused()
+    }
+
+    pub fn play(&self) {
+        self.output.play();
+    }
+
+    pub fn pause(&self) {
+        self.output.pause();
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.output.volume()
+    }
+
+    /// `volume` above `1.0` amplifies past the track's original level and
+    /// can clip a signal that was already close to full scale; we can't
+    /// inspect what the output's mixer does with it, so just warn once per
+    /// call rather than trying to analyze the output stream.
+    pub fn set_volume(&self, volume: f32) {
+        if volume > 1.0 {
+            log::warn!("volume {volume:.2} is above unity and may clip the output");
+        }
+        self.output.set_volume(volume);
+    }
+
+    /// Replace the active loop region (or clear it, with `None`); takes
+    /// effect on the next [`Player::tick`].
+    pub fn set_loop_points(&mut self, loop_points: Option<LoopPoints>) {
+        self.loop_points = loop_points;
+    }
+
+    /// Stop the current track and start playing `audio` from
+    /// `start_frame` instead, reusing this player's output device.
+    pub fn load(&mut self, audio: &AudioData, loop_points: Option<LoopPoints>, start_frame: u64) -> Result<()> {
+        self.samples = track_samples(audio, self.karaoke, self.normalize, self.metronome, self.crossfeed);
+        self.channels = audio.channels;
+        self.sample_rate = audio.sample_rate;
+        self.loop_points = loop_points;
+        self.loop_count = 0;
+        self.meter = LoudnessMeter::new(audio.channels as usize, audio.sample_rate);
+        self.spectrum = SpectrumAnalyzer::new();
+        self.seek_to_frame(start_frame)
+    }
+
+    /// Momentary/short-term/integrated loudness measured from the audio
+    /// played so far, in LUFS.
+    pub fn loudness(&self) -> Loudness {
+        self.meter.current()
+    }
+
+    /// `0..=100`-scaled magnitude of `buckets` frequency bands, from the
+    /// most recently played audio.
+    pub fn spectrum(&self, buckets: usize) -> Vec<u64> {
+        self.spectrum.spectrum(buckets)
+    }
+
+    /// Start streaming whatever plays from now on (across loops and, via
+    /// [`Player::load`], later tracks) to a new Ogg Vorbis file at `path`;
+    /// see [`crate::record`]. Finishes a capture already in progress first.
+    pub fn start_recording(&mut self, path: &Path) -> Result<()> {
+        self.stop_recording()?;
+        self.recorder = Some(Recorder::create(path, self.sample_rate, self.channels)?);
+        Ok(())
+    }
+
+    /// Finish and close the current capture, if one is in progress.
+    pub fn stop_recording(&mut self) -> Result<()> {
+        if let Some(recorder) = self.recorder.take() {
+            recorder.finish()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn toggle_pause(&self) {
+        if self.output.is_paused() {
+            self.output.play();
+        } else {
+            self.output.pause();
+        }
+    }
+
+    /// Current playback position, in frames.
+    pub fn position_frame(&self) -> u64 {
+        self.base_frame + duration_to_frame(self.output.position(), self.sample_rate)
+    }
+
+    /// Jump to `frame`, preserving the paused/playing state.
+    pub fn seek_to_frame(&mut self, frame: u64) -> Result<()> {
+        let frame = frame.min(self.frame_count());
+        self.output.play_source(SampleSource::at_frame(
+            self.samples.clone(),
+            self.channels,
+            self.sample_rate,
+            frame,
+        ))?;
+        self.base_frame = frame;
+        self.metered_frame = frame;
+        self.last_observed_frame = frame;
+        self.stalled_since = None;
+        Ok(())
+    }
+
+    /// Seek forward (positive) or backward (negative) by `seconds`.
+    pub fn seek_relative(&mut self, seconds: f64) -> Result<()> {
+        let current = self.position_frame() as i64;
+        let delta = (seconds * self.sample_rate as f64) as i64;
+        let target = (current + delta).max(0) as u64;
+        self.seek_to_frame(target)
+    }
+
+    /// Advance the loop: if playback has reached the loop's end, jump back
+    /// to its start and count it. Call this regularly (e.g. once per UI
+    /// tick); it's a no-op when there's no loop or the end hasn't been
+    /// reached yet.
+    pub fn tick(&mut self) -> Result<()> {
+        self.check_device_health();
+        self.feed_meter()?;
+        let Some(loop_points) = self.loop_points else {
+            return Ok(());
+        };
+        if !self.output.is_paused() && self.position_frame() >= loop_points.end_frame {
+            self.loop_count += 1;
+            if self.target_loops.is_some_and(|target| self.loop_count >= target) {
+                self.loop_points = None;
+            } else {
+                self.seek_to_frame(loop_points.start_frame)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the most recent hot-plug status change, if any, clearing it so
+    /// it's only reported once - mirrors the one-shot drain pattern used
+    /// elsewhere for UI-facing events (e.g. `gui::AbloopApp::poll_load_state`).
+    pub fn take_device_event(&mut self) -> Option<DeviceEvent> {
+        self.device_event.take()
+    }
+
+    /// Notice when playback has stopped advancing despite not being paused
+    /// (and not having legitimately run off the end of a non-looping
+    /// track), and try to recover by reopening the default output device.
+    /// `rodio`/`cpal` don't surface a disconnect event directly, so this is
+    /// the only way to detect it: a real stall starves [`AudioOutput::position`]
+    /// of new samples just as thoroughly as a device physically disappearing.
+    fn check_device_health(&mut self) {
+        if self.output.is_paused() {
+            self.stalled_since = None;
+            return;
+        }
+        let current = self.position_frame();
+        let at_end = self.loop_points.is_none() && current >= self.frame_count();
+        if at_end || current != self.last_observed_frame {
+            self.last_observed_frame = current;
+            self.stalled_since = None;
+            return;
+        }
+        let stalled_since = *self.stalled_since.get_or_insert_with(Instant::now);
+        if stalled_since.elapsed() < STALL_TIMEOUT {
+            return;
+        }
+        self.stalled_since = None;
+        self.device_event = Some(match self.rebuild_on_default_device(current) {
+            Ok(()) => DeviceEvent::Reconnected,
+            Err(err) => DeviceEvent::Disconnected(err.to_string()),
+        });
+    }
+
+    /// Re-open the default output device (picking up a newly plugged-in one
+    /// if the previous default disappeared) and resume from `frame`,
+    /// preserving pause state and the active loop region.
+    fn rebuild_on_default_device(&mut self, frame: u64) -> Result<()> {
+        self.output.rebuild(self.sample_rate, self.channels, self.low_latency)?;
+        self.seek_to_frame(frame)
+    }
+
+    /// Feed the loudness meter, spectrum analyzer, and any in-progress
+    /// recording whatever's been played since the last tick.
+    fn feed_meter(&mut self) -> Result<()> {
+        let current = self.position_frame().min(self.frame_count());
+        if current > self.metered_frame {
+            let start = self.metered_frame as usize * self.channels as usize;
+            let end = current as usize * self.channels as usize;
+            self.meter.feed(&self.samples[start..end]);
+            self.spectrum.feed(&self.samples[start..end], self.channels as usize);
+            if let Some(recorder) = &mut self.recorder {
+                recorder.write_interleaved(&self.samples[start..end])?;
+            }
+            self.metered_frame = current;
+        }
+        Ok(())
+    }
+}
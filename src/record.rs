@@ -0,0 +1,69 @@
+//! Streaming to-disk capture of whatever [`crate::player::Player`] is
+//! rendering, so "record a couple hours of this loop" doesn't mean
+//! pre-rendering a couple hours of samples into RAM first: each chunk
+//! [`crate::player::Player::tick`] plays is handed to [`Recorder::write_interleaved`]
+//! and encoded immediately, so peak memory stays bounded by one chunk
+//! regardless of how long the capture runs.
+//!
+//! Only Ogg Vorbis is implemented. A FLAC option was also asked for, but
+//! `flacenc` (the only pure-Rust FLAC encoder available here) is built
+//! around encoding a fully-buffered, known-length source into an in-memory
+//! `ByteSink` so it can finalize `STREAMINFO` up front - the opposite of
+//! the bounded, unknown-length streaming this feature is for - so it's
+//! left out rather than forced into a shape it isn't designed for.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::num::{NonZeroU32, NonZeroU8};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoder, VorbisEncoderBuilder};
+
+/// Vorbis quality target (`-1.0`-`1.0`) used for recordings; `0.6` sits
+/// around 160-192 kbps for typical program material, comfortably
+/// transparent for a practice/reference recording without the file size
+/// of a maximum-quality encode over a multi-hour capture.
+const RECORD_QUALITY: f32 = 0.6;
+
+/// An in-progress Ogg Vorbis capture, fed one played chunk at a time.
+pub struct Recorder {
+    encoder: VorbisEncoder<BufWriter<File>>,
+    channels: u16,
+}
+
+impl Recorder {
+    /// Start encoding to a new Ogg Vorbis file at `path`, for audio at
+    /// `sample_rate`/`channels`.
+    pub fn create(path: &Path, sample_rate: u32, channels: u16) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        let sampling_frequency =
+            NonZeroU32::new(sample_rate).context("sample rate must be nonzero")?;
+        let channel_count = NonZeroU8::new(channels as u8).context("channel count must be nonzero")?;
+        let encoder = VorbisEncoderBuilder::new(sampling_frequency, channel_count, BufWriter::new(file))
+            .context("initializing the Vorbis encoder")?
+            .bitrate_management_strategy(VorbisBitrateManagementStrategy::QualityVbr { target_quality: RECORD_QUALITY })
+            .build()
+            .context("building the Vorbis encoder")?;
+        Ok(Self { encoder, channels })
+    }
+
+    /// Encode `samples` (interleaved, in this recorder's channel layout) as
+    /// the next block of the capture.
+    pub fn write_interleaved(&mut self, samples: &[f32]) -> Result<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+        let channel_buffers: Vec<Vec<f32>> = (0..self.channels as usize)
+            .map(|channel| samples.iter().skip(channel).step_by(self.channels as usize).copied().collect())
+            .collect();
+        self.encoder.encode_audio_block(&channel_buffers).context("encoding a block of recorded audio")?;
+        Ok(())
+    }
+
+    /// Flush and close the Ogg Vorbis stream.
+    pub fn finish(self) -> Result<()> {
+        self.encoder.finish().context("finishing the Vorbis stream")?;
+        Ok(())
+    }
+}
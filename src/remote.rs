@@ -0,0 +1,143 @@
+//! A line-delimited JSON TCP control surface for [`crate::player::Player`],
+//! for installations that drive looped ambience from lighting or show
+//! software instead of a keyboard. Build with `--features remote` and pass
+//! `--remote <addr>` to `play`.
+//!
+//! Each connection accepts one [`Command`] per line as JSON (e.g.
+//! `{"cmd":"seek","frame":48000}`) and writes back one JSON reply line -
+//! `{"ok":true}`, or `{"ok":false,"error":"..."}` on failure.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{self, AnalysisSettings};
+use crate::audio;
+use crate::player::Player;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Command {
+    /// Load a new file, replacing whatever is currently playing. The loop
+    /// point is re-detected with default [`AnalysisSettings`], falling
+    /// back to one already embedded in the file.
+    Load { path: PathBuf },
+    Play,
+    Pause,
+    /// Jump to an absolute frame.
+    Seek { frame: u64 },
+    /// Replace the active loop region; omit both fields to clear it.
+    SetLoop {
+        start_frame: Option<u64>,
+        end_frame: Option<u64>,
+    },
+    SetVolume { volume: f32 },
+}
+
+#[derive(Debug, Serialize)]
+struct Reply {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Reply {
+    fn ok() -> Self {
+        Reply { ok: true, error: None }
+    }
+
+    fn err(message: impl ToString) -> Self {
+        Reply { ok: false, error: Some(message.to_string()) }
+    }
+}
+
+/// Run the control server, handling connections until the process is
+/// killed. `player` is shared with the caller's playback loop, which keeps
+/// calling [`Player::tick`] independently of commands arriving here.
+pub fn serve(addr: &str, player: Arc<Mutex<Player>>) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    log::info!("remote control listening on {addr}");
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let player = Arc::clone(&player);
+        std::thread::spawn(move || handle_connection(stream, &player));
+    }
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, player: &Arc<Mutex<Player>>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(err) => {
+            log::warn!("remote control: {err}");
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                log::warn!("remote control: {err}");
+                return;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Command>(&line) {
+            Ok(command) => apply(command, player),
+            Err(err) => Reply::err(err),
+        };
+        let Ok(mut body) = serde_json::to_vec(&reply) else {
+            continue;
+        };
+        body.push(b'\n');
+        if writer.write_all(&body).is_err() {
+            return;
+        }
+    }
+}
+
+fn apply(command: Command, player: &Arc<Mutex<Player>>) -> Reply {
+    let Ok(mut player) = player.lock() else {
+        return Reply::err("player lock poisoned");
+    };
+    let result = match command {
+        Command::Load { path } => load(&mut player, &path),
+        Command::Play => {
+            player.play();
+            Ok(())
+        }
+        Command::Pause => {
+            player.pause();
+            Ok(())
+        }
+        Command::Seek { frame } => player.seek_to_frame(frame),
+        Command::SetLoop { start_frame, end_frame } => {
+            player.set_loop_points(start_frame.zip(end_frame).map(|(start_frame, end_frame)| {
+                audio::LoopPoints { start_frame, end_frame }
+            }));
+            Ok(())
+        }
+        Command::SetVolume { volume } => {
+            player.set_volume(volume);
+            Ok(())
+        }
+    };
+    match result {
+        Ok(()) => Reply::ok(),
+        Err(err) => Reply::err(err),
+    }
+}
+
+fn load(player: &mut Player, path: &std::path::Path) -> anyhow::Result<()> {
+    let data = audio::load_audio_from_path(path)?;
+    let loop_points = analysis::detect_loop(&data, &AnalysisSettings::default())
+        .loop_points
+        .map(|candidate| audio::LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame })
+        .or(data.loop_points);
+    player.load(&data, loop_points, 0)
+}
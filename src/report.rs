@@ -0,0 +1,160 @@
+//! Batch analysis reports: a CSV or HTML summary of detected loop points
+//! across many files (or just one, from the GUI), for reviewing a whole
+//! soundtrack at a glance instead of rereading `analyze` output file by
+//! file.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::analysis::{self, AnalysisSettings};
+use crate::audio::{AudioData, LoopPoints};
+use crate::error::{Context, Result};
+
+/// One row of a report: what [`analysis::detect_loop`] found for a file,
+/// independently of any loop database or fingerprint override an actual
+/// export might use instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRow {
+    pub file: PathBuf,
+    pub duration_secs: f64,
+    pub start_frame: Option<u64>,
+    pub end_frame: Option<u64>,
+    pub confidence: Option<f32>,
+    /// [`analysis::seam_cost_at`] for the detected loop point, or `None`
+    /// when no loop was found.
+    pub seam_cost: Option<f32>,
+    pub fade_out_start_frame: Option<u64>,
+    /// Why `start_frame`/`end_frame` are `None`, or
+    /// [`analysis::LoopDetectionOutcome::Found`] when they aren't.
+    pub outcome: analysis::LoopDetectionOutcome,
+}
+
+impl ReportRow {
+    /// Run detection on `data` (already decoded from `file`) with
+    /// `settings` and summarize the result.
+    pub fn new(file: impl Into<PathBuf>, data: &AudioData, settings: &AnalysisSettings) -> Self {
+        let result = analysis::detect_loop(data, settings);
+        let seam_cost = result.loop_points.map(|candidate| {
+            analysis::seam_cost_at(
+                data,
+                LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame },
+            )
+        });
+        Self {
+            file: file.into(),
+            duration_secs: data.frame_count() as f64 / data.sample_rate as f64,
+            start_frame: result.loop_points.map(|c| c.start_frame),
+            end_frame: result.loop_points.map(|c| c.end_frame),
+            confidence: result.loop_points.map(|c| c.confidence),
+            seam_cost,
+            fade_out_start_frame: result.fade_out.map(|f| f.start_frame),
+            outcome: result.outcome,
+        }
+    }
+}
+
+/// Write `rows` to `path`: an HTML table if `path`'s extension is `html`
+/// or `htm`, CSV otherwise.
+pub fn write_report(path: &Path, rows: &[ReportRow]) -> Result<()> {
+    let is_html = matches!(path.extension().and_then(|e| e.to_str()), Some("html") | Some("htm"));
+    let body = if is_html { render_html(rows) } else { render_csv(rows) };
+    std::fs::write(path, body).context(|| format!("writing {}", path.display()))
+}
+
+const COLUMNS: [&str; 9] = [
+    "file",
+    "duration_secs",
+    "loop_found",
+    "start_frame",
+    "end_frame",
+    "confidence",
+    "seam_cost",
+    "fade_out_start_frame",
+    "outcome",
+];
+
+/// `outcome`'s label for the CSV/HTML columns above, matching the
+/// `#[serde(rename_all = "kebab-case")]` spelling `analyze --json` uses for
+/// the same field, so a reader cross-referencing the two doesn't see two
+/// different vocabularies for the same value.
+fn outcome_label(outcome: analysis::LoopDetectionOutcome) -> &'static str {
+    match outcome {
+        analysis::LoopDetectionOutcome::Found => "found",
+        analysis::LoopDetectionOutcome::TooShort => "too-short",
+        analysis::LoopDetectionOutcome::Silent => "silent",
+        analysis::LoopDetectionOutcome::BelowThreshold => "below-threshold",
+        analysis::LoopDetectionOutcome::Cancelled => "cancelled",
+    }
+}
+
+fn render_csv(rows: &[ReportRow]) -> String {
+    let mut out = String::new();
+    out.push_str(&COLUMNS.join(","));
+    out.push('\n');
+    for row in rows {
+        let fields = [
+            csv_field(&row.file.display().to_string()),
+            format!("{:.3}", row.duration_secs),
+            (row.start_frame.is_some()).to_string(),
+            opt_field(row.start_frame),
+            opt_field(row.end_frame),
+            row.confidence.map(|c| format!("{c:.4}")).unwrap_or_default(),
+            row.seam_cost.map(|c| format!("{c:.4}")).unwrap_or_default(),
+            opt_field(row.fade_out_start_frame),
+            outcome_label(row.outcome).to_string(),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn opt_field<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quote `field` per RFC 4180 if it contains a comma, quote, or newline.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn render_html(rows: &[ReportRow]) -> String {
+    let mut out = String::new();
+    out.push_str("<!doctype html>\n<html>\n<head><meta charset=\"utf-8\"><title>auto-abloop batch report</title></head>\n<body>\n<table border=\"1\" cellpadding=\"4\" cellspacing=\"0\">\n<tr>");
+    for column in COLUMNS {
+        out.push_str(&format!("<th>{column}</th>"));
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        out.push_str(&format!("<td>{}</td>", html_escape(&row.file.display().to_string())));
+        out.push_str(&format!("<td>{:.3}</td>", row.duration_secs));
+        out.push_str(&format!("<td>{}</td>", row.start_frame.is_some()));
+        out.push_str(&format!("<td>{}</td>", opt_field(row.start_frame)));
+        out.push_str(&format!("<td>{}</td>", opt_field(row.end_frame)));
+        out.push_str(&format!(
+            "<td>{}</td>",
+            row.confidence.map(|c| format!("{c:.4}")).unwrap_or_default()
+        ));
+        out.push_str(&format!(
+            "<td>{}</td>",
+            row.seam_cost.map(|c| format!("{c:.4}")).unwrap_or_default()
+        ));
+        out.push_str(&format!("<td>{}</td>", opt_field(row.fade_out_start_frame)));
+        out.push_str(&format!("<td>{}</td>", outcome_label(row.outcome)));
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
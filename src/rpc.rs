@@ -0,0 +1,222 @@
+//! A JSON-RPC 2.0 stdio control surface, for embedding auto-abloop as a
+//! child process instead of shelling out to the CLI and parsing its
+//! human-oriented output. Build with `--features playback` and run
+//! `auto-abloop rpc`.
+//!
+//! One request per line on stdin, one response per line on stdout:
+//! `{"jsonrpc":"2.0","id":1,"method":"load","params":{"path":"track.ogg"}}`
+//! gets back `{"jsonrpc":"2.0","id":1,"result":{...}}`, or
+//! `{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"..."}}` on
+//! failure. Supported methods: `load`, `analyze`, `get_result`, `export`,
+//! `play`, `stop`.
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::analysis::{self, AnalysisResult, AnalysisSettings};
+use crate::audio::{self, AudioData, LoopPoints};
+use crate::export::{self, ExportFormat, ExportSettings};
+use crate::player::{Player, PlayerOptions};
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Serialize)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+impl RpcResponse {
+    fn ok(id: Value, result: Value) -> Self {
+        Self { jsonrpc: "2.0", id, result: Some(result), error: None }
+    }
+
+    fn err(id: Value, message: impl ToString) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(RpcError { code: -32000, message: message.to_string() }),
+        }
+    }
+}
+
+/// What `load`/`analyze` leave behind for `get_result`/`export`/`play` to
+/// use: the decoded audio, the loop points currently in effect (from
+/// analysis or the file's own embedded loop), the last analysis result,
+/// and a shared handle to the live player, created on the first `play`.
+#[derive(Default)]
+struct State {
+    data: Option<AudioData>,
+    loop_points: Option<LoopPoints>,
+    result: Option<AnalysisResult>,
+    player: Arc<Mutex<Option<Player>>>,
+}
+
+/// Read one request per line from stdin, dispatch it, and write one
+/// response per line to stdout, until stdin closes. A background thread
+/// ticks the player (once `play` has created one) every 100ms, the same
+/// cadence `play`'s own loop uses, independently of whatever request is
+/// being handled.
+pub fn serve_stdio() -> anyhow::Result<()> {
+    let mut state = State::default();
+    {
+        let player = Arc::clone(&state.player);
+        std::thread::spawn(move || loop {
+            if let Ok(mut guard) = player.lock() {
+                if let Some(player) = guard.as_mut() {
+                    if let Err(err) = player.tick() {
+                        log::warn!("rpc: playback tick failed: {err}");
+                    }
+                }
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        });
+    }
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<RpcRequest>(&line) {
+            Ok(request) => {
+                let id = request.id.clone();
+                match dispatch(&mut state, &request.method, request.params) {
+                    Ok(result) => RpcResponse::ok(id, result),
+                    Err(err) => RpcResponse::err(id, err),
+                }
+            }
+            Err(err) => RpcResponse::err(Value::Null, err),
+        };
+        let mut body = serde_json::to_vec(&response)?;
+        body.push(b'\n');
+        stdout.write_all(&body)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+fn dispatch(state: &mut State, method: &str, params: Value) -> Result<Value, String> {
+    match method {
+        "load" => load(state, params),
+        "analyze" => analyze(state),
+        "get_result" => get_result(state),
+        "export" => export_one(state, params),
+        "play" => play(state),
+        "stop" => stop(state),
+        other => Err(format!("unknown method {other:?}")),
+    }
+}
+
+#[derive(Deserialize)]
+struct LoadParams {
+    path: PathBuf,
+}
+
+fn load(state: &mut State, params: Value) -> Result<Value, String> {
+    let params: LoadParams = serde_json::from_value(params).map_err(|err| err.to_string())?;
+    let data = audio::load_audio_from_path(&params.path).map_err(|err| err.to_string())?;
+    let info = serde_json::json!({
+        "sample_rate": data.sample_rate,
+        "channels": data.channels,
+        "frame_count": data.frame_count(),
+    });
+    state.loop_points = data.loop_points;
+    state.result = None;
+    state.data = Some(data);
+    Ok(info)
+}
+
+/// Run detection with default [`AnalysisSettings`] on the loaded file,
+/// replacing the loop points `export`/`play` use with whatever it found
+/// (falling back to the file's own embedded loop, as `load` already did,
+/// if detection found nothing).
+fn analyze(state: &mut State) -> Result<Value, String> {
+    let data = state.data.as_ref().ok_or("no file loaded; call load first")?;
+    let result = analysis::detect_loop(data, &AnalysisSettings::default());
+    state.loop_points = result
+        .loop_points
+        .map(|candidate| LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame })
+        .or(data.loop_points);
+    let value = serde_json::to_value(&result).map_err(|err| err.to_string())?;
+    state.result = Some(result);
+    Ok(value)
+}
+
+fn get_result(state: &State) -> Result<Value, String> {
+    let result = state.result.as_ref().ok_or("no analysis result yet; call analyze first")?;
+    serde_json::to_value(result).map_err(|err| err.to_string())
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    path: PathBuf,
+    #[serde(default)]
+    format: Option<ExportFormat>,
+    #[serde(default)]
+    bit_depth: Option<u16>,
+}
+
+fn export_one(state: &State, params: Value) -> Result<Value, String> {
+    let data = state.data.as_ref().ok_or("no file loaded; call load first")?;
+    let params: ExportParams = serde_json::from_value(params).map_err(|err| err.to_string())?;
+    let settings = ExportSettings {
+        format: params.format.unwrap_or_default(),
+        bit_depth: params.bit_depth.unwrap_or(16),
+        ..Default::default()
+    };
+    export::export(data, state.loop_points, &settings, &params.path).map_err(|err| err.to_string())?;
+    Ok(serde_json::json!({ "path": params.path }))
+}
+
+/// Start (or resume) playback of the loaded file's current loop points on
+/// the default audio output device, creating the shared [`Player`] on the
+/// first call.
+fn play(state: &mut State) -> Result<Value, String> {
+    let mut guard = state.player.lock().map_err(|_| "player lock poisoned")?;
+    if let Some(player) = guard.as_ref() {
+        player.play();
+        return Ok(Value::Null);
+    }
+    let data = state.data.as_ref().ok_or("no file loaded; call load first")?;
+    let player = Player::new(data, state.loop_points, 0, PlayerOptions::default()).map_err(|err| err.to_string())?;
+    player.play();
+    *guard = Some(player);
+    Ok(Value::Null)
+}
+
+/// Pause playback and rewind to the start, rather than tearing down the
+/// player - a follow-up `play` resumes instantly instead of re-decoding.
+fn stop(state: &State) -> Result<Value, String> {
+    let mut guard = state.player.lock().map_err(|_| "player lock poisoned")?;
+    if let Some(player) = guard.as_mut() {
+        player.pause();
+        player.seek_to_frame(0).map_err(|err| err.to_string())?;
+    }
+    Ok(Value::Null)
+}
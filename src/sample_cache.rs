@@ -0,0 +1,153 @@
+//! Storage for decoded PCM that can transparently spill to a memory-mapped
+//! temp file instead of living on the heap, so a multi-hour recording
+//! doesn't have to fit in RAM twice over (once decoded, once again for
+//! whatever copies playback/export/analysis make along the way). See
+//! [`SampleStorage::new`].
+
+use std::ops::{Deref, Index};
+use std::path::PathBuf;
+use std::slice::SliceIndex;
+use std::sync::Arc;
+
+#[cfg(not(target_family = "wasm"))]
+use std::fs::{self, File};
+#[cfg(not(target_family = "wasm"))]
+use std::io::Write;
+#[cfg(not(target_family = "wasm"))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(not(target_family = "wasm"))]
+use memmap2::Mmap;
+
+use crate::error::Result;
+
+/// Interleaved PCM samples, either held in memory or backed by a
+/// memory-mapped spill file on disk. Both variants are `Arc`-shared, so
+/// cloning a [`SampleStorage`] is always cheap regardless of which one it
+/// is - callers that only read through `&[f32]` (via [`Deref`]) never need
+/// to care which.
+#[derive(Debug, Clone)]
+pub enum SampleStorage {
+    /// The common case: samples live on the heap.
+    Memory(Arc<[f32]>),
+    /// Samples live in a temp file, mapped back in so they still read like
+    /// an ordinary slice; see [`MappedSamples`].
+    #[cfg(not(target_family = "wasm"))]
+    Mapped(Arc<MappedSamples>),
+}
+
+impl SampleStorage {
+    /// Keep `samples` in memory, or - if its footprint exceeds
+    /// `budget_bytes` - spill it to a uniquely-named file under
+    /// [`std::env::temp_dir`] and map that back in instead, so the decoded
+    /// buffer's pages can be evicted and refetched by the OS under memory
+    /// pressure rather than pinned for the life of the process. `budget_bytes`
+    /// of `0` disables spilling (never-disk, matching [`SampleStorage::from`]).
+    ///
+    /// `wasm32-unknown-unknown` has no filesystem to spill to, so there the
+    /// budget is ignored and samples always stay in memory.
+    pub fn new(samples: Vec<f32>, budget_bytes: u64) -> Result<Self> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let footprint = samples.len() as u64 * size_of::<f32>() as u64;
+            if budget_bytes > 0 && footprint > budget_bytes {
+                return Ok(SampleStorage::Mapped(Arc::new(MappedSamples::spill(&samples)?)));
+            }
+        }
+        #[cfg(target_family = "wasm")]
+        let _ = budget_bytes;
+        Ok(SampleStorage::Memory(samples.into()))
+    }
+}
+
+impl From<Vec<f32>> for SampleStorage {
+    fn from(samples: Vec<f32>) -> Self {
+        SampleStorage::Memory(samples.into())
+    }
+}
+
+impl Deref for SampleStorage {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        match self {
+            SampleStorage::Memory(samples) => samples,
+            #[cfg(not(target_family = "wasm"))]
+            SampleStorage::Mapped(mapped) => mapped.samples(),
+        }
+    }
+}
+
+impl<I: SliceIndex<[f32]>> Index<I> for SampleStorage {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &I::Output {
+        Index::index(&**self, index)
+    }
+}
+
+/// A spill file's mapping, kept alive for as long as any [`SampleStorage`]
+/// clone still needs to read through it; the file itself is removed as
+/// soon as it's mapped, so it never outlives the process even on a crash
+/// (the mapping keeps serving reads from the OS page cache regardless).
+#[cfg(not(target_family = "wasm"))]
+pub struct MappedSamples {
+    mmap: Mmap,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl std::fmt::Debug for MappedSamples {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedSamples").field("len", &self.samples().len()).finish()
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl MappedSamples {
+    fn spill(samples: &[f32]) -> Result<Self> {
+        let path = spill_path();
+        let mut file = File::create(&path)?;
+        // Safety of the later `Mmap::map` below relies on nothing else
+        // writing to this path while it's mapped; it's process-unique (see
+        // `spill_path`) and removed immediately after mapping, so nothing
+        // else ever gets the chance to open it.
+        let bytes: &[u8] = bytemuck_cast_slice(samples);
+        file.write_all(bytes)?;
+        file.flush()?;
+        // Safety: relies on the file not being truncated or rewritten
+        // elsewhere for as long as this map is alive, the same caveat
+        // `audio::MappedFile` carries for the encoded-file case. We drop
+        // our own `File` and unlink the path right after mapping, so nothing
+        // in this process can violate that afterwards.
+        let mmap = unsafe { Mmap::map(&file)? };
+        drop(file);
+        let _ = fs::remove_file(&path);
+        Ok(Self { mmap })
+    }
+
+    fn samples(&self) -> &[f32] {
+        // Safety: `spill` only ever writes a whole number of native-endian
+        // `f32`s, and `Mmap::map` guarantees page (hence `f32`) alignment,
+        // so reinterpreting the mapped bytes back as `[f32]` is sound.
+        let bytes = &self.mmap[..];
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast::<f32>(), bytes.len() / size_of::<f32>()) }
+    }
+}
+
+/// A path under [`std::env::temp_dir`] unique to this process and call, so
+/// concurrent loads (or concurrent `auto-abloop` processes) never collide
+/// on the same spill file.
+#[cfg(not(target_family = "wasm"))]
+fn spill_path() -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("auto-abloop-samples-{}-{}.f32", std::process::id(), n))
+}
+
+/// Reinterpret `samples` as raw bytes for writing to the spill file. Hand-rolled
+/// instead of pulling in `bytemuck` for one call site: `f32` has no padding
+/// and no niches, so a byte-for-byte view of the slice is always valid.
+#[cfg(not(target_family = "wasm"))]
+fn bytemuck_cast_slice(samples: &[f32]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(samples.as_ptr().cast::<u8>(), std::mem::size_of_val(samples)) }
+}
@@ -0,0 +1,143 @@
+//! Minimal synchronous HTTP server exposing loop detection/rendering as a
+//! microservice, for sites that want to tag or render looping audio
+//! without embedding the library directly. Build with `--features server`
+//! and run `auto-abloop serve`.
+//!
+//! Routes:
+//! - `POST /analyze` - body is raw audio bytes; responds with the
+//!   detected loop point (and fade-out) as JSON, shaped like
+//!   [`analysis::AnalysisResult`].
+//! - `POST /render` - body is raw audio bytes; responds with a looped WAV,
+//!   the same shape [`export::export_wav`] produces.
+//!
+//! Both routes accept an optional `?ext=<hint>` query parameter, used the
+//! same way as [`audio::load_audio_from_bytes`]'s `ext_hint`, for clients
+//! that can't supply a file extension (e.g. a raw upload stream).
+//!
+//! There is no concurrency here: requests are handled one at a time on the
+//! calling thread, which is plenty for the batch-ish, infrequent uploads
+//! this is meant for. Put a reverse proxy in front of it for anything
+//! higher-traffic.
+
+use std::io::Read;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::analysis::{self, AnalysisSettings};
+use crate::audio;
+use crate::error::{AbloopError, Result};
+use crate::export;
+
+/// Largest request body `handle_analyze`/`handle_render` will buffer into
+/// memory. The module doc says this is meant to sit behind a reverse proxy,
+/// but that's no substitute for a limit of our own - an unbounded POST body
+/// would otherwise OOM-kill the process before the proxy's own limits (if
+/// any) even come into play.
+const MAX_REQUEST_BODY_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Run the HTTP server, handling requests until the process is killed.
+/// Binds to `addr` (e.g. `"127.0.0.1:8080"`).
+pub fn serve(addr: &str) -> Result<()> {
+    let server = Server::http(addr).map_err(|err| AbloopError::Io {
+        context: format!("binding HTTP server to {addr}"),
+        source: err,
+    })?;
+    log::info!("listening on http://{addr}");
+    for request in server.incoming_requests() {
+        let method = request.method().clone();
+        let path = request.url().split('?').next().unwrap_or("").to_string();
+        let ext_hint = query_param(request.url(), "ext");
+        let outcome = match (&method, path.as_str()) {
+            (Method::Post, "/analyze") => handle_analyze(request, ext_hint),
+            (Method::Post, "/render") => handle_render(request, ext_hint),
+            _ => request.respond(text_response(404, "not found")),
+        };
+        if let Err(err) = outcome {
+            log::warn!("failed to write HTTP response: {err}");
+        }
+    }
+    Ok(())
+}
+
+fn handle_analyze(mut request: Request, ext_hint: Option<String>) -> std::io::Result<()> {
+    let bytes = match read_capped_body(&mut request) {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => return request.respond(text_response(413, "request body too large")),
+        Err(err) => return request.respond(text_response(400, &format!("failed to read request body: {err}"))),
+    };
+    match audio::load_audio_from_bytes(bytes, ext_hint.as_deref()) {
+        Ok(data) => {
+            let result = analysis::detect_loop(&data, &AnalysisSettings::default());
+            request.respond(json_response(200, &result))
+        }
+        Err(err) => request.respond(text_response(400, &err.to_string())),
+    }
+}
+
+fn handle_render(mut request: Request, ext_hint: Option<String>) -> std::io::Result<()> {
+    let bytes = match read_capped_body(&mut request) {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => return request.respond(text_response(413, "request body too large")),
+        Err(err) => return request.respond(text_response(400, &format!("failed to read request body: {err}"))),
+    };
+    let data = match audio::load_audio_from_bytes(bytes, ext_hint.as_deref()) {
+        Ok(data) => data,
+        Err(err) => return request.respond(text_response(400, &err.to_string())),
+    };
+    let loop_points = analysis::detect_loop(&data, &AnalysisSettings::default())
+        .loop_points
+        .map(|candidate| audio::LoopPoints { start_frame: candidate.start_frame, end_frame: candidate.end_frame })
+        .or(data.loop_points);
+
+    let mut wav = Vec::new();
+    if let Err(err) = export::export_wav_to_writer(&data, loop_points, &mut wav) {
+        return request.respond(text_response(500, &err.to_string()));
+    }
+    let response = Response::from_data(wav)
+        .with_status_code(200)
+        .with_header(content_type("audio/wav"));
+    request.respond(response)
+}
+
+/// Read `request`'s body, rejecting anything over [`MAX_REQUEST_BODY_BYTES`]
+/// with `Ok(None)` rather than buffering it all just to find out it's too
+/// big. Checks the declared `Content-Length` up front where present, and
+/// also caps the actual read in case the header is missing or understated.
+fn read_capped_body(request: &mut Request) -> std::io::Result<Option<Vec<u8>>> {
+    if let Some(len) = request.body_length() {
+        if len as u64 > MAX_REQUEST_BODY_BYTES {
+            return Ok(None);
+        }
+    }
+    let mut bytes = Vec::new();
+    let read = request.as_reader().take(MAX_REQUEST_BODY_BYTES + 1).read_to_end(&mut bytes)?;
+    if read as u64 > MAX_REQUEST_BODY_BYTES {
+        return Ok(None);
+    }
+    Ok(Some(bytes))
+}
+
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let query = url.split_once('?')?.1;
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then(|| value.to_string())
+    })
+}
+
+fn text_response(status: u16, body: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type("text/plain; charset=utf-8"))
+}
+
+fn json_response(status: u16, body: &impl serde::Serialize) -> Response<std::io::Cursor<Vec<u8>>> {
+    let body = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type("application/json"))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], value.as_bytes()).expect("static header name/value is always valid")
+}
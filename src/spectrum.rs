@@ -0,0 +1,97 @@
+//! Real-time FFT spectrum for the TUI playback visualizer: a ring buffer of
+//! the most recently played samples (downmixed to mono), windowed and
+//! transformed on demand so the display always reflects what's currently
+//! audible instead of the whole track at once.
+
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+
+const FFT_SIZE: usize = 1024;
+
+pub struct SpectrumAnalyzer {
+    fft: Arc<dyn Fft<f32>>,
+    ring: Vec<f32>,
+    write_pos: usize,
+    filled: usize,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new() -> Self {
+        SpectrumAnalyzer {
+            fft: FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            ring: vec![0.0; FFT_SIZE],
+            write_pos: 0,
+            filled: 0,
+        }
+    }
+
+    /// Feed newly played interleaved samples (`channels` per frame),
+    /// downmixed to mono, into the ring buffer.
+    pub fn feed(&mut self, samples: &[f32], channels: usize) {
+        for frame in samples.chunks_exact(channels) {
+            self.ring[self.write_pos] = frame.iter().sum::<f32>() / channels as f32;
+            self.write_pos = (self.write_pos + 1) % FFT_SIZE;
+            self.filled = (self.filled + 1).min(FFT_SIZE);
+        }
+    }
+
+    /// `0..=100`-scaled magnitude of each of `buckets` evenly-sized
+    /// frequency bands, lowest frequency first. All zero until the ring
+    /// buffer has filled once.
+    pub fn spectrum(&self, buckets: usize) -> Vec<u64> {
+        let buckets = buckets.max(1);
+        if self.filled < FFT_SIZE {
+            return vec![0; buckets];
+        }
+
+        let mut windowed: Vec<Complex32> = (0..FFT_SIZE)
+            .map(|i| {
+                let sample = self.ring[(self.write_pos + i) % FFT_SIZE];
+                // Hann window, to keep the block edges from ringing into
+                // neighboring bins.
+                let window = 0.5
+                    - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (FFT_SIZE - 1) as f32).cos();
+                Complex32::new(sample * window, 0.0)
+            })
+            .collect();
+        self.fft.process(&mut windowed);
+
+        let usable_bins = FFT_SIZE / 2;
+        let buckets = buckets.min(usable_bins);
+        let bins_per_bucket = usable_bins / buckets;
+        (0..buckets)
+            .map(|bucket| {
+                let start = bucket * bins_per_bucket;
+                let end = if bucket == buckets - 1 {
+                    usable_bins
+                } else {
+                    start + bins_per_bucket
+                };
+                let peak = windowed[start..end]
+                    .iter()
+                    .map(|bin| bin.norm())
+                    .fold(0.0f32, f32::max);
+                magnitude_to_scaled(peak)
+            })
+            .collect()
+    }
+}
+
+impl Default for SpectrumAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Map an FFT bin magnitude to `0..=100` on a dB scale, clamped to a
+/// -60dB..0dB range relative to full scale - loud enough to see quiet
+/// content without every bin pegging at 100 on a hot master.
+fn magnitude_to_scaled(magnitude: f32) -> u64 {
+    if magnitude <= 0.0 {
+        return 0;
+    }
+    let db = 20.0 * (magnitude / FFT_SIZE as f32).log10();
+    (((db + 60.0) / 60.0).clamp(0.0, 1.0) * 100.0) as u64
+}
@@ -0,0 +1,249 @@
+//! Interactive terminal playback (`--tui`): an ASCII waveform with loop
+//! markers, play/pause/seek keys, and a live loop counter, for servers and
+//! users who don't want the egui window.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+
+use crate::audio::{AudioData, LoopPoints};
+use crate::player::{DeviceEvent, Player, PlayerOptions};
+use crate::waveform::PeakPyramid;
+
+const SEEK_STEP_SECONDS: f64 = 5.0;
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+const WAVEFORM_BUCKETS: usize = 512;
+const SPECTRUM_BUCKETS: usize = 128;
+/// How long a device hot-plug toast stays in the status line before fading
+/// back to the normal playback status.
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Run the TUI until the user quits (`q` or `Esc`). `target_loops` caps
+/// how many times the loop region cycles before playback runs past its
+/// end (see [`Player::set_target_loops`]); `None` loops forever. `record`,
+/// if given, streams whatever plays to that path as Ogg Vorbis for the
+/// duration of this call (see [`Player::start_recording`]). `warning`
+/// (e.g. from `--on-low-confidence`) is shown in the status line as the
+/// initial toast, the same way a device hot-plug event is.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    audio: &AudioData,
+    loop_points: Option<LoopPoints>,
+    start_frame: u64,
+    karaoke: bool,
+    normalize: bool,
+    low_latency: bool,
+    metronome: bool,
+    crossfeed: f32,
+    target_loops: Option<u64>,
+    record: Option<PathBuf>,
+    warning: Option<String>,
+) -> Result<()> {
+    let envelope = waveform_envelope(&PeakPyramid::build(audio, WAVEFORM_BUCKETS));
+    let mut player = Player::new(
+        audio,
+        loop_points,
+        start_frame,
+        PlayerOptions { karaoke, normalize, low_latency, metronome, crossfeed },
+    )?;
+    player.set_target_loops(target_loops);
+    if let Some(path) = &record {
+        player.start_recording(path)?;
+    }
+
+    enable_raw_mode().context("enabling terminal raw mode")?;
+    execute!(std::io::stdout(), EnterAlternateScreen).context("entering alternate screen")?;
+    let mut terminal =
+        Terminal::new(CrosstermBackend::new(std::io::stdout())).context("initializing terminal")?;
+
+    let result = event_loop(&mut terminal, &mut player, &envelope, warning);
+    let stop_result = player.stop_recording();
+
+    disable_raw_mode().ok();
+    execute!(std::io::stdout(), LeaveAlternateScreen).ok();
+
+    result.and(stop_result)
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    player: &mut Player,
+    envelope: &[u64],
+    warning: Option<String>,
+) -> Result<()> {
+    let mut toast: Option<(String, Instant)> = warning.map(|text| (text, Instant::now()));
+    loop {
+        player.tick()?;
+        if let Some(event) = player.take_device_event() {
+            toast = Some((device_toast_text(&event), Instant::now()));
+        }
+        if toast.as_ref().is_some_and(|(_, shown_at)| shown_at.elapsed() >= TOAST_DURATION) {
+            toast = None;
+        }
+        terminal.draw(|frame| draw(frame, player, envelope, toast.as_ref().map(|(text, _)| text.as_str())))?;
+
+        if event::poll(TICK_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') => player.toggle_pause(),
+                    KeyCode::Left => player.seek_relative(-SEEK_STEP_SECONDS)?,
+                    KeyCode::Right => player.seek_relative(SEEK_STEP_SECONDS)?,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Render a [`DeviceEvent`] as the short message shown in the status toast.
+fn device_toast_text(event: &DeviceEvent) -> String {
+    match event {
+        DeviceEvent::Reconnected => "audio device reconnected".to_string(),
+        DeviceEvent::Disconnected(err) => format!("audio device lost: {err}"),
+    }
+}
+
+fn draw(frame: &mut Frame, player: &Player, envelope: &[u64], toast: Option<&str>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(3),
+        ])
+        .split(frame.area());
+
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title("waveform"))
+            .data(envelope)
+            .style(Style::default().fg(Color::Cyan)),
+        chunks[0],
+    );
+
+    frame.render_widget(
+        Paragraph::new(markers_line(player, envelope.len())),
+        chunks[1],
+    );
+
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title("spectrum"))
+            .data(&player.spectrum(SPECTRUM_BUCKETS))
+            .style(Style::default().fg(Color::Magenta)),
+        chunks[2],
+    );
+
+    let loudness = player.loudness();
+    let status = format!(
+        "{} | {} | frame {}/{} | {} | M {} S {} I {} LUFS | [space] pause/play  [<-/->] seek {:.0}s  [q] quit",
+        if player.is_paused() { "paused" } else { "playing" },
+        player.output_format(),
+        player.position_frame(),
+        player.frame_count(),
+        loop_status(player),
+        format_lufs(loudness.momentary),
+        format_lufs(loudness.short_term),
+        format_lufs(loudness.integrated),
+        SEEK_STEP_SECONDS,
+    );
+    let status = match toast {
+        Some(toast) => format!("{toast} | {status}"),
+        None => status,
+    };
+    frame.render_widget(
+        Paragraph::new(status).block(Block::default().borders(Borders::ALL).title("auto-abloop")),
+        chunks[3],
+    );
+}
+
+/// The status line's loop segment: `loops N` while unlimited, or `Loop
+/// N / M - MM:SS remaining` once [`Player::set_target_loops`] gives it a
+/// target to count down to.
+fn loop_status(player: &Player) -> String {
+    let Some(target) = player.target_loops() else {
+        return format!("loops {}", player.loop_count());
+    };
+    let current = (player.loop_count() + 1).min(target);
+    match player.remaining_loop_duration() {
+        Some(remaining) => format!("Loop {current} / {target} - {} remaining", format_duration(remaining)),
+        None => format!("Loop {current} / {target}"),
+    }
+}
+
+/// `MM:SS`, truncating to whole seconds.
+fn format_duration(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    format!("{:02}:{:02}", total_seconds / 60, total_seconds % 60)
+}
+
+/// One line beneath the waveform marking the loop region (`=`, bounded by
+/// `[`/`]`) and the current playhead (`>`).
+fn markers_line(player: &Player, width: usize) -> Line<'static> {
+    let total_frames = player.frame_count().max(1);
+    let frame_at = |bucket: usize| (bucket as u64 * total_frames) / width.max(1) as u64;
+    let playhead_bucket =
+        ((player.position_frame() * width as u64) / total_frames).min(width.saturating_sub(1) as u64) as usize;
+
+    let mut spans = Vec::with_capacity(width);
+    for bucket in 0..width {
+        let frame = frame_at(bucket);
+        let next_frame = frame_at(bucket + 1).max(frame + 1);
+        let ch = if bucket == playhead_bucket {
+            '>'
+        } else if let Some(loop_points) = player.loop_points() {
+            if (frame..next_frame).contains(&loop_points.start_frame) {
+                '['
+            } else if (frame..next_frame).contains(&loop_points.end_frame) {
+                ']'
+            } else if frame >= loop_points.start_frame && frame < loop_points.end_frame {
+                '='
+            } else {
+                '-'
+            }
+        } else {
+            '-'
+        };
+        let color = match ch {
+            '>' => Color::Yellow,
+            '[' | ']' => Color::Green,
+            '=' => Color::DarkGray,
+            _ => Color::Reset,
+        };
+        spans.push(Span::styled(ch.to_string(), Style::default().fg(color)));
+    }
+    Line::from(spans)
+}
+
+/// `-inf` for silence/not-enough-audio-yet, otherwise one decimal place.
+fn format_lufs(lufs: f32) -> String {
+    if lufs.is_finite() {
+        format!("{lufs:.1}")
+    } else {
+        "-inf".to_string()
+    }
+}
+
+/// Flatten a [`PeakPyramid`]'s min/max buckets into `0..=100`-scaled
+/// absolute peaks, for the waveform [`Sparkline`].
+fn waveform_envelope(pyramid: &PeakPyramid) -> Vec<u64> {
+    pyramid
+        .peaks(WAVEFORM_BUCKETS)
+        .iter()
+        .map(|&(min, max)| (min.abs().max(max.abs()).clamp(0.0, 1.0) * 100.0) as u64)
+        .collect()
+}
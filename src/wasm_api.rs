@@ -0,0 +1,205 @@
+//! Plain JS bindings for web music players that want loop detection
+//! without pulling in the full egui web app (see [`crate::gui`]). Build
+//! for `wasm32-unknown-unknown` with `--features js-api`.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::analysis::{self, AnalysisSettings, ChunkedLoopSearch};
+use crate::audio;
+use crate::export;
+
+/// The result of [`analyze_buffer`]: a detected loop region and its
+/// confidence (`0.0` to `1.0`). `found` is `false` when no candidate
+/// cleared the threshold; the other fields are then meaningless.
+#[wasm_bindgen(getter_with_clone)]
+pub struct LoopResult {
+    pub found: bool,
+    #[wasm_bindgen(js_name = loopStart)]
+    pub loop_start: u32,
+    #[wasm_bindgen(js_name = loopEnd)]
+    pub loop_end: u32,
+    pub confidence: f32,
+}
+
+fn loop_result_for(data: &audio::AudioData) -> LoopResult {
+    loop_result_from(analysis::detect_loop(data, &AnalysisSettings::default()))
+}
+
+fn loop_result_from(result: analysis::AnalysisResult) -> LoopResult {
+    match result.loop_points {
+        Some(candidate) => LoopResult {
+            found: true,
+            loop_start: candidate.start_frame as u32,
+            loop_end: candidate.end_frame as u32,
+            confidence: candidate.confidence,
+        },
+        None => LoopResult { found: false, loop_start: 0, loop_end: 0, confidence: 0.0 },
+    }
+}
+
+/// Decode `bytes` and detect its loop point, using default analysis
+/// settings. `ext_hint` (e.g. `"flac"`) helps the format probe when the
+/// buffer has no filename to go by; pass `undefined` if unknown.
+#[wasm_bindgen(js_name = analyzeBuffer)]
+pub fn analyze_buffer(bytes: Vec<u8>, ext_hint: Option<String>) -> Result<LoopResult, JsError> {
+    let data = audio::load_audio_from_bytes(bytes, ext_hint.as_deref())?;
+    Ok(loop_result_for(&data))
+}
+
+/// Fetch `url` with the browser's `fetch` API and detect its loop point,
+/// using default analysis settings - the browser-side equivalent of
+/// [`crate::audio::load_audio_from_url`], for pages that want to turn a
+/// `?src=<url>` deep link into a ready loop point without a round trip
+/// through JS to download the bytes first.
+#[wasm_bindgen(js_name = analyzeUrl)]
+pub async fn analyze_url(url: String) -> Result<LoopResult, JsError> {
+    let bytes = fetch_bytes(&url).await?;
+    let ext_hint = url.rsplit('.').next().filter(|s| !s.contains('/'));
+    let data = audio::load_audio_from_bytes(bytes, ext_hint)?;
+    Ok(loop_result_for(&data))
+}
+
+/// Fetch `url`'s body as bytes via `window.fetch`.
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JsError> {
+    let window = web_sys::window().ok_or_else(|| JsError::new("not running in a browser (no `window`)"))?;
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(|err| JsError::new(&format!("fetching {url}: {}", js_value_to_string(&err))))?;
+    let response: web_sys::Response = response
+        .dyn_into()
+        .map_err(|_| JsError::new("fetch did not resolve to a Response"))?;
+    if !response.ok() {
+        return Err(JsError::new(&format!("fetching {url}: HTTP {}", response.status())));
+    }
+    let buffer = response
+        .array_buffer()
+        .map_err(|err| JsError::new(&format!("reading response body: {}", js_value_to_string(&err))))?;
+    let buffer = JsFuture::from(buffer)
+        .await
+        .map_err(|err| JsError::new(&format!("reading response body: {}", js_value_to_string(&err))))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+fn js_value_to_string(value: &JsValue) -> String {
+    value.as_string().unwrap_or_else(|| format!("{value:?}"))
+}
+
+/// Extract the first file's bytes from a browser `DataTransfer` - what
+/// both paste (`ClipboardEvent.clipboardData`) and drag-and-drop
+/// (`DragEvent.dataTransfer`) events carry a dropped or pasted file in.
+/// Prefers `.files` (populated for a real file drop) and falls back to
+/// scanning `.items` for a `file`-kind entry, since a paste from another
+/// browser tab rather than the OS file picker typically populates only
+/// that.
+async fn bytes_from_data_transfer(data_transfer: &web_sys::DataTransfer) -> Result<Vec<u8>, JsError> {
+    let file = data_transfer.files().and_then(|files| files.get(0)).or_else(|| {
+        let items = data_transfer.items();
+        (0..items.length()).find_map(|i| items.get(i).and_then(|item| item.get_as_file().ok().flatten()))
+    });
+    let file = file.ok_or_else(|| JsError::new("no file found on the clipboard/drag data"))?;
+    let buffer = JsFuture::from(file.array_buffer())
+        .await
+        .map_err(|err| JsError::new(&format!("reading pasted file: {}", js_value_to_string(&err))))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// Detect a loop in whatever file was pasted, from a `paste` event's
+/// `clipboardData` - e.g. an audio file copied in a file manager and
+/// pasted here, or one dragged in from another browser tab, for pages
+/// that want paste support as an alternative to a file dialog (awkward on
+/// some mobile browsers). `ext_hint` helps the format probe the same way
+/// it does for [`analyze_buffer`]; pass `undefined` if unknown.
+#[wasm_bindgen(js_name = analyzeClipboardEvent)]
+pub async fn analyze_clipboard_event(
+    event: web_sys::ClipboardEvent,
+    ext_hint: Option<String>,
+) -> Result<LoopResult, JsError> {
+    let data_transfer =
+        event.clipboard_data().ok_or_else(|| JsError::new("clipboard event has no clipboardData"))?;
+    let bytes = bytes_from_data_transfer(&data_transfer).await?;
+    let data = audio::load_audio_from_bytes(bytes, ext_hint.as_deref())?;
+    Ok(loop_result_for(&data))
+}
+
+/// Detect a loop in whatever file was dropped, from a `drop` event's
+/// `dataTransfer` - the same paste-without-a-dialog use case as
+/// [`analyze_clipboard_event`], for a drag from another browser tab
+/// rather than the OS file picker a plain `<input type="file">` drop zone
+/// already handles.
+#[wasm_bindgen(js_name = analyzeDropEvent)]
+pub async fn analyze_drop_event(event: web_sys::DragEvent, ext_hint: Option<String>) -> Result<LoopResult, JsError> {
+    let data_transfer = event.data_transfer().ok_or_else(|| JsError::new("drag event has no dataTransfer"))?;
+    let bytes = bytes_from_data_transfer(&data_transfer).await?;
+    let data = audio::load_audio_from_bytes(bytes, ext_hint.as_deref())?;
+    Ok(loop_result_for(&data))
+}
+
+/// Decode `bytes`, detect (or reuse an already-known) loop point, and
+/// render a 16-bit PCM WAV with it embedded as a `smpl` chunk loop - the
+/// same shape [`export::export_wav`] produces, as bytes a web player can
+/// feed straight to `decodeAudioData`.
+#[wasm_bindgen(js_name = renderLoop)]
+pub fn render_loop(bytes: Vec<u8>, ext_hint: Option<String>) -> Result<Vec<u8>, JsError> {
+    let data = audio::load_audio_from_bytes(bytes, ext_hint.as_deref())?;
+    let loop_points = analysis::detect_loop(&data, &AnalysisSettings::default())
+        .loop_points
+        .map(|candidate| audio::LoopPoints {
+            start_frame: candidate.start_frame,
+            end_frame: candidate.end_frame,
+        })
+        .or(data.loop_points);
+
+    let mut wav = Vec::new();
+    export::export_wav_to_writer(&data, loop_points, &mut wav)?;
+    Ok(wav)
+}
+
+/// Incrementally drive a loop search from JS so a long track's analysis
+/// doesn't freeze the page for the whole search at once: this crate has no
+/// shared-memory wasm threads to hand the work to, and `std::thread::spawn`
+/// (the backend [`analysis::run_analysis_async`] uses natively) isn't
+/// available on `wasm32-unknown-unknown` either, so there's no real Worker
+/// or background thread to move decoding and analysis onto. Call
+/// [`Self::step`] repeatedly - from `requestAnimationFrame`, or forwarded
+/// from a Web Worker's own message loop if the caller wants the decode off
+/// the main thread too - until [`Self::isFinished`](Self::is_finished), so
+/// the browser gets to paint and handle input between chunks instead of
+/// only before and after one long synchronous call.
+#[wasm_bindgen(js_name = ChunkedAnalysis)]
+pub struct WasmChunkedAnalysis {
+    search: ChunkedLoopSearch,
+}
+
+#[wasm_bindgen(js_class = ChunkedAnalysis)]
+impl WasmChunkedAnalysis {
+    /// Decode `bytes` and set up a search, without scoring any candidates
+    /// yet. Decoding itself still runs synchronously here - it's normally
+    /// far cheaper than the correlation search that follows, which is what
+    /// `step` breaks up.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: Vec<u8>, ext_hint: Option<String>) -> Result<WasmChunkedAnalysis, JsError> {
+        let data = audio::load_audio_from_bytes(bytes, ext_hint.as_deref())?;
+        let search = ChunkedLoopSearch::start(&data, &AnalysisSettings::default());
+        Ok(WasmChunkedAnalysis { search })
+    }
+
+    /// Score up to `max_candidates` more loop-start positions. Returns the
+    /// fraction of the search completed so far (`1.0` once
+    /// [`Self::is_finished`]).
+    pub fn step(&mut self, max_candidates: usize) -> f32 {
+        self.search.step(max_candidates)
+    }
+
+    #[wasm_bindgen(js_name = isFinished)]
+    pub fn is_finished(&self) -> bool {
+        self.search.is_finished()
+    }
+
+    /// Consume the search, returning the loop it found - or, called before
+    /// [`Self::is_finished`], the best candidate seen so far.
+    pub fn result(self) -> LoopResult {
+        loop_result_from(self.search.finish())
+    }
+}
@@ -0,0 +1,165 @@
+//! Multi-resolution min/max waveform peaks, decimated once per decoded
+//! file and shared by every renderer that draws a waveform - the TUI's
+//! [`ratatui::widgets::Sparkline`] and the GUI's waveform minimap both
+//! reuse a [`PeakPyramid`] instead of re-scanning the sample buffer at
+//! their own resolution.
+
+use crate::audio::AudioData;
+
+/// `(min, max)` amplitude across all channels for one equal-width span of
+/// the track.
+pub type Peak = (f32, f32);
+
+/// A mipmap-style stack of peak levels, coarsest (1 bucket) first and
+/// finest (`base_buckets`) last, built once from the full sample buffer so
+/// [`PeakPyramid::peaks`] can serve any requested resolution without
+/// rescanning it.
+pub struct PeakPyramid {
+    levels: Vec<Vec<Peak>>,
+}
+
+impl PeakPyramid {
+    /// Build a pyramid from `audio`, with `base_buckets` buckets at the
+    /// finest level (clamped to at least 1).
+    pub fn build(audio: &AudioData, base_buckets: usize) -> Self {
+        let base = decimate(audio, base_buckets.max(1));
+        let mut levels = vec![base];
+        while levels.last().expect("always at least one level").len() > 1 {
+            let coarser = halve(levels.last().expect("just pushed"));
+            levels.push(coarser);
+        }
+        levels.reverse();
+        PeakPyramid { levels }
+    }
+
+    /// Peaks at (about) `buckets` resolution: the coarsest level with at
+    /// least that many buckets, resampled down to exactly `buckets` if
+    /// it's finer than requested. Never finer than the level the pyramid
+    /// was built with.
+    pub fn peaks(&self, buckets: usize) -> Vec<Peak> {
+        let buckets = buckets.max(1);
+        let level = self
+            .levels
+            .iter()
+            .find(|level| level.len() >= buckets)
+            .unwrap_or_else(|| self.levels.last().expect("always at least one level"));
+        if level.len() == buckets {
+            level.clone()
+        } else {
+            resample(level, buckets)
+        }
+    }
+}
+
+/// Scan `audio` once, producing `buckets` min/max pairs across all
+/// channels. Empty buckets (a track shorter than `buckets` frames) come
+/// out as `(0.0, 0.0)` rather than the unmatched `(INFINITY, -INFINITY)`
+/// a fold over no samples would otherwise leave behind.
+fn decimate(audio: &AudioData, buckets: usize) -> Vec<Peak> {
+    peaks_in_range(audio, 0, audio.frame_count(), buckets)
+}
+
+/// Scan just `start_frame..end_frame` of `audio`, producing `buckets`
+/// min/max pairs - the building block for a zoomed-in waveform view,
+/// where re-decimating the whole track at a finer resolution would be
+/// wasteful. Out-of-range frames are clamped rather than erroring.
+pub fn peaks_in_range(audio: &AudioData, start_frame: u64, end_frame: u64, buckets: usize) -> Vec<Peak> {
+    let frame_count = audio.frame_count();
+    let start_frame = start_frame.min(frame_count);
+    let end_frame = end_frame.clamp(start_frame, frame_count);
+    let channels = audio.channels as usize;
+    let slice = &audio.samples[start_frame as usize * channels..end_frame as usize * channels];
+    peaks_from_samples(slice, audio.channels, buckets)
+}
+
+/// Like [`peaks_in_range`], but scans a flat interleaved sample buffer
+/// directly instead of a full [`AudioData`] - the building block for a
+/// progressive waveform painted from partially-decoded chunks, where
+/// there's no complete `AudioData` yet to hand `peaks_in_range`. See
+/// [`crate::audio::DecodeProgress`].
+pub fn peaks_from_samples(samples: &[f32], channels: u16, buckets: usize) -> Vec<Peak> {
+    let buckets = buckets.max(1);
+    let channels = (channels as usize).max(1);
+    let range_len = (samples.len() / channels).max(1);
+
+    let mut peaks = vec![(f32::INFINITY, f32::NEG_INFINITY); buckets];
+    for (index, frame) in samples.chunks_exact(channels).enumerate() {
+        let bucket = (index * buckets / range_len).min(buckets - 1);
+        for &sample in frame {
+            peaks[bucket].0 = peaks[bucket].0.min(sample);
+            peaks[bucket].1 = peaks[bucket].1.max(sample);
+        }
+    }
+    for peak in &mut peaks {
+        if peak.0 > peak.1 {
+            *peak = (0.0, 0.0);
+        }
+    }
+    peaks
+}
+
+/// Like [`peaks_in_range`], but keeps each channel separate instead of
+/// min/maxing across all of them - for the GUI's per-channel waveform
+/// lanes, where a problem isolated to one channel (a click introduced by a
+/// mono mixdown tool, a panned effect that doesn't loop cleanly) would be
+/// invisible in the combined mix.
+pub fn peaks_in_range_per_channel(audio: &AudioData, start_frame: u64, end_frame: u64, buckets: usize) -> Vec<Vec<Peak>> {
+    let frame_count = audio.frame_count();
+    let start_frame = start_frame.min(frame_count);
+    let end_frame = end_frame.clamp(start_frame, frame_count);
+    let channels = audio.channels as usize;
+    let slice = &audio.samples[start_frame as usize * channels..end_frame as usize * channels];
+    peaks_from_samples_per_channel(slice, audio.channels, buckets)
+}
+
+/// Like [`peaks_from_samples`], but returns one [`Peak`] sequence per
+/// channel instead of combining them into a single lane.
+pub fn peaks_from_samples_per_channel(samples: &[f32], channels: u16, buckets: usize) -> Vec<Vec<Peak>> {
+    let buckets = buckets.max(1);
+    let channel_count = (channels as usize).max(1);
+    let range_len = (samples.len() / channel_count).max(1);
+
+    let mut peaks = vec![vec![(f32::INFINITY, f32::NEG_INFINITY); buckets]; channel_count];
+    for (index, frame) in samples.chunks_exact(channel_count).enumerate() {
+        let bucket = (index * buckets / range_len).min(buckets - 1);
+        for (channel, &sample) in frame.iter().enumerate() {
+            peaks[channel][bucket].0 = peaks[channel][bucket].0.min(sample);
+            peaks[channel][bucket].1 = peaks[channel][bucket].1.max(sample);
+        }
+    }
+    for channel_peaks in &mut peaks {
+        for peak in channel_peaks {
+            if peak.0 > peak.1 {
+                *peak = (0.0, 0.0);
+            }
+        }
+    }
+    peaks
+}
+
+/// Merge adjacent pairs into half as many buckets; an odd trailing bucket
+/// is kept on its own rather than dropped.
+fn halve(peaks: &[Peak]) -> Vec<Peak> {
+    peaks
+        .chunks(2)
+        .map(|pair| {
+            let min = pair.iter().map(|&(min, _)| min).fold(f32::INFINITY, f32::min);
+            let max = pair.iter().map(|&(_, max)| max).fold(f32::NEG_INFINITY, f32::max);
+            (min, max)
+        })
+        .collect()
+}
+
+/// Merge `peaks` down to exactly `buckets` entries (`buckets <= peaks.len()`).
+fn resample(peaks: &[Peak], buckets: usize) -> Vec<Peak> {
+    (0..buckets)
+        .map(|i| {
+            let start = i * peaks.len() / buckets;
+            let end = ((i + 1) * peaks.len() / buckets).max(start + 1).min(peaks.len());
+            let slice = &peaks[start..end];
+            let min = slice.iter().map(|&(min, _)| min).fold(f32::INFINITY, f32::min);
+            let max = slice.iter().map(|&(_, max)| max).fold(f32::NEG_INFINITY, f32::max);
+            (min, max)
+        })
+        .collect()
+}